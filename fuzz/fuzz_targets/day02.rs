@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../src/bin/02.rs"]
+mod day02;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let config = day02::GuideConfig::default();
+        let _ = day02::RoundStrategyGuide::parse(input, &config);
+        let _ = day02::PlanStrategyGuide::parse(input, &config);
+    }
+});
@@ -0,0 +1,468 @@
+/*
+ * Recording of puzzle states as animations.
+ * A `Recorder` draws the `Display` output of a state as a frame and writes it
+ * straight to an asciicast for terminal players or to an animated GIF, so
+ * long runs never hold more than one frame in memory. The first frame fixes
+ * the size of the animation.
+ * Example: `recorder.record(|| &grid)?` in a loop, then `recorder.finish()`.
+ */
+
+use anyhow::{bail, Context};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::mem;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Frames a `Recorder` writes before giving up, unless told otherwise.
+pub const MAX_FRAMES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    /// asciicast v2, played by `asciinema play`
+    Asciicast,
+    Gif,
+}
+
+impl AnimationFormat {
+    /// The format matching the extension of `path`.
+    pub fn from_path(path: &Path) -> anyhow::Result<AnimationFormat> {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        extension
+            .parse()
+            .with_context(|| format!("cannot animate to {}", path.display()))
+    }
+}
+
+impl FromStr for AnimationFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cast" => Ok(AnimationFormat::Asciicast),
+            "gif" => Ok(AnimationFormat::Gif),
+            _ => bail!("unknown animation format {:?}, expected cast or gif", s),
+        }
+    }
+}
+
+/// Where the frames go once the first one has set the size.
+enum Output<W: Write> {
+    Pending(W, AnimationFormat),
+    Asciicast(W),
+    Gif(gif::Encoder<W>),
+    /// Left behind by a failed write
+    Broken,
+}
+
+/// Text snapshots of a changing state, written as frames while they are
+/// recorded.
+pub struct Recorder<W: Write> {
+    output: Output<W>,
+    frame_delay: Duration,
+    every: usize,
+    frame_limit: usize,
+    size: (usize, usize),
+    steps: usize,
+    frames: usize,
+    latest_drawn: bool,
+}
+
+impl Recorder<BufWriter<File>> {
+    /// A recorder writing to `path` in the format given by its extension.
+    pub fn create(path: &Path, frame_delay: Duration) -> anyhow::Result<Self> {
+        let format = AnimationFormat::from_path(path)?;
+        let file =
+            File::create(path).with_context(|| format!("could not write {}", path.display()))?;
+        Ok(Recorder::new(BufWriter::new(file), format, frame_delay))
+    }
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(out: W, format: AnimationFormat, frame_delay: Duration) -> Recorder<W> {
+        Recorder {
+            output: Output::Pending(out, format),
+            frame_delay,
+            every: 1,
+            frame_limit: MAX_FRAMES,
+            size: (1, 1),
+            steps: 0,
+            frames: 0,
+            latest_drawn: false,
+        }
+    }
+
+    /// Only draws every `steps`-th recorded step, 0 counting as 1.
+    pub fn with_sampling(mut self, steps: usize) -> Recorder<W> {
+        self.every = steps.max(1);
+        self
+    }
+
+    /// Fails instead of writing more than `frames` frames.
+    pub fn with_frame_limit(mut self, frames: usize) -> Recorder<W> {
+        self.frame_limit = frames;
+        self
+    }
+
+    /// Frames written so far.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Records the state after a step. `state` is only drawn for the steps
+    /// sampling keeps.
+    pub fn record<S: fmt::Display>(&mut self, state: impl FnOnce() -> S) -> anyhow::Result<()> {
+        self.latest_drawn = self.steps % self.every == 0;
+        self.steps += 1;
+        match self.latest_drawn {
+            true => self.draw(&state().to_string()),
+            false => Ok(()),
+        }
+    }
+
+    /// Records the state after the last step, drawn even if sampling skipped
+    /// that step so the animation ends where the run did.
+    pub fn record_last<S: fmt::Display>(
+        &mut self,
+        state: impl FnOnce() -> S,
+    ) -> anyhow::Result<()> {
+        match self.latest_drawn {
+            true => Ok(()),
+            false => {
+                self.latest_drawn = true;
+                self.draw(&state().to_string())
+            }
+        }
+    }
+
+    /// Draws a frame that is not a step, such as a summary at the end.
+    pub fn record_frame(&mut self, frame: &impl fmt::Display) -> anyhow::Result<()> {
+        self.draw(&frame.to_string())
+    }
+
+    /// Ends the animation and hands back the flushed output.
+    pub fn finish(mut self) -> anyhow::Result<W> {
+        if let Output::Pending(..) = self.output {
+            self.start()?;
+        }
+        let mut out = match mem::replace(&mut self.output, Output::Broken) {
+            Output::Asciicast(out) => out,
+            Output::Gif(encoder) => encoder.into_inner()?,
+            Output::Pending(..) | Output::Broken => bail!("the animation failed earlier"),
+        };
+        out.flush()?;
+        Ok(out)
+    }
+
+    /// Writes the header for frames of `self.size`.
+    fn start(&mut self) -> anyhow::Result<()> {
+        let (columns, rows) = self.size;
+        self.output = match mem::replace(&mut self.output, Output::Broken) {
+            Output::Pending(mut out, AnimationFormat::Asciicast) => {
+                writeln!(
+                    out,
+                    "{{\"version\": 2, \"width\": {columns}, \"height\": {rows}}}"
+                )?;
+                Output::Asciicast(out)
+            }
+            Output::Pending(out, AnimationFormat::Gif) => {
+                let (width, height) = (columns * GIF_CELL, rows * GIF_CELL);
+                let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+                    bail!("frames of {columns}x{rows} characters are too large for a GIF");
+                };
+                let mut encoder = gif::Encoder::new(out, width, height, &GIF_PALETTE.concat())?;
+                encoder.set_repeat(gif::Repeat::Infinite)?;
+                Output::Gif(encoder)
+            }
+            output => output,
+        };
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &str) -> anyhow::Result<()> {
+        if self.frames == self.frame_limit {
+            bail!(
+                "more than {} frames, record fewer steps with --every",
+                self.frame_limit
+            );
+        }
+        let size = frame_size(frame);
+        if let Output::Pending(..) = self.output {
+            self.size = (size.0.max(1), size.1.max(1));
+            self.start()?;
+        }
+        if size.0 > self.size.0 || size.1 > self.size.1 {
+            bail!(
+                "frame {} is {}x{} characters, larger than the {}x{} of the first frame",
+                self.frames,
+                size.0,
+                size.1,
+                self.size.0,
+                self.size.1
+            );
+        }
+        match &mut self.output {
+            Output::Asciicast(out) => {
+                let time = (self.frame_delay * self.frames as u32).as_secs_f64();
+                let output = format!("\x1b[H\x1b[2J{}", frame.replace('\n', "\r\n"));
+                writeln!(out, "[{time:.3}, \"o\", {}]", json_string(&output))?;
+            }
+            Output::Gif(encoder) => {
+                let (width, height) = (self.size.0 * GIF_CELL, self.size.1 * GIF_CELL);
+                let mut pixels = vec![0; width * height];
+                for (row, line) in frame.lines().enumerate() {
+                    for (column, c) in line.chars().enumerate() {
+                        let color = gif_color(c);
+                        for y in row * GIF_CELL..(row + 1) * GIF_CELL {
+                            let start = y * width + column * GIF_CELL;
+                            pixels[start..start + GIF_CELL].fill(color);
+                        }
+                    }
+                }
+                let mut image =
+                    gif::Frame::from_indexed_pixels(width as u16, height as u16, &pixels, None);
+                image.delay = u16::try_from(self.frame_delay.as_millis() / 10).unwrap_or(u16::MAX);
+                encoder.write_frame(&image)?;
+            }
+            Output::Pending(..) | Output::Broken => bail!("the animation failed earlier"),
+        }
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+/// Columns and rows of a text frame.
+fn frame_size(frame: &str) -> (usize, usize) {
+    let columns = frame.lines().map(|line| line.chars().count()).max();
+    (columns.unwrap_or(0), frame.lines().count())
+}
+
+/// The frames of an asciicast written by a `Recorder`, as they were recorded.
+pub fn asciicast_frames(cast: &str) -> anyhow::Result<Vec<String>> {
+    cast.lines()
+        .skip(1)
+        .map(|event| {
+            let output = event
+                .split_once(", \"o\", ")
+                .and_then(|(_, output)| output.strip_suffix(']'))
+                .with_context(|| format!("not an output event: {event}"))?;
+            let output = parse_json_string(output)?;
+            let frame = output
+                .strip_prefix("\x1b[H\x1b[2J")
+                .with_context(|| format!("event does not clear the screen: {event}"))?;
+            Ok(frame.replace("\r\n", "\n"))
+        })
+        .collect()
+}
+
+/// `s` as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// The string a JSON string literal written by `json_string` stands for.
+fn parse_json_string(json: &str) -> anyhow::Result<String> {
+    let inner = json
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .with_context(|| format!("not a JSON string: {json}"))?;
+    let mut s = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            s.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => s.push('"'),
+            Some('\\') => s.push('\\'),
+            Some('n') => s.push('\n'),
+            Some('r') => s.push('\r'),
+            Some('t') => s.push('\t'),
+            Some('u') => {
+                let code: String = chars.by_ref().take(4).collect();
+                let c = u32::from_str_radix(&code, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .with_context(|| format!("bad escape \\u{code} in {json}"))?;
+                s.push(c);
+            }
+            other => bail!("bad escape {:?} in {json}", other),
+        }
+    }
+    Ok(s)
+}
+
+/// Pixels per character side in a GIF.
+const GIF_CELL: usize = 4;
+
+const GIF_PALETTE: [[u8; 3]; 16] = [
+    [0x10, 0x10, 0x18],
+    [0xe8, 0xe8, 0xe8],
+    [0xf0, 0xc8, 0x50],
+    [0xe0, 0x40, 0x40],
+    [0x70, 0x70, 0x80],
+    [0x40, 0xa0, 0xe0],
+    [0x50, 0xc0, 0x60],
+    [0xc0, 0x60, 0xd0],
+    [0xf0, 0x90, 0x40],
+    [0x40, 0xd0, 0xc0],
+    [0xd0, 0xd0, 0x60],
+    [0xa0, 0x70, 0x50],
+    [0x90, 0xb0, 0xf0],
+    [0xf0, 0x80, 0xa0],
+    [0x80, 0xe0, 0x90],
+    [0xb0, 0xb0, 0xb0],
+];
+
+/// Palette index for a character: background for blanks, fixed colours for
+/// walls, sand, falling rocks and borders, and a spread of the rest for any
+/// other marker.
+fn gif_color(c: char) -> u8 {
+    match c {
+        ' ' | '.' => 0,
+        '#' => 1,
+        'o' => 2,
+        '@' => 3,
+        '|' | '-' | '+' => 4,
+        c => 5 + (c as u32 % 11) as u8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gif_recorder() -> Recorder<Vec<u8>> {
+        Recorder::new(Vec::new(), AnimationFormat::Gif, Duration::from_millis(100))
+    }
+
+    fn cast_recorder() -> Recorder<Vec<u8>> {
+        Recorder::new(
+            Vec::new(),
+            AnimationFormat::Asciicast,
+            Duration::from_millis(250),
+        )
+    }
+
+    #[test]
+    fn gif_frames() {
+        let mut recorder = gif_recorder();
+        recorder.record(|| "#.\n.#").unwrap();
+        recorder.record(|| "o").unwrap();
+        let gif = recorder.finish().unwrap();
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(gif.as_slice()).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (8, 8));
+        assert_eq!(
+            decoder.global_palette(),
+            Some(GIF_PALETTE.concat().as_slice())
+        );
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 10);
+            frames.push(frame.buffer.to_vec());
+        }
+        // Every character is a 4x4 block, smaller frames are padded
+        let cells = |rows: [[u8; 2]; 2]| {
+            rows.iter()
+                .flat_map(|row| {
+                    let line = row.iter().flat_map(|c| [*c; 4]).collect::<Vec<_>>();
+                    std::iter::repeat(line).take(4).flatten()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(frames, [cells([[1, 0], [0, 1]]), cells([[2, 0], [0, 0]])]);
+
+        let wide = "#".repeat(20_000);
+        assert!(gif_recorder().record(|| &wide).is_err());
+    }
+
+    #[test]
+    fn asciicast_events() {
+        let mut recorder = cast_recorder();
+        recorder.record(|| "ab\n\"c\"").unwrap();
+        recorder.record(|| "x").unwrap();
+        let cast = String::from_utf8(recorder.finish().unwrap()).unwrap();
+        assert_eq!(
+            cast,
+            concat!(
+                "{\"version\": 2, \"width\": 3, \"height\": 2}\n",
+                "[0.000, \"o\", \"\\u001b[H\\u001b[2Jab\\r\\n\\\"c\\\"\"]\n",
+                "[0.250, \"o\", \"\\u001b[H\\u001b[2Jx\"]\n",
+            )
+        );
+        assert_eq!(asciicast_frames(&cast).unwrap(), ["ab\n\"c\"", "x"]);
+
+        let cast = cast_recorder().finish().unwrap();
+        assert_eq!(cast, b"{\"version\": 2, \"width\": 1, \"height\": 1}\n");
+    }
+
+    #[test]
+    fn sampled_steps() {
+        let mut recorder = cast_recorder().with_sampling(3);
+        let mut drawn = Vec::new();
+        for step in 0..8 {
+            recorder
+                .record(|| {
+                    drawn.push(step);
+                    step
+                })
+                .unwrap();
+        }
+        recorder.record_last(|| 7).unwrap();
+        recorder.record_frame(&"!").unwrap();
+        // Skipped steps are never drawn
+        assert_eq!(drawn, [0, 3, 6]);
+        let cast = String::from_utf8(recorder.finish().unwrap()).unwrap();
+        assert_eq!(asciicast_frames(&cast).unwrap(), ["0", "3", "6", "7", "!"]);
+
+        // The last step is not drawn twice
+        let mut recorder = cast_recorder().with_sampling(2);
+        for step in 0..3 {
+            recorder.record(|| step).unwrap();
+        }
+        recorder.record_last(|| 2).unwrap();
+        assert_eq!(recorder.frames(), 2);
+    }
+
+    #[test]
+    fn frame_limits() {
+        let mut recorder = cast_recorder().with_frame_limit(2);
+        recorder.record(|| "a").unwrap();
+        recorder.record(|| "b").unwrap();
+        assert!(recorder.record(|| "c").is_err());
+        assert_eq!(recorder.frames(), 2);
+
+        let mut recorder = cast_recorder();
+        recorder.record(|| "ab").unwrap();
+        assert!(recorder.record(|| "abc").is_err());
+        assert!(recorder.record(|| "a\nb").is_err());
+    }
+
+    #[test]
+    fn formats_from_paths() {
+        let format = |path: &str| AnimationFormat::from_path(Path::new(path)).ok();
+        assert_eq!(format("rope.cast"), Some(AnimationFormat::Asciicast));
+        assert_eq!(format("out/sand.gif"), Some(AnimationFormat::Gif));
+        assert_eq!(format("sand.png"), None);
+        assert_eq!(format("sand"), None);
+    }
+}
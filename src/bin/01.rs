@@ -1,16 +1,29 @@
+use anyhow::Context;
 use itertools::Itertools;
 use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::io::{BufRead, Lines};
 use std::num::ParseIntError;
+use std::ops::RangeInclusive;
+use std::process;
 use std::str::FromStr;
 
 #[derive(Debug)]
-struct Elf {
+pub struct Elf {
     snacks: Vec<u32>,
 }
 
 impl Elf {
-    fn count_calories(&self) -> u32 {
-        self.snacks.iter().sum()
+    fn count_calories(&self) -> anyhow::Result<u32> {
+        self.snacks
+            .iter()
+            .try_fold(0_u32, |total, snack| total.checked_add(*snack))
+            .context("too many calories")
+    }
+
+    fn count_items(&self) -> usize {
+        self.snacks.len()
     }
 }
 
@@ -28,56 +41,339 @@ impl FromStr for Elf {
     }
 }
 
-type Input = Vec<Elf>;
+pub type Input = Vec<Elf>;
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let parsed_input = parse_input(input);
-    most_calories_carried(&parsed_input)
+pub fn part_one(input: &str) -> anyhow::Result<u32> {
+    let parsed_input = parse_input(input)?;
+    most_calories_carried(&parsed_input)?.context("no elves in input")
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let parsed_input = parse_input(input);
-    top3_calories_carried(&parsed_input)
+pub fn part_two(input: &str) -> anyhow::Result<u32> {
+    let top3 = top_k_calories(input.as_bytes(), 3)?;
+    anyhow::ensure!(!top3.is_empty(), "no elves in input");
+    top3.iter()
+        .try_fold(0_u32, |total, (_, calories)| total.checked_add(*calories))
+        .context("too many calories")
 }
 
-fn parse_input(input: &str) -> Input {
+pub fn parse_input(input: &str) -> anyhow::Result<Input> {
     input
         .split("\n\n")
-        .map(|e| e.parse::<Elf>().unwrap())
+        .enumerate()
+        .map(|(i, e)| {
+            e.parse::<Elf>()
+                .with_context(|| format!("invalid inventory for elf {}", i + 1))
+        })
         .collect()
 }
 
-fn most_calories_carried(input: &Input) -> Option<u32> {
-    input.iter().map(Elf::count_calories).max()
+fn most_calories_carried(input: &Input) -> anyhow::Result<Option<u32>> {
+    let totals = input
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            e.count_calories()
+                .with_context(|| format!("invalid inventory for elf {}", i + 1))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(totals.into_iter().max())
+}
+
+/// Calories carried by each elf, read one line at a time so that inventories
+/// of any size can be processed without holding them in memory.
+/// Every blank line ends an elf, so consecutive blank lines yield elves with
+/// no snacks.
+pub struct CalorieTotals<R> {
+    lines: Lines<R>,
+    elf: usize,
+}
+
+impl<R: BufRead> CalorieTotals<R> {
+    pub fn new(reader: R) -> Self {
+        CalorieTotals {
+            lines: reader.lines(),
+            elf: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for CalorieTotals<R> {
+    type Item = anyhow::Result<u32>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.elf += 1;
+        let mut total = None;
+        for line in self.lines.by_ref() {
+            let snack = match line {
+                Ok(line) if line.is_empty() => return Some(Ok(total.unwrap_or(0))),
+                Ok(line) => line.parse::<u32>().map_err(anyhow::Error::from),
+                Err(e) => Err(e.into()),
+            };
+            let calories = snack.and_then(|snack| {
+                total
+                    .unwrap_or(0_u32)
+                    .checked_add(snack)
+                    .context("too many calories")
+            });
+            match calories {
+                Ok(calories) => total = Some(calories),
+                Err(e) => {
+                    let elf = self.elf;
+                    return Some(Err(e.context(format!("invalid inventory for elf {elf}"))));
+                }
+            }
+        }
+        total.map(Ok)
+    }
+}
+
+/// The `k` elves carrying the most calories as `(index, calories)` pairs,
+/// most calories first and ties in input order. Elves are indexed from 0 and
+/// at most `k` of them are kept in memory.
+pub fn top_k_calories(reader: impl BufRead, k: usize) -> anyhow::Result<Vec<(usize, u32)>> {
+    // Min-heap whose top is the elf to drop first: fewest calories, then
+    // latest in the input
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for (index, calories) in CalorieTotals::new(reader).enumerate() {
+        heap.push(Reverse((calories?, Reverse(index))));
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+    Ok(heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((calories, Reverse(index)))| (index, calories))
+        .collect())
+}
+
+/// Statistics over a parsed inventory. Elves are indexed from 0 and
+/// percentiles use the nearest-rank method.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InventoryReport {
+    /// Item count and total calories of every elf
+    pub elves: Vec<(usize, u32)>,
+    pub median: Option<u32>,
+    pub percentiles: Vec<(u8, u32)>,
+    /// Number of elves in equally wide ranges of total calories
+    pub histogram: Vec<(RangeInclusive<u32>, usize)>,
+    /// Groups of elves carrying the exact same snacks, empty elves excluded
+    pub duplicates: Vec<Vec<usize>>,
+    /// Elves carrying no snacks
+    pub empty: Vec<usize>,
+}
+
+impl InventoryReport {
+    const PERCENTILES: [u8; 5] = [10, 25, 50, 75, 90];
+    const BUCKETS: u32 = 10;
+    const BAR_WIDTH: usize = 40;
+
+    pub fn new(input: &Input) -> anyhow::Result<Self> {
+        let elves = input
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                let calories = e
+                    .count_calories()
+                    .with_context(|| format!("invalid inventory for elf {}", i + 1))?;
+                Ok((e.count_items(), calories))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let totals = elves
+            .iter()
+            .map(|(_, calories)| *calories)
+            .sorted()
+            .collect_vec();
+        let percentile = |p: u8| {
+            let rank = (p as usize * totals.len() + 99) / 100;
+            totals.get(rank.max(1) - 1).copied()
+        };
+        let percentiles = Self::PERCENTILES
+            .iter()
+            .filter_map(|p| Some((*p, percentile(*p)?)))
+            .collect();
+        let histogram = match (totals.first(), totals.last()) {
+            (Some(min), Some(max)) => {
+                let width = (max - min) / Self::BUCKETS + 1;
+                (0..Self::BUCKETS)
+                    .map_while(|bucket| min.checked_add(bucket.checked_mul(width)?))
+                    .take_while(|from| from <= max)
+                    .map(|from| {
+                        let range = from..=from.saturating_add(width - 1).min(*max);
+                        let count = totals.iter().filter(|c| range.contains(c)).count();
+                        (range, count)
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        let duplicates = input
+            .iter()
+            .enumerate()
+            .filter(|(_, elf)| !elf.snacks.is_empty())
+            .into_group_map_by(|(_, elf)| &elf.snacks)
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .map(|group| group.into_iter().map(|(index, _)| index).collect_vec())
+            .sorted()
+            .collect();
+        let empty = input
+            .iter()
+            .positions(|elf| elf.snacks.is_empty())
+            .collect();
+        Ok(InventoryReport {
+            elves,
+            median: percentile(50),
+            percentiles,
+            histogram,
+            duplicates,
+            empty,
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let elves = self
+            .elves
+            .iter()
+            .map(|(items, calories)| format!("{{\"items\":{items},\"calories\":{calories}}}"))
+            .join(",");
+        let median = self
+            .median
+            .map_or("null".to_string(), |median| median.to_string());
+        let percentiles = self
+            .percentiles
+            .iter()
+            .map(|(p, calories)| format!("\"p{p}\":{calories}"))
+            .join(",");
+        let histogram = self
+            .histogram
+            .iter()
+            .map(|(range, count)| {
+                format!(
+                    "{{\"from\":{},\"to\":{},\"count\":{count}}}",
+                    range.start(),
+                    range.end()
+                )
+            })
+            .join(",");
+        let duplicates = self
+            .duplicates
+            .iter()
+            .map(|group| format!("[{}]", group.iter().join(",")))
+            .join(",");
+        format!(
+            "{{\"elves\":[{elves}],\"median\":{median},\"percentiles\":{{{percentiles}}},\"histogram\":[{histogram}],\"duplicates\":[{duplicates}],\"empty\":[{}]}}",
+            self.empty.iter().join(",")
+        )
+    }
 }
 
-fn top3_calories_carried(input: &Input) -> Option<u32> {
-    Some(
-        input
+impl fmt::Display for InventoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:>5} {:>6} {:>10}", "elf", "items", "calories")?;
+        for (index, (items, calories)) in self.elves.iter().enumerate() {
+            writeln!(f, "{index:>5} {items:>6} {calories:>10}")?;
+        }
+        let median = self
+            .median
+            .map_or("-".to_string(), |median| median.to_string());
+        writeln!(f, "median: {median}")?;
+        let percentiles = self
+            .percentiles
+            .iter()
+            .map(|(p, calories)| format!("p{p} {calories}"))
+            .join(", ");
+        writeln!(f, "percentiles: {percentiles}")?;
+        writeln!(f, "histogram:")?;
+        let largest = self.histogram.iter().map(|(_, count)| *count).max();
+        for (range, count) in &self.histogram {
+            let bar = count * Self::BAR_WIDTH / largest.unwrap_or(1);
+            writeln!(
+                f,
+                "{:>10}..={:<10} {count:>6} {}",
+                range.start(),
+                range.end(),
+                "#".repeat(bar)
+            )?;
+        }
+        let duplicates = self
+            .duplicates
             .iter()
-            .map(Elf::count_calories)
-            .sorted_by_key(|c| Reverse(*c))
-            .take(3)
-            .sum(),
-    )
+            .map(|group| group.iter().join(" "))
+            .join(", ");
+        writeln!(f, "duplicates: {duplicates}")?;
+        write!(f, "empty: {}", self.empty.iter().join(" "))
+    }
+}
+
+enum ReportFormat {
+    Table,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(ReportFormat::Table),
+            "json" => Ok(ReportFormat::Json),
+            _ => anyhow::bail!("unknown report format '{s}', expected table or json"),
+        }
+    }
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 1);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let mut args = pico_args::Arguments::from_env();
+    match args.opt_value_from_str::<_, ReportFormat>("--report") {
+        Ok(None) => {
+            advent_of_code::solve!(1, part_one, input);
+            advent_of_code::solve!(2, part_two, input);
+        }
+        Ok(Some(format)) => match parse_input(input).and_then(|elves| InventoryReport::new(&elves))
+        {
+            Ok(report) => match format {
+                ReportFormat::Table => println!("{report}"),
+                ReportFormat::Json => println!("{}", report.to_json()),
+            },
+            Err(e) => {
+                eprintln!("failed: {:#}", e);
+                process::exit(1);
+            }
+        },
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            process::exit(1);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+
+    fn random_input(rng: &mut Rng) -> String {
+        (0..rng.range(1..=10))
+            .map(|_| {
+                (0..rng.range(1..=5))
+                    .map(|_| rng.range(1..=99999_u32).to_string())
+                    .join("\n")
+            })
+            .join("\n\n")
+    }
 
     #[test]
     fn test_elf_count_calories() {
         let elf = Elf {
             snacks: vec![7000, 8000, 9000],
         };
-        assert_eq!(elf.count_calories(), 24000);
+        assert_eq!(elf.count_calories().unwrap(), 24000);
+        let elf = Elf {
+            snacks: vec![u32::MAX, 1],
+        };
+        assert!(elf.count_calories().is_err());
     }
     #[test]
     fn test_most_calories_carried() {
@@ -96,18 +392,126 @@ mod tests {
                 snacks: vec![10000],
             },
         ];
-        assert_eq!(most_calories_carried(&input), Some(24000));
+        assert_eq!(most_calories_carried(&input).unwrap(), Some(24000));
+    }
+
+    #[test]
+    fn test_top_k_calories() {
+        let input = advent_of_code::read_file("examples", 1);
+        assert_eq!(
+            top_k_calories(input.as_bytes(), 3).unwrap(),
+            vec![(3, 24000), (2, 11000), (4, 10000)]
+        );
+        assert_eq!(top_k_calories(input.as_bytes(), 0).unwrap(), vec![]);
+        assert_eq!(top_k_calories(input.as_bytes(), 9).unwrap().len(), 5);
+        assert_eq!(
+            top_k_calories("1\n\n2\n\n2\n\n1".as_bytes(), 2).unwrap(),
+            vec![(1, 2), (2, 2)]
+        );
+        assert!(top_k_calories("1\n\nx".as_bytes(), 1).is_err());
+        assert!(top_k_calories("4294967295\n1".as_bytes(), 1).is_err());
+    }
+
+    #[test]
+    fn test_inventory_report() {
+        let input = advent_of_code::read_file("examples", 1);
+        let report = InventoryReport::new(&parse_input(&input).unwrap()).unwrap();
+        assert_eq!(
+            report.elves,
+            vec![(3, 6000), (1, 4000), (2, 11000), (3, 24000), (1, 10000)]
+        );
+        assert_eq!(report.median, Some(10000));
+        assert_eq!(
+            report.percentiles,
+            vec![
+                (10, 4000),
+                (25, 6000),
+                (50, 10000),
+                (75, 11000),
+                (90, 24000)
+            ]
+        );
+        assert_eq!(report.histogram.len(), 10);
+        assert_eq!(report.histogram[0], (4000..=6000, 2));
+        assert_eq!(report.histogram[2], (8002..=10002, 1));
+        assert_eq!(report.histogram[9], (22009..=24000, 1));
+        assert!(report.duplicates.is_empty());
+        assert!(report.empty.is_empty());
+    }
+
+    #[test]
+    fn test_inventory_report_json() {
+        let report = InventoryReport::new(&parse_input("1\n2\n\n3\n\n1\n2\n\n").unwrap()).unwrap();
+        assert_eq!(
+            report.to_json(),
+            concat!(
+                r#"{"elves":[{"items":2,"calories":3},{"items":1,"calories":3},"#,
+                r#"{"items":2,"calories":3},{"items":0,"calories":0}],"median":3,"#,
+                r#""percentiles":{"p10":0,"p25":0,"p50":3,"p75":3,"p90":3},"#,
+                r#""histogram":[{"from":0,"to":0,"count":1},{"from":1,"to":1,"count":0},"#,
+                r#"{"from":2,"to":2,"count":0},{"from":3,"to":3,"count":3}],"#,
+                r#""duplicates":[[0,2]],"empty":[3]}"#
+            )
+        );
+        let report = InventoryReport::new(&Vec::new()).unwrap();
+        assert_eq!(report.median, None);
+        assert!(report.to_json().contains(r#""median":null"#));
+    }
+
+    #[test]
+    fn test_inventory_report_overflow() {
+        let input = parse_input("4294967295\n1").unwrap();
+        assert!(InventoryReport::new(&input).is_err());
+        assert!(part_one("4294967295\n1").is_err());
+        assert!(part_two("4294967295\n\n1").is_err());
+        // Buckets past the largest total would start beyond u32::MAX
+        let report =
+            InventoryReport::new(&parse_input("4294967290\n\n4294967295").unwrap()).unwrap();
+        assert_eq!(report.histogram.len(), 6);
+        assert_eq!(report.histogram[5], (4294967295..=4294967295, 1));
     }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 1);
-        assert_eq!(part_one(&input), Some(24000));
+        assert_eq!(part_one(&input).unwrap(), 24000);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 1);
-        assert_eq!(part_two(&input), Some(45000));
+        assert_eq!(part_two(&input).unwrap(), 45000);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let input = random_input(&mut rng);
+            let elves = parse_input(&input).unwrap();
+            let printed = elves
+                .iter()
+                .map(|e| e.snacks.iter().join("\n"))
+                .join("\n\n");
+            assert_eq!(printed, input);
+            let totals = CalorieTotals::new(input.as_bytes())
+                .collect::<anyhow::Result<Vec<_>>>()
+                .unwrap();
+            let counted = elves
+                .iter()
+                .map(Elf::count_calories)
+                .collect::<anyhow::Result<Vec<_>>>()
+                .unwrap();
+            assert_eq!(totals, counted);
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = random_input(&mut rng);
+            let _ = parse_input(&rng.mutate(&input, "0123456789\n"));
+        }
     }
 }
@@ -1,243 +1,990 @@
+use advent_of_code::helpers::{ArithmeticError, CheckedNum};
+use anyhow::Context;
 use itertools::Itertools;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::process;
 use std::str::FromStr;
 
-type ParseError = String;
-
 #[derive(Clone, Debug, PartialEq)]
-enum Shape {
-    Rock,
-    Paper,
-    Scissors,
-}
-
-impl Shape {
-    fn score(&self) -> u32 {
-        match self {
-            Self::Rock => 1,
-            Self::Paper => 2,
-            Self::Scissors => 3,
-        }
+pub enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Loss => "Loss",
+            Self::Draw => "Draw",
+            Self::Win => "Win",
+        };
+        write!(f, "{name}")
     }
 }
 
-impl FromStr for Shape {
-    type Err = ParseError;
+/// The shapes played by the opponent and in response, as indices into the
+/// shapes of a `CyclicGame`.
+#[derive(Debug, PartialEq)]
+struct Round(usize, usize);
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "A" | "X" => Ok(Self::Rock),
-            "B" | "Y" => Ok(Self::Paper),
-            "C" | "Z" => Ok(Self::Scissors),
-            _ => Err(format!("Shape parse error: \"{s}\" not a valid shape")),
-        }
+impl Round {
+    fn score(&self, game: &CyclicGame) -> Result<u32, ArithmeticError> {
+        game.score(self.0, self.1)
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
-enum Outcome {
-    Loss,
-    Draw,
-    Win,
+/// A strategy guide whose response symbols stand for shapes.
+#[derive(Debug)]
+pub struct RoundStrategyGuide {
+    rounds: Vec<Round>,
 }
 
-impl Outcome {
-    fn score(&self) -> u32 {
-        match self {
-            Self::Loss => 0,
-            Self::Draw => 3,
-            Self::Win => 6,
+impl RoundStrategyGuide {
+    /// Reads the symbols with the opponent and shape maps of `config`.
+    pub fn parse(input: &str, config: &GuideConfig) -> anyhow::Result<Self> {
+        input
+            .parse::<Tournament>()?
+            .rounds(&config.game, &config.opponent, &config.shape)
+    }
+
+    fn play(&self, game: &CyclicGame) -> Result<TournamentResult, ArithmeticError> {
+        let mut result = TournamentResult::default();
+        for round in &self.rounds {
+            result.record(game, round)?;
         }
+        Ok(result)
     }
 }
 
-impl FromStr for Outcome {
-    type Err = ParseError;
+/// The shape played by the opponent and the outcome to aim for.
+#[derive(Debug, PartialEq)]
+struct Plan(usize, Outcome);
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "X" => Ok(Self::Loss),
-            "Y" => Ok(Self::Draw),
-            "Z" => Ok(Self::Win),
-            _ => Err(format!("Outcome parse error: \"{s}\" not a valid outcome")),
+impl Plan {
+    fn round(&self, game: &CyclicGame) -> Round {
+        Round(self.0, game.response(self.0, &self.1))
+    }
+}
+
+/// A strategy guide whose response symbols stand for outcomes.
+#[derive(Debug)]
+pub struct PlanStrategyGuide {
+    rounds: Vec<Plan>,
+}
+
+impl PlanStrategyGuide {
+    /// Reads the symbols with the opponent and outcome maps of `config`.
+    pub fn parse(input: &str, config: &GuideConfig) -> anyhow::Result<Self> {
+        input
+            .parse::<Tournament>()?
+            .plans(&config.game, &config.opponent, &config.outcome)
+    }
+
+    /// Answers the opponent with `CyclicGame::response` for every outcome.
+    fn play(&self, game: &CyclicGame) -> Result<TournamentResult, ArithmeticError> {
+        let mut result = TournamentResult::default();
+        for plan in &self.rounds {
+            result.record(game, &plan.round(game))?;
         }
+        Ok(result)
     }
 }
 
-#[derive(Debug, PartialEq)]
-struct Round(Shape, Shape);
+/// Scores of a cyclic game: one per shape, in the order of the shapes, and
+/// one per outcome.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoringTable {
+    pub shapes: Vec<u32>,
+    pub loss: u32,
+    pub draw: u32,
+    pub win: u32,
+}
 
-impl Round {
-    fn outcome(&self) -> Outcome {
-        match self {
-            Round(a, b) if a == b => Outcome::Draw,
-            Round(Shape::Rock, Shape::Scissors)
-            | Round(Shape::Paper, Shape::Rock)
-            | Round(Shape::Scissors, Shape::Paper) => Outcome::Loss,
-            _ => Outcome::Win,
+/// Rock paper scissors generalized to any odd number of shapes arranged in a
+/// cycle, where every shape beats the half of the other shapes preceding it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CyclicGame {
+    shapes: Vec<String>,
+    scoring: ScoringTable,
+}
+
+impl CyclicGame {
+    pub fn new(shapes: &[&str], scoring: ScoringTable) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            shapes.len() >= 3 && shapes.len() % 2 == 1,
+            "a cyclic game needs an odd number of at least 3 shapes, got {}",
+            shapes.len()
+        );
+        anyhow::ensure!(shapes.iter().all_unique(), "shape names must be unique");
+        anyhow::ensure!(
+            scoring.shapes.len() == shapes.len(),
+            "expected {} shape scores, got {}",
+            shapes.len(),
+            scoring.shapes.len()
+        );
+        Ok(CyclicGame {
+            shapes: shapes.iter().map(|s| s.to_string()).collect(),
+            scoring,
+        })
+    }
+
+    pub fn rock_paper_scissors() -> Self {
+        let scoring = ScoringTable {
+            shapes: vec![1, 2, 3],
+            loss: 0,
+            draw: 3,
+            win: 6,
+        };
+        Self::new(&["Rock", "Paper", "Scissors"], scoring).unwrap()
+    }
+
+    pub fn rock_paper_scissors_lizard_spock() -> Self {
+        let scoring = ScoringTable {
+            shapes: vec![1, 2, 3, 4, 5],
+            loss: 0,
+            draw: 3,
+            win: 6,
+        };
+        Self::new(&["Rock", "Spock", "Paper", "Lizard", "Scissors"], scoring).unwrap()
+    }
+
+    pub fn shapes(&self) -> &[String] {
+        &self.shapes
+    }
+
+    pub fn shape(&self, name: &str) -> Option<usize> {
+        self.shapes.iter().position(|s| s == name)
+    }
+
+    pub fn outcome(&self, opponent: usize, response: usize) -> Outcome {
+        let n = self.shapes.len();
+        match (response + n - opponent) % n {
+            0 => Outcome::Draw,
+            d if d <= n / 2 => Outcome::Win,
+            _ => Outcome::Loss,
         }
     }
 
-    fn score(&self) -> u32 {
-        let outcome_score = self.outcome().score();
-        let shape_score = self.1.score();
-        shape_score + outcome_score
+    pub fn score(&self, opponent: usize, response: usize) -> Result<u32, ArithmeticError> {
+        let outcome_score = match self.outcome(opponent, response) {
+            Outcome::Loss => self.scoring.loss,
+            Outcome::Draw => self.scoring.draw,
+            Outcome::Win => self.scoring.win,
+        };
+        self.scoring.shapes[response].try_add(&outcome_score)
+    }
+
+    /// The best scoring shape to answer `opponent` with for `outcome`, the
+    /// first one in the cycle on a tie.
+    pub fn response(&self, opponent: usize, outcome: &Outcome) -> usize {
+        (0..self.shapes.len())
+            .filter(|response| self.outcome(opponent, *response) == *outcome)
+            .max_by_key(|response| (self.scoring.shapes[*response], Reverse(*response)))
+            .expect("every outcome is reachable in a cyclic game")
     }
 }
 
-impl FromStr for Round {
-    type Err = ParseError;
+/// Shape of a cyclic game that each symbol of a strategy guide stands for.
+pub type SymbolMap = BTreeMap<String, usize>;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (movea, moveb) = s
-            .split_whitespace()
-            .map(|m| m.parse::<Shape>())
-            .collect_tuple()
-            .ok_or("Round Parse Error: expected 2 Shapes per round")?;
-        Ok(Round(movea?, moveb?))
-    }
+/// Maps the `i`-th symbol to the `i`-th shape.
+pub fn symbol_map(symbols: &[&str]) -> SymbolMap {
+    symbols
+        .iter()
+        .enumerate()
+        .map(|(shape, symbol)| (symbol.to_string(), shape))
+        .collect()
 }
 
-#[derive(Debug)]
-struct RoundStrategyGuide {
-    rounds: Vec<Round>,
+#[derive(Debug, Default, PartialEq)]
+pub struct TournamentResult {
+    pub score: u32,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
 }
 
-impl RoundStrategyGuide {
-    fn score(&self) -> u32 {
-        self.rounds.iter().map(Round::score).sum()
+impl TournamentResult {
+    fn record(&mut self, game: &CyclicGame, round: &Round) -> Result<(), ArithmeticError> {
+        self.score = self.score.try_add(&round.score(game)?)?;
+        match game.outcome(round.0, round.1) {
+            Outcome::Loss => self.losses += 1,
+            Outcome::Draw => self.draws += 1,
+            Outcome::Win => self.wins += 1,
+        }
+        Ok(())
     }
 }
 
-impl FromStr for RoundStrategyGuide {
-    type Err = ParseError;
+/// The shape `symbol` on the line with index `line` stands for in `game`.
+fn symbol_shape(
+    game: &CyclicGame,
+    map: &SymbolMap,
+    symbol: &str,
+    line: usize,
+) -> anyhow::Result<usize> {
+    map.get(symbol)
+        .copied()
+        .filter(|shape| *shape < game.shapes.len())
+        .with_context(|| format!("no shape for symbol '{symbol}' on line {}", line + 1))
+}
+
+/// Strategy guide kept as raw symbols, to be played with any interpretation
+/// of the symbols.
+#[derive(Debug)]
+pub struct Tournament {
+    rounds: Vec<(String, String)>,
+}
+
+impl FromStr for Tournament {
+    type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parsed_rounds = s
+        let rounds = s
             .lines()
-            .map(|l| l.parse::<Round>())
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(Self {
-            rounds: parsed_rounds,
-        })
+            .enumerate()
+            .map(|(i, l)| {
+                l.split_whitespace()
+                    .map(str::to_string)
+                    .collect_tuple()
+                    .with_context(|| format!("expected 2 symbols on line {}", i + 1))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Tournament { rounds })
     }
 }
 
-#[derive(Debug, PartialEq)]
-struct Plan(Shape, Outcome);
+impl Tournament {
+    /// The guide with the response symbols standing for shapes.
+    pub fn rounds(
+        &self,
+        game: &CyclicGame,
+        opponent: &SymbolMap,
+        response: &SymbolMap,
+    ) -> anyhow::Result<RoundStrategyGuide> {
+        let rounds = self
+            .rounds
+            .iter()
+            .enumerate()
+            .map(|(i, (a, b))| {
+                Ok(Round(
+                    symbol_shape(game, opponent, a, i)?,
+                    symbol_shape(game, response, b, i)?,
+                ))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(RoundStrategyGuide { rounds })
+    }
 
-impl Plan {
-    fn round(&self) -> Round {
-        let shape = match self {
-            Plan(s, Outcome::Draw) => s.clone(),
-            Plan(Shape::Paper, Outcome::Win) => Shape::Scissors,
-            Plan(Shape::Scissors, Outcome::Win) => Shape::Rock,
-            Plan(Shape::Rock, Outcome::Win) => Shape::Paper,
-            Plan(Shape::Paper, Outcome::Loss) => Shape::Rock,
-            Plan(Shape::Scissors, Outcome::Loss) => Shape::Paper,
-            Plan(Shape::Rock, Outcome::Loss) => Shape::Scissors,
-        };
-        Round(self.0.clone(), shape)
+    /// The guide with the response symbols standing for outcomes.
+    pub fn plans(
+        &self,
+        game: &CyclicGame,
+        opponent: &SymbolMap,
+        outcome: &BTreeMap<String, Outcome>,
+    ) -> anyhow::Result<PlanStrategyGuide> {
+        let rounds = self
+            .rounds
+            .iter()
+            .enumerate()
+            .map(|(i, (a, b))| {
+                let outcome = outcome
+                    .get(b)
+                    .with_context(|| format!("no outcome for symbol '{b}' on line {}", i + 1))?;
+                Ok(Plan(symbol_shape(game, opponent, a, i)?, outcome.clone()))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(PlanStrategyGuide { rounds })
+    }
+
+    pub fn simulate(
+        &self,
+        game: &CyclicGame,
+        opponent: &SymbolMap,
+        response: &SymbolMap,
+    ) -> anyhow::Result<TournamentResult> {
+        let guide = self.rounds(game, opponent, response)?;
+        guide.play(game).context("tournament score overflows")
+    }
+
+    /// Plays the response symbols as the outcomes they stand for, answering
+    /// the opponent with `CyclicGame::response`.
+    pub fn follow(
+        &self,
+        game: &CyclicGame,
+        opponent: &SymbolMap,
+        outcome: &BTreeMap<String, Outcome>,
+    ) -> anyhow::Result<TournamentResult> {
+        let guide = self.plans(game, opponent, outcome)?;
+        guide.play(game).context("tournament score overflows")
     }
 
-    fn score(&self) -> u32 {
-        self.round().score()
+    /// Response mapping, with a different shape for every response symbol,
+    /// that maximizes the total score against the opponent as mapped.
+    pub fn optimize(
+        &self,
+        game: &CyclicGame,
+        opponent: &SymbolMap,
+    ) -> anyhow::Result<(SymbolMap, TournamentResult)> {
+        let symbols = self
+            .rounds
+            .iter()
+            .map(|(_, b)| b.as_str())
+            .sorted()
+            .dedup()
+            .collect_vec();
+        anyhow::ensure!(
+            symbols.len() <= game.shapes.len(),
+            "{} response symbols can't map to {} different shapes",
+            symbols.len(),
+            game.shapes.len()
+        );
+        let mut best: Option<(SymbolMap, TournamentResult)> = None;
+        for shapes in (0..game.shapes.len()).permutations(symbols.len()) {
+            let response = symbols
+                .iter()
+                .zip(shapes)
+                .map(|(symbol, shape)| (symbol.to_string(), shape))
+                .collect();
+            let result = self.simulate(game, opponent, &response)?;
+            if best.as_ref().map_or(true, |(_, b)| result.score > b.score) {
+                best = Some((response, result));
+            }
+        }
+        best.context("no mapping to choose from")
     }
 }
 
-impl FromStr for Plan {
-    type Err = ParseError;
+/// A cyclic game and what the symbols of a strategy guide stand for in it.
+/// Written as one section per line or separated by `;`:
+/// `shapes Rock=1 Paper=2 Scissors=3` names the shapes in cycle order with
+/// their scores, `outcomes Loss=0 Draw=3 Win=6` scores the outcomes,
+/// `opponent A=Rock B=Paper C=Scissors` and `shape X=Rock Y=Paper Z=Scissors`
+/// map symbols to shapes, and `outcome X=Loss Y=Draw Z=Win` maps them to
+/// outcomes. Sections that are left out keep these defaults.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GuideConfig {
+    pub game: CyclicGame,
+    pub opponent: SymbolMap,
+    pub shape: SymbolMap,
+    pub outcome: BTreeMap<String, Outcome>,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (shape_str, outcome_str) = s
-            .split_whitespace()
-            .collect_tuple()
-            .ok_or("Round Parse Error: expected 2 Shapes per round")?;
-        let shape = shape_str.parse::<Shape>()?;
-        let outcome = outcome_str.parse::<Outcome>()?;
-        Ok(Plan(shape, outcome))
+impl GuideConfig {
+    const SECTIONS: [(&'static str, &'static str); 5] = [
+        ("shapes", "Rock=1 Paper=2 Scissors=3"),
+        ("outcomes", "Loss=0 Draw=3 Win=6"),
+        ("opponent", "A=Rock B=Paper C=Scissors"),
+        ("shape", "X=Rock Y=Paper Z=Scissors"),
+        ("outcome", "X=Loss Y=Draw Z=Win"),
+    ];
+
+    /// The config with its shapes and outcomes assigned to the response
+    /// symbols in every possible order, starting with the config itself.
+    pub fn permutations(&self) -> anyhow::Result<Vec<GuideConfig>> {
+        anyhow::ensure!(
+            self.shape.keys().eq(self.outcome.keys()),
+            "shape and outcome sections must use the same symbols"
+        );
+        let symbols = self.shape.keys().collect_vec();
+        let shapes = self.shape.values().collect_vec();
+        let outcomes = self.outcome.values().collect_vec();
+        Ok((0..symbols.len())
+            .permutations(symbols.len())
+            .map(|order| GuideConfig {
+                game: self.game.clone(),
+                opponent: self.opponent.clone(),
+                shape: order
+                    .iter()
+                    .enumerate()
+                    .map(|(i, j)| (symbols[i].clone(), *shapes[*j]))
+                    .collect(),
+                outcome: order
+                    .iter()
+                    .enumerate()
+                    .map(|(i, j)| (symbols[i].clone(), outcomes[*j].clone()))
+                    .collect(),
+            })
+            .collect())
+    }
+
+    fn format_shapes(&self, map: &SymbolMap) -> String {
+        map.iter()
+            .map(|(symbol, shape)| format!("{symbol}={}", self.game.shapes[*shape]))
+            .join(" ")
+    }
+
+    fn format_outcomes(&self) -> String {
+        self.outcome
+            .iter()
+            .map(|(symbol, outcome)| format!("{symbol}={outcome}"))
+            .join(" ")
     }
 }
 
-#[derive(Debug)]
-struct PlanStrategyGuide {
-    rounds: Vec<Plan>,
+fn outcome_named(name: &str) -> anyhow::Result<Outcome> {
+    [Outcome::Loss, Outcome::Draw, Outcome::Win]
+        .into_iter()
+        .find(|outcome| outcome.to_string() == name)
+        .with_context(|| format!("'{name}' is not an outcome"))
 }
 
-impl PlanStrategyGuide {
-    fn score(&self) -> u32 {
-        self.rounds.iter().map(Plan::score).sum()
+impl Default for GuideConfig {
+    fn default() -> Self {
+        "".parse().unwrap()
     }
 }
 
-impl FromStr for PlanStrategyGuide {
-    type Err = ParseError;
+impl FromStr for GuideConfig {
+    type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parsed_rounds = s
-            .lines()
-            .map(|l| l.parse::<Plan>())
-            .collect::<Result<Vec<_>, _>>()?;
-        Ok(Self {
-            rounds: parsed_rounds,
+        let mut sections = Self::SECTIONS
+            .iter()
+            .map(|(name, entries)| (*name, entries.split_whitespace().collect_vec()))
+            .collect::<BTreeMap<_, _>>();
+        for line in s.split(['\n', ';']) {
+            let mut words = line.split_whitespace();
+            let Some(section) = words.next() else {
+                continue;
+            };
+            let entries = sections
+                .get_mut(section)
+                .with_context(|| format!("unknown section '{section}'"))?;
+            *entries = words.collect();
+            anyhow::ensure!(!entries.is_empty(), "empty {section} section");
+        }
+        let section = |name: &str| {
+            sections[name]
+                .iter()
+                .map(|entry| {
+                    entry
+                        .split_once('=')
+                        .with_context(|| format!("expected key=value in {name}, got '{entry}'"))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()
+        };
+        let score = |score: &str| {
+            score
+                .parse::<u32>()
+                .with_context(|| format!("invalid score '{score}'"))
+        };
+        let shapes = section("shapes")?;
+        let mut scoring = ScoringTable {
+            shapes: shapes
+                .iter()
+                .map(|(_, s)| score(s))
+                .collect::<anyhow::Result<_>>()?,
+            loss: 0,
+            draw: 0,
+            win: 0,
+        };
+        let outcomes = section("outcomes")?;
+        anyhow::ensure!(
+            outcomes
+                .iter()
+                .map(|(name, _)| *name)
+                .sorted()
+                .eq(["Draw", "Loss", "Win"]),
+            "outcomes must score Loss, Draw and Win once each"
+        );
+        for (name, s) in outcomes {
+            let points = score(s)?;
+            match outcome_named(name)? {
+                Outcome::Loss => scoring.loss = points,
+                Outcome::Draw => scoring.draw = points,
+                Outcome::Win => scoring.win = points,
+            }
+        }
+        let names = shapes.iter().map(|(name, _)| *name).collect_vec();
+        let game = CyclicGame::new(&names, scoring)?;
+        let symbols = |name: &str| {
+            section(name)?
+                .into_iter()
+                .map(|(symbol, shape)| {
+                    let shape = game
+                        .shape(shape)
+                        .with_context(|| format!("unknown shape '{shape}' in {name}"))?;
+                    Ok((symbol.to_string(), shape))
+                })
+                .collect::<anyhow::Result<SymbolMap>>()
+        };
+        let outcome = section("outcome")?
+            .into_iter()
+            .map(|(symbol, name)| Ok((symbol.to_string(), outcome_named(name)?)))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(GuideConfig {
+            opponent: symbols("opponent")?,
+            shape: symbols("shape")?,
+            outcome,
+            game,
         })
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let strategy_guide = input.parse::<RoundStrategyGuide>().ok()?;
-    Some(strategy_guide.score())
+impl fmt::Display for GuideConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shapes = self
+            .game
+            .shapes
+            .iter()
+            .zip(&self.game.scoring.shapes)
+            .map(|(name, score)| format!("{name}={score}"))
+            .join(" ");
+        let scoring = &self.game.scoring;
+        writeln!(f, "shapes {shapes}")?;
+        writeln!(
+            f,
+            "outcomes Loss={} Draw={} Win={}",
+            scoring.loss, scoring.draw, scoring.win
+        )?;
+        writeln!(f, "opponent {}", self.format_shapes(&self.opponent))?;
+        writeln!(f, "shape {}", self.format_shapes(&self.shape))?;
+        write!(f, "outcome {}", self.format_outcomes())
+    }
+}
+
+/// Scores of a strategy guide when its response symbols stand for the shapes
+/// and the outcomes of `config`.
+#[derive(Debug, PartialEq)]
+pub struct Interpretation {
+    pub config: GuideConfig,
+    pub round_score: u32,
+    pub plan_score: u32,
+}
+
+/// Scores of the guide for every order of the response symbols of `config`.
+pub fn interpret(input: &str, config: &GuideConfig) -> anyhow::Result<Vec<Interpretation>> {
+    let tournament = input.parse::<Tournament>()?;
+    config
+        .permutations()?
+        .into_iter()
+        .map(|config| {
+            let rounds = tournament.simulate(&config.game, &config.opponent, &config.shape)?;
+            let plans = tournament.follow(&config.game, &config.opponent, &config.outcome)?;
+            Ok(Interpretation {
+                round_score: rounds.score,
+                plan_score: plans.score,
+                config,
+            })
+        })
+        .collect()
+}
+
+pub fn part_one(input: &str) -> anyhow::Result<u32> {
+    let config = GuideConfig::default();
+    let strategy_guide = RoundStrategyGuide::parse(input, &config)?;
+    let result = strategy_guide
+        .play(&config.game)
+        .context("total score overflows")?;
+    Ok(result.score)
+}
+
+pub fn part_two(input: &str) -> anyhow::Result<u32> {
+    let config = GuideConfig::default();
+    let strategy_guide = PlanStrategyGuide::parse(input, &config)?;
+    let result = strategy_guide
+        .play(&config.game)
+        .context("total score overflows")?;
+    Ok(result.score)
+}
+
+struct Args {
+    config: Option<GuideConfig>,
+    interpretations: bool,
+}
+
+fn parse_args() -> anyhow::Result<Args> {
+    let mut args = pico_args::Arguments::from_env();
+    let interpretations = args.contains("--interpretations");
+    let mapping: Option<String> = args.opt_value_from_str("--mapping")?;
+    let mapping_file: Option<String> = args.opt_value_from_str("--mapping-file")?;
+    let config = match (mapping, mapping_file) {
+        (Some(_), Some(_)) => anyhow::bail!("--mapping and --mapping-file can't be combined"),
+        (Some(mapping), None) => Some(mapping),
+        (None, Some(path)) => {
+            Some(fs::read_to_string(&path).with_context(|| format!("could not read {path}"))?)
+        }
+        (None, None) => None,
+    };
+    let config = config.map(|c| c.parse::<GuideConfig>()).transpose()?;
+    Ok(Args {
+        config,
+        interpretations,
+    })
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let strategy_guide = input.parse::<PlanStrategyGuide>().ok()?;
-    Some(strategy_guide.score())
+fn report(input: &str, config: &GuideConfig, interpretations: bool) -> anyhow::Result<()> {
+    if interpretations {
+        for interpretation in interpret(input, config)? {
+            let config = &interpretation.config;
+            println!(
+                "shape {}: {:>8} | outcome {}: {:>8}",
+                config.format_shapes(&config.shape),
+                interpretation.round_score,
+                config.format_outcomes(),
+                interpretation.plan_score
+            );
+        }
+    } else {
+        let tournament = input.parse::<Tournament>()?;
+        let rounds = tournament.simulate(&config.game, &config.opponent, &config.shape)?;
+        let plans = tournament.follow(&config.game, &config.opponent, &config.outcome)?;
+        println!("{config}");
+        println!("rounds: {}", rounds.score);
+        println!("plans: {}", plans.score);
+    }
+    Ok(())
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 2);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {:#}", e);
+            process::exit(1);
+        }
+    };
+    match args {
+        Args {
+            config: None,
+            interpretations: false,
+        } => {
+            advent_of_code::solve!(1, part_one, input);
+            advent_of_code::solve!(2, part_two, input);
+        }
+        Args {
+            config,
+            interpretations,
+        } => {
+            if let Err(e) = report(input, &config.unwrap_or_default(), interpretations) {
+                eprintln!("failed: {:#}", e);
+                process::exit(1);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+
+    fn random_input(rng: &mut Rng) -> String {
+        (0..rng.range(1..=20))
+            .map(|_| {
+                format!(
+                    "{} {}",
+                    rng.choose(&['A', 'B', 'C']),
+                    rng.choose(&['X', 'Y', 'Z'])
+                )
+            })
+            .join("\n")
+    }
 
     #[test]
     fn test_round_outcome() {
-        assert_eq!(Round(Shape::Rock, Shape::Scissors).outcome(), Outcome::Loss);
-        assert_eq!(Round(Shape::Rock, Shape::Rock).outcome(), Outcome::Draw);
-        assert_eq!(Round(Shape::Rock, Shape::Paper).outcome(), Outcome::Win);
+        let game = CyclicGame::rock_paper_scissors();
+        let (rock, paper, scissors) = (0, 1, 2);
+        assert_eq!(game.outcome(rock, scissors), Outcome::Loss);
+        assert_eq!(game.outcome(rock, rock), Outcome::Draw);
+        assert_eq!(game.outcome(rock, paper), Outcome::Win);
+        assert_eq!(game.outcome(scissors, rock), Outcome::Win);
     }
 
     #[test]
     fn test_round_score() {
-        assert_eq!(Round(Shape::Rock, Shape::Paper).score(), 8);
-        assert_eq!(Round(Shape::Paper, Shape::Rock).score(), 1);
-        assert_eq!(Round(Shape::Scissors, Shape::Scissors).score(), 6);
+        let game = CyclicGame::rock_paper_scissors();
+        assert_eq!(Round(0, 1).score(&game).unwrap(), 8);
+        assert_eq!(Round(1, 0).score(&game).unwrap(), 1);
+        assert_eq!(Round(2, 2).score(&game).unwrap(), 6);
     }
 
     #[test]
     fn test_round_strategy_guide_score() {
         let guide = RoundStrategyGuide {
-            rounds: vec![
-                Round(Shape::Rock, Shape::Paper),
-                Round(Shape::Paper, Shape::Rock),
-                Round(Shape::Scissors, Shape::Scissors),
-            ],
+            rounds: vec![Round(0, 1), Round(1, 0), Round(2, 2)],
+        };
+        let result = guide.play(&CyclicGame::rock_paper_scissors()).unwrap();
+        assert_eq!(result.score, 15);
+    }
+
+    #[test]
+    fn test_guides_parse_with_config() {
+        let config = GuideConfig::default();
+        let rounds = RoundStrategyGuide::parse("A Y\nC X", &config).unwrap();
+        assert_eq!(rounds.rounds, [Round(0, 1), Round(2, 0)]);
+        let plans = PlanStrategyGuide::parse("A Y\nC X", &config).unwrap();
+        assert_eq!(
+            plans.rounds,
+            [Plan(0, Outcome::Draw), Plan(2, Outcome::Loss)]
+        );
+
+        let config = "opponent Q=Scissors R=Rock; shape K=Paper; outcome K=Win"
+            .parse::<GuideConfig>()
+            .unwrap();
+        let rounds = RoundStrategyGuide::parse("Q K\nR K", &config).unwrap();
+        assert_eq!(rounds.rounds, [Round(2, 1), Round(0, 1)]);
+        let plans = PlanStrategyGuide::parse("R K", &config).unwrap();
+        assert_eq!(plans.rounds, [Plan(0, Outcome::Win)]);
+
+        let err = RoundStrategyGuide::parse("Q K\nA K", &config).unwrap_err();
+        assert_eq!(err.to_string(), "no shape for symbol 'A' on line 2");
+        let err = PlanStrategyGuide::parse("R Z", &config).unwrap_err();
+        assert_eq!(err.to_string(), "no outcome for symbol 'Z' on line 1");
+        assert!(RoundStrategyGuide::parse("Q", &config).is_err());
+    }
+
+    #[test]
+    fn test_plan_round() {
+        let game = CyclicGame::rock_paper_scissors();
+        assert_eq!(Plan(0, Outcome::Draw).round(&game), Round(0, 0));
+        assert_eq!(Plan(1, Outcome::Loss).round(&game), Round(1, 0));
+        assert_eq!(Plan(2, Outcome::Win).round(&game), Round(2, 0));
+    }
+
+    #[test]
+    fn test_rock_paper_scissors_lizard_spock() {
+        let game = CyclicGame::rock_paper_scissors_lizard_spock();
+        let shape = |name| game.shape(name).unwrap();
+        let beats = [
+            ("Scissors", "Paper"),
+            ("Paper", "Rock"),
+            ("Rock", "Lizard"),
+            ("Lizard", "Spock"),
+            ("Spock", "Scissors"),
+            ("Scissors", "Lizard"),
+            ("Lizard", "Paper"),
+            ("Paper", "Spock"),
+            ("Spock", "Rock"),
+            ("Rock", "Scissors"),
+        ];
+        for (winner, loser) in beats {
+            assert_eq!(game.outcome(shape(loser), shape(winner)), Outcome::Win);
+            assert_eq!(game.outcome(shape(winner), shape(loser)), Outcome::Loss);
+        }
+        assert_eq!(game.score(shape("Rock"), shape("Scissors")).unwrap(), 5);
+        assert_eq!(game.score(shape("Spock"), shape("Spock")).unwrap(), 2 + 3);
+    }
+
+    #[test]
+    fn test_cyclic_game_validation() {
+        let scoring = |n| ScoringTable {
+            shapes: vec![1; n],
+            loss: 0,
+            draw: 3,
+            win: 6,
         };
-        assert_eq!(guide.score(), 15);
+        assert!(CyclicGame::new(&["A", "B", "C", "D"], scoring(4)).is_err());
+        assert!(CyclicGame::new(&["A", "B", "A"], scoring(3)).is_err());
+        assert!(CyclicGame::new(&["A", "B", "C"], scoring(2)).is_err());
+        assert!(CyclicGame::new(&["A", "B", "C"], scoring(3)).is_ok());
+    }
+
+    #[test]
+    fn test_tournament_simulate() {
+        let input = advent_of_code::read_file("examples", 2);
+        let tournament = input.parse::<Tournament>().unwrap();
+        let game = CyclicGame::rock_paper_scissors();
+        let result = tournament
+            .simulate(
+                &game,
+                &symbol_map(&["A", "B", "C"]),
+                &symbol_map(&["X", "Y", "Z"]),
+            )
+            .unwrap();
+        assert_eq!(
+            result,
+            TournamentResult {
+                score: 15,
+                wins: 1,
+                draws: 1,
+                losses: 1
+            }
+        );
+        assert!(tournament
+            .simulate(
+                &game,
+                &symbol_map(&["A", "B"]),
+                &symbol_map(&["X", "Y", "Z"])
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_tournament_optimize() {
+        let input = advent_of_code::read_file("examples", 2);
+        let tournament = input.parse::<Tournament>().unwrap();
+        let game = CyclicGame::rock_paper_scissors();
+        let (mapping, result) = tournament
+            .optimize(&game, &symbol_map(&["A", "B", "C"]))
+            .unwrap();
+        assert_eq!(mapping, symbol_map(&["Z", "Y", "X"]));
+        assert_eq!(result.score, 24);
+        assert_eq!(result.wins, 3);
+
+        let game = CyclicGame::rock_paper_scissors_lizard_spock();
+        let (_, result) = tournament
+            .optimize(&game, &symbol_map(&["A", "B", "C"]))
+            .unwrap();
+        assert!(result.score >= 24);
+    }
+
+    #[test]
+    fn test_guide_config() {
+        let input = "Q K\nR K";
+        let tournament = input.parse::<Tournament>().unwrap();
+        let config = "opponent Q=Scissors R=Rock; shape K=Paper\n"
+            .parse::<GuideConfig>()
+            .unwrap();
+        assert_eq!(config.to_string().parse::<GuideConfig>().unwrap(), config);
+        let rounds = tournament
+            .simulate(&config.game, &config.opponent, &config.shape)
+            .unwrap();
+        assert_eq!(rounds.score, 2 + 2 + 6);
+        let plans = "R Z"
+            .parse::<Tournament>()
+            .unwrap()
+            .follow(&config.game, &config.opponent, &config.outcome)
+            .unwrap();
+        assert_eq!(plans.score, 2 + 6);
+        assert!("A X"
+            .parse::<Tournament>()
+            .unwrap()
+            .simulate(&config.game, &config.opponent, &config.shape)
+            .is_err());
+        assert!("shape X".parse::<GuideConfig>().is_err());
+        assert!("shape X=Spock".parse::<GuideConfig>().is_err());
+        assert!("tactics X=Rock".parse::<GuideConfig>().is_err());
+        assert!("shape X:Rock".parse::<GuideConfig>().is_err());
+        assert!("outcomes Loss=0 Win=6".parse::<GuideConfig>().is_err());
+        assert!("shapes Rock=1 Paper=2".parse::<GuideConfig>().is_err());
+    }
+
+    #[test]
+    fn test_guide_config_describes_game() {
+        let config = "shapes Rock=1 Spock=2 Paper=3 Lizard=4 Scissors=5
+            opponent A=Rock B=Spock C=Paper D=Lizard E=Scissors
+            shape V=Rock W=Spock X=Paper Y=Lizard Z=Scissors
+            outcome X=Loss Y=Draw Z=Win"
+            .parse::<GuideConfig>()
+            .unwrap();
+        assert_eq!(
+            config.game,
+            CyclicGame::new(
+                &["Rock", "Spock", "Paper", "Lizard", "Scissors"],
+                ScoringTable {
+                    shapes: vec![1, 2, 3, 4, 5],
+                    loss: 0,
+                    draw: 3,
+                    win: 6
+                }
+            )
+            .unwrap()
+        );
+        let tournament = "A Y\nE V\nD W".parse::<Tournament>().unwrap();
+        let rounds = tournament
+            .simulate(&config.game, &config.opponent, &config.shape)
+            .unwrap();
+        assert_eq!(
+            rounds,
+            TournamentResult {
+                score: 4 + 1 + 6 + 2,
+                wins: 1,
+                draws: 0,
+                losses: 2
+            }
+        );
+        // Rock beats Lizard and Scissors, and Scissors scores more
+        let plans = "A X".parse::<Tournament>().unwrap();
+        let plans = plans
+            .follow(&config.game, &config.opponent, &config.outcome)
+            .unwrap();
+        assert_eq!(plans.score, 5);
+    }
+
+    #[test]
+    fn test_tournament_score_overflow() {
+        let game = CyclicGame::new(
+            &["Rock", "Paper", "Scissors"],
+            ScoringTable {
+                shapes: vec![u32::MAX / 2, 2, 3],
+                loss: 0,
+                draw: 3,
+                win: 6,
+            },
+        )
+        .unwrap();
+        let symbols = || symbol_map(&["A", "B", "C"]);
+        let tournament = "A A".parse::<Tournament>().unwrap();
+        assert!(tournament.simulate(&game, &symbols(), &symbols()).is_ok());
+        let tournament = "A A\nA A".parse::<Tournament>().unwrap();
+        assert!(tournament.simulate(&game, &symbols(), &symbols()).is_err());
+        let game = CyclicGame::new(
+            &["Rock", "Paper", "Scissors"],
+            ScoringTable {
+                shapes: vec![u32::MAX, 2, 3],
+                loss: 0,
+                draw: 3,
+                win: 6,
+            },
+        )
+        .unwrap();
+        assert!(game.score(0, 0).is_err());
+    }
+
+    #[test]
+    fn test_interpretations() {
+        let input = advent_of_code::read_file("examples", 2);
+        let interpretations = interpret(&input, &GuideConfig::default()).unwrap();
+        assert_eq!(interpretations.len(), 6);
+        assert_eq!(interpretations[0].config, GuideConfig::default());
+        assert_eq!(interpretations[0].round_score, 15);
+        assert_eq!(interpretations[0].plan_score, 12);
+        let best = interpretations.iter().map(|i| i.round_score).max();
+        assert_eq!(best, Some(24));
+        let config = "shape X=Rock Y=Paper".parse::<GuideConfig>().unwrap();
+        assert!(interpret(&input, &config).is_err());
     }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 2);
-        assert_eq!(part_one(&input), Some(15));
+        assert_eq!(part_one(&input).unwrap(), 15);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 2);
-        assert_eq!(part_two(&input), Some(12));
+        assert_eq!(part_two(&input).unwrap(), 12);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let input = random_input(&mut rng);
+            let config = GuideConfig::default();
+            let rounds = RoundStrategyGuide::parse(&input, &config).unwrap().rounds;
+            let plans = PlanStrategyGuide::parse(&input, &config).unwrap().rounds;
+            assert_eq!(rounds.len(), input.lines().count());
+            assert_eq!(plans.len(), input.lines().count());
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = random_input(&mut rng);
+            let input = rng.mutate(&input, "ABCXYZ \n");
+            let _ = RoundStrategyGuide::parse(&input, &GuideConfig::default());
+            let _ = PlanStrategyGuide::parse(&input, &GuideConfig::default());
+        }
     }
 }
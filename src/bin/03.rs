@@ -1,5 +1,5 @@
+use anyhow::{bail, Context};
 use std::str::FromStr;
-use std::{collections::HashSet, ops::BitAnd};
 
 type Input<'a> = Vec<&'a str>;
 
@@ -7,7 +7,9 @@ type Input<'a> = Vec<&'a str>;
 struct Item(char);
 
 impl Item {
-    fn score(&self) -> u32 {
+    /// Priority between 1 and 52, which is also the bit of the item in a
+    /// [`Rucksack`].
+    fn priority(&self) -> u32 {
         let val = self.0 as u32;
         if self.0.is_uppercase() {
             val - ('A' as u32) + 27
@@ -16,81 +18,130 @@ impl Item {
         }
     }
 
-    fn from_char(c: &char) -> Item {
-        Item(*c)
+    fn from_priority(priority: u32) -> Item {
+        let c = if priority > 26 {
+            b'A' + (priority - 27) as u8
+        } else {
+            b'a' + (priority - 1) as u8
+        };
+        Item(c as char)
+    }
+}
+
+impl TryFrom<char> for Item {
+    type Error = anyhow::Error;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        if !c.is_ascii_alphabetic() {
+            bail!("'{c}' is not a valid item");
+        }
+        Ok(Item(c))
     }
 }
 
-#[derive(Debug, Clone)]
+/// Set of items as a bitmask indexed by priority.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 struct Rucksack {
-    items: HashSet<Item>,
+    items: u64,
 }
 
 impl FromIterator<Item> for Rucksack {
     fn from_iter<I: IntoIterator<Item = Item>>(iter: I) -> Self {
         Self {
-            items: HashSet::from_iter(iter),
+            items: iter
+                .into_iter()
+                .fold(0, |items, item| items | 1 << item.priority()),
         }
     }
 }
 
 impl FromStr for Rucksack {
-    type Err = ();
+    type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self::from_iter(s.chars().map(|c| Item::from_char(&c))))
+        s.chars().map(Item::try_from).collect()
     }
 }
 
 impl Rucksack {
     fn common_sack(self, other: &Rucksack) -> Rucksack {
         Rucksack {
-            items: self.items.bitand(&other.items),
+            items: self.items & other.items,
+        }
+    }
+
+    /// Items found in every one of `sacks`. A plain fold over the masks,
+    /// which compilers turn into vector instructions for large batches.
+    fn common_items(sacks: &[Rucksack]) -> Rucksack {
+        Rucksack {
+            items: sacks
+                .iter()
+                .fold(u64::MAX, |items, sack| items & sack.items),
         }
     }
 
+    fn iter(&self) -> impl Iterator<Item = Item> {
+        let mut items = self.items;
+        std::iter::from_fn(move || {
+            if items == 0 {
+                return None;
+            }
+            let priority = items.trailing_zeros();
+            items &= items - 1;
+            Some(Item::from_priority(priority))
+        })
+    }
+
     fn score(&self) -> u32 {
-        self.items.iter().map(Item::score).sum()
+        self.iter().map(|item| item.priority()).sum()
     }
 }
 
-fn score_pockets(input: &Input) -> Option<u32> {
-    Some(
-        input
-            .iter()
-            .filter_map(|l| {
-                let (one_str, two_str) = l.split_at(l.len() / 2);
-                let one = one_str.parse::<Rucksack>().ok()?;
-                let two = two_str.parse::<Rucksack>().ok()?;
-                Some(one.common_sack(&two).score())
-            })
-            .sum(),
-    )
+fn parse_rucksack(line_no: usize, s: &str) -> anyhow::Result<Rucksack> {
+    s.parse::<Rucksack>()
+        .with_context(|| format!("invalid rucksack on line {}", line_no + 1))
 }
 
-fn score_groups(group_size: usize, input: &Input) -> Option<u32> {
-    Some(
-        input
-            .chunks(group_size)
-            .filter_map(|chunks| {
-                Some(
-                    chunks
-                        .iter()
-                        .filter_map(|chunk| chunk.parse::<Rucksack>().ok())
-                        .reduce(|accum, r| accum.common_sack(&r))?
-                        .score(),
-                )
-            })
-            .sum(),
-    )
+fn score_pockets(input: &Input) -> anyhow::Result<u32> {
+    input
+        .iter()
+        .enumerate()
+        .map(|(i, l)| {
+            let items = l.chars().count();
+            if items % 2 != 0 {
+                bail!("odd number of items in rucksack on line {}", i + 1);
+            }
+            let half = l
+                .char_indices()
+                .nth(items / 2)
+                .map_or(l.len(), |(at, _)| at);
+            let (one, two) = l.split_at(half);
+            let one = parse_rucksack(i, one)?;
+            let two = parse_rucksack(i, two)?;
+            Ok(one.common_sack(&two).score())
+        })
+        .sum()
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
+fn score_groups(group_size: usize, input: &Input) -> anyhow::Result<u32> {
+    anyhow::ensure!(group_size > 0, "groups need at least one rucksack");
+    let sacks = input
+        .iter()
+        .enumerate()
+        .map(|(i, l)| parse_rucksack(i, l))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(sacks
+        .chunks(group_size)
+        .map(|group| Rucksack::common_items(group).score())
+        .sum())
+}
+
+pub fn part_one(input: &str) -> anyhow::Result<u32> {
     let lines: Input = input.lines().collect();
     score_pockets(&lines)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
+pub fn part_two(input: &str) -> anyhow::Result<u32> {
     let lines: Input = input.lines().collect();
     score_groups(3, &lines)
 }
@@ -104,16 +155,122 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+    use std::collections::HashSet;
+
+    const ITEMS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+    fn random_input(rng: &mut Rng) -> String {
+        let items = ITEMS.chars().collect::<Vec<_>>();
+        (0..3 * rng.range(1..=5))
+            .map(|_| {
+                (0..2 * rng.range(1..=12))
+                    .map(|_| *rng.choose(&items))
+                    .collect::<String>()
+            })
+            .join("\n")
+    }
+
+    #[test]
+    fn test_item_priority() {
+        for (c, priority) in ITEMS.chars().zip(1..) {
+            assert_eq!(Item(c).priority(), priority);
+            assert_eq!(Item::from_priority(priority), Item(c));
+        }
+    }
+
+    #[test]
+    fn test_rucksack_bitmask() {
+        let sack = "vJrwpWtwJgWr".parse::<Rucksack>().unwrap();
+        assert_eq!(
+            sack.iter().collect::<Vec<_>>(),
+            "gprtvwJW".chars().map(Item).collect::<Vec<_>>()
+        );
+        let other = "hcsFMMfFFhFp".parse::<Rucksack>().unwrap();
+        assert_eq!(
+            sack.common_sack(&other).iter().collect::<Vec<_>>(),
+            [Item('p')]
+        );
+        assert_eq!(
+            Rucksack::common_items(&[sack, other]),
+            sack.common_sack(&other)
+        );
+        assert_eq!(Rucksack::common_items(&[sack]), sack);
+    }
+
+    #[test]
+    fn test_score_groups_of_any_size() {
+        let input = advent_of_code::read_file("examples", 3);
+        let lines: Input = input.lines().collect();
+        assert_eq!(score_groups(3, &lines).unwrap(), 70);
+        assert!(score_groups(0, &lines).is_err());
+
+        let mut rng = Rng::new(4);
+        for _ in 0..100 {
+            let input = random_input(&mut rng);
+            let lines: Input = input.lines().collect();
+            for group_size in 1..=4 {
+                let expected: u32 = lines
+                    .iter()
+                    .chunks(group_size)
+                    .into_iter()
+                    .map(|group| {
+                        group
+                            .map(|l| l.chars().collect::<HashSet<_>>())
+                            .reduce(|a, b| &a & &b)
+                            .unwrap()
+                            .into_iter()
+                            .map(|c| Item(c).priority())
+                            .sum::<u32>()
+                    })
+                    .sum();
+                assert_eq!(score_groups(group_size, &lines).unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_score_pockets_errors() {
+        let error = score_pockets(&vec!["abc1d"]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "odd number of items in rucksack on line 1"
+        );
+        let error = score_pockets(&vec!["ab", "aéb€"]).unwrap_err();
+        assert_eq!(error.to_string(), "invalid rucksack on line 2");
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 3);
-        assert_eq!(part_one(&input), Some(157));
+        assert_eq!(part_one(&input).unwrap(), 157);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 3);
-        assert_eq!(part_two(&input), Some(70));
+        assert_eq!(part_two(&input).unwrap(), 70);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let input = random_input(&mut rng);
+            assert!(part_one(&input).is_ok(), "{input}");
+            assert!(part_two(&input).is_ok(), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = random_input(&mut rng);
+            let input = rng.mutate(&input, ITEMS);
+            let _ = part_one(&input);
+            let _ = part_two(&input);
+        }
     }
 }
@@ -1,62 +1,60 @@
-use std::cmp;
-use std::error::Error;
+use advent_of_code::helpers::Interval;
+use anyhow::Context;
 use std::str::FromStr;
 
-#[derive(Debug)]
-struct Range(u32, u32);
-
-impl FromStr for Range {
-    type Err = Box<dyn Error>;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (l1_s, l2_s) = s.split_once('-').ok_or("Cannot parse Range")?;
-        let l1 = l1_s.parse::<u32>()?;
-        let l2 = l2_s.parse::<u32>()?;
-        Ok(Self(l1, l2))
-    }
+fn parse_range(s: &str) -> anyhow::Result<Interval<u32>> {
+    let (l1_s, l2_s) = s.split_once('-').context("Cannot parse Range")?;
+    let l1 = l1_s.parse::<u32>()?;
+    let l2 = l2_s.parse::<u32>()?;
+    Interval::new(l1, l2).with_context(|| format!("Range {l1}-{l2} ends before it starts"))
 }
 
 #[derive(Debug)]
-struct ElfPair(Range, Range);
+pub struct ElfPair(Interval<u32>, Interval<u32>);
 
 impl FromStr for ElfPair {
-    type Err = Box<dyn Error>;
+    type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (l1_s, l2_s) = s.split_once(',').ok_or("Cannot parse ElfPair")?;
-        let l1 = l1_s.parse::<Range>()?;
-        let l2 = l2_s.parse::<Range>()?;
+        let (l1_s, l2_s) = s.split_once(',').context("Cannot parse ElfPair")?;
+        let l1 = parse_range(l1_s)?;
+        let l2 = parse_range(l2_s)?;
         Ok(Self(l1, l2))
     }
 }
 
 impl ElfPair {
     fn fully_contain(&self) -> bool {
-        (self.0 .0 <= self.1 .0 && self.0 .1 >= self.1 .1)
-            || (self.1 .0 <= self.0 .0 && self.1 .1 >= self.0 .1)
+        self.0.contains_interval(&self.1) || self.1.contains_interval(&self.0)
     }
 
     fn overlap(&self) -> bool {
-        cmp::max(self.0 .0, self.1 .0) <= cmp::min(self.0 .1, self.1 .1)
+        self.0.overlaps(&self.1)
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let val: u32 = input
+pub fn parse_input(input: &str) -> anyhow::Result<Vec<ElfPair>> {
+    input
         .lines()
-        .filter_map(|l| l.parse::<ElfPair>().ok())
-        .filter(ElfPair::fully_contain)
-        .count() as u32;
-    Some(val)
+        .enumerate()
+        .map(|(i, l)| {
+            l.parse::<ElfPair>()
+                .with_context(|| format!("invalid pair on line {}", i + 1))
+        })
+        .collect()
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let val: u32 = input
-        .lines()
-        .filter_map(|l| l.parse::<ElfPair>().ok())
-        .filter(ElfPair::overlap)
+pub fn part_one(input: &str) -> anyhow::Result<u32> {
+    let val: u32 = parse_input(input)?
+        .iter()
+        .filter(|p| p.fully_contain())
         .count() as u32;
-    Some(val)
+    Ok(val)
+}
+
+pub fn part_two(input: &str) -> anyhow::Result<u32> {
+    let val: u32 = parse_input(input)?.iter().filter(|p| p.overlap()).count() as u32;
+    Ok(val)
 }
 
 fn main() {
@@ -68,16 +66,53 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+
+    fn random_input(rng: &mut Rng) -> String {
+        (0..rng.range(1..=20))
+            .map(|_| {
+                let (a, b, c, d) = (0..4)
+                    .map(|_| rng.range(1..=99_u32))
+                    .collect_tuple()
+                    .unwrap();
+                format!("{}-{},{}-{}", a.min(b), a.max(b), c.min(d), c.max(d))
+            })
+            .join("\n")
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 4);
-        assert_eq!(part_one(&input), Some(2));
+        assert_eq!(part_one(&input).unwrap(), 2);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 4);
-        assert_eq!(part_two(&input), Some(4));
+        assert_eq!(part_two(&input).unwrap(), 4);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let input = random_input(&mut rng);
+            let printed = parse_input(&input)
+                .unwrap()
+                .iter()
+                .map(|ElfPair(a, b)| format!("{}-{},{}-{}", a.start(), a.end(), b.start(), b.end()))
+                .join("\n");
+            assert_eq!(printed, input);
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = random_input(&mut rng);
+            let _ = parse_input(&rng.mutate(&input, "0123456789-,\n"));
+        }
     }
 }
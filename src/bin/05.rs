@@ -1,5 +1,8 @@
-use advent_of_code::helpers::Stack;
-use std::borrow::BorrowMut;
+use advent_of_code::helpers::{Stack, StackUnderflow};
+use anyhow::{anyhow, Context};
+use itertools::Itertools;
+use std::fmt;
+use std::process;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
@@ -23,36 +26,167 @@ pub enum CraneType {
     CrateMover9001,
 }
 
-impl CraneSystem {
-    fn crate_mover_9000(&mut self, command: &Command) -> usize {
-        (0..command.quantity)
-            .filter_map(|_| {
-                let from_stack = self.stacks.get_mut(command.from_id)?;
-                let item = from_stack.borrow_mut().pop()?;
-                let to_stack = self.stacks.get_mut(command.to_id)?;
-                to_stack.push(item);
-                Some(())
-            })
-            .count()
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IllegalMove {
+    NoSuchStack(usize),
+    NotEnoughCrates(usize, StackUnderflow),
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IllegalMove::NoSuchStack(id) => write!(f, "stack {} does not exist", id + 1),
+            IllegalMove::NotEnoughCrates(id, underflow) => {
+                write!(f, "stack {}: {}", id + 1, underflow)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
+/// How a crane lifts crates off one stack and sets them down on another.
+pub trait Crane {
+    /// Takes `quantity` crates out of `stack`, which holds at least that
+    /// many. The crates are listed top first, in the order they will end up
+    /// in on the destination stack.
+    fn pick_up(&self, stack: &mut Stack<Item>, quantity: usize) -> Vec<Item>;
+
+    /// Sets `items` down in `stack`. By default they go on top, the first
+    /// one uppermost.
+    fn put_down(&self, stack: &mut Stack<Item>, items: Vec<Item>) {
+        stack.push_n(items);
+    }
+}
+
+impl Crane for CraneType {
+    fn pick_up(&self, stack: &mut Stack<Item>, quantity: usize) -> Vec<Item> {
+        let mut items = stack.pop_n(quantity);
+        // The CrateMover 9000 moves one crate at a time, reversing their order
+        if *self == CraneType::CrateMover9000 {
+            items.reverse();
+        }
+        items
+    }
+}
+
+/// A crane that can lift at most `capacity` crates at once, so larger moves
+/// are split into lifts of that many crates, starting from the top. With a
+/// capacity of one it behaves like a CrateMover 9000.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitedCrane {
+    capacity: usize,
+}
+
+impl LimitedCrane {
+    pub fn new(capacity: usize) -> Option<LimitedCrane> {
+        (capacity > 0).then_some(LimitedCrane { capacity })
+    }
+}
+
+impl Crane for LimitedCrane {
+    fn pick_up(&self, stack: &mut Stack<Item>, quantity: usize) -> Vec<Item> {
+        let lifts = stack.pop_n(quantity);
+        // Each lift lands on top of the previous one
+        lifts
+            .chunks(self.capacity)
+            .rev()
+            .flat_map(|lift| lift.iter().cloned())
+            .collect()
     }
+}
 
-    fn crate_mover_9001(&mut self, command: &Command) -> usize {
-        (|| {
-            let from_stack = self.stacks.get_mut(command.from_id)?;
-            let items = from_stack.pop_n(command.quantity);
-            let to_stack = self.stacks.get_mut(command.to_id)?;
-            let count = items.len();
-            to_stack.push_n(items);
-            Some(count)
-        })()
-        .unwrap_or(0)
+/// A crane that reaches under its source stack and pulls out the bottom
+/// crates, keeping their order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BottomCrane;
+
+impl Crane for BottomCrane {
+    fn pick_up(&self, stack: &mut Stack<Item>, quantity: usize) -> Vec<Item> {
+        stack.pop_bottom_n(quantity)
     }
+}
+
+/// A crane that lifts crates like a CrateMover 9001 but slides them in under
+/// the top `depth` crates of the destination, or at the bottom of a shorter
+/// stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlottingCrane {
+    depth: usize,
+}
 
-    pub fn apply(&mut self, crane_type: &CraneType, command: &Command) -> usize {
-        match crane_type {
-            CraneType::CrateMover9000 => self.crate_mover_9000(command),
-            CraneType::CrateMover9001 => self.crate_mover_9001(command),
+impl SlottingCrane {
+    pub fn new(depth: usize) -> SlottingCrane {
+        SlottingCrane { depth }
+    }
+}
+
+impl Crane for SlottingCrane {
+    fn pick_up(&self, stack: &mut Stack<Item>, quantity: usize) -> Vec<Item> {
+        stack.pop_n(quantity)
+    }
+
+    fn put_down(&self, stack: &mut Stack<Item>, items: Vec<Item>) {
+        stack.insert_n_at(self.depth, items);
+    }
+}
+
+impl FromStr for CraneType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "9000" | "CrateMover9000" => Ok(CraneType::CrateMover9000),
+            "9001" | "CrateMover9001" => Ok(CraneType::CrateMover9001),
+            _ => Err(anyhow!("unknown crane type {:?}", s)),
+        }
+    }
+}
+
+impl CraneSystem {
+    fn stack_mut(&mut self, id: usize) -> Result<&mut Stack<Item>, IllegalMove> {
+        self.stacks.get_mut(id).ok_or(IllegalMove::NoSuchStack(id))
+    }
+
+    /// Moves the crates for `command` with `crane`. A `partial` move takes
+    /// whatever the source stack holds when it has fewer than requested.
+    fn move_crates<C: Crane + ?Sized>(
+        &mut self,
+        crane: &C,
+        command: &Command,
+        partial: bool,
+    ) -> Result<usize, IllegalMove> {
+        self.stack_mut(command.to_id)?;
+        let from_stack = self.stack_mut(command.from_id)?;
+        if !partial {
+            from_stack
+                .peek_n(command.quantity)
+                .map_err(|underflow| IllegalMove::NotEnoughCrates(command.from_id, underflow))?;
         }
+        let quantity = command.quantity.min(from_stack.len());
+        let items = crane.pick_up(from_stack, quantity);
+        crane.put_down(self.stack_mut(command.to_id)?, items);
+        Ok(quantity)
+    }
+
+    /// Moves the crates for `command`, or leaves every stack untouched and
+    /// reports why the move is not possible.
+    pub fn apply<C: Crane + ?Sized>(
+        &mut self,
+        crane: &C,
+        command: &Command,
+    ) -> Result<usize, IllegalMove> {
+        self.move_crates(crane, command, false)
+    }
+
+    /// Like `apply`, but a source stack holding fewer crates than requested
+    /// gives up all it has. Returns how many crates moved.
+    pub fn apply_partial<C: Crane + ?Sized>(
+        &mut self,
+        crane: &C,
+        command: &Command,
+    ) -> Result<usize, IllegalMove> {
+        self.move_crates(crane, command, true)
     }
 
     pub fn top_items(&self) -> String {
@@ -66,18 +200,49 @@ impl CraneSystem {
     pub fn build(stacks_str: &[&str]) -> CraneSystem {
         let stacks = stacks_str
             .iter()
-            .map(|stack_str| {
-                let mut stack = Stack::new();
-                stack_str.chars().rev().map(Item).for_each(|i| {
-                    stack.push(i);
-                });
-                stack
-            })
+            .map(|stack_str| stack_str.chars().rev().map(Item).collect())
             .collect();
         CraneSystem { stacks }
     }
 }
 
+impl fmt::Display for CraneSystem {
+    /// Draws the stacks the way the puzzle input does, crates as `[A]` above
+    /// a row of stack numbers.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stacks = self
+            .stacks
+            .iter()
+            .map(|stack| stack.iter().rev().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let height = stacks.iter().map(Vec::len).max().unwrap_or(0);
+        for level in (0..height).rev() {
+            let row = stacks
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(item) => format!("[{}]", item.0),
+                    None => "   ".to_string(),
+                })
+                .join(" ");
+            writeln!(f, "{}", row)?;
+        }
+        let ids = (1..=stacks.len()).map(|id| format!(" {id} ")).join(" ");
+        write!(f, "{}", ids)
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.quantity,
+            self.from_id + 1,
+            self.to_id + 1
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Plan {
     initial_system: CraneSystem,
@@ -85,12 +250,120 @@ pub struct Plan {
 }
 
 impl Plan {
-    pub fn apply(self, crane_type: &CraneType) -> String {
+    pub fn apply<C: Crane + ?Sized>(self, crane: &C) -> anyhow::Result<String> {
         let mut system = self.initial_system;
-        self.rearrangement_procedure.iter().for_each(|command| {
-            system.apply(crane_type, command);
+        for (i, command) in self.rearrangement_procedure.iter().enumerate() {
+            system
+                .apply(crane, command)
+                .with_context(|| format!("illegal move on command {}", i + 1))?;
+        }
+        Ok(system.top_items())
+    }
+}
+
+/// A command that has been carried out during a replay, along with how many
+/// crates it actually moved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub index: usize,
+    pub command: Command,
+    pub moved: usize,
+}
+
+impl Step {
+    pub fn is_short(&self) -> bool {
+        self.moved < self.command.quantity
+    }
+}
+
+/// Steps through a plan one command at a time. Moves are partial, so a
+/// command asking for more crates than its source stack holds still goes
+/// ahead and shows up in `short_moves`. Every step can be undone.
+#[derive(Debug, Clone)]
+pub struct Replay<'a, C> {
+    crane: C,
+    system: CraneSystem,
+    procedure: &'a [Command],
+    steps: Vec<Step>,
+    history: Vec<CraneSystem>,
+}
+
+impl<'a, C: Crane> Replay<'a, C> {
+    pub fn new(plan: &'a Plan, crane: C) -> Replay<'a, C> {
+        Replay {
+            crane,
+            system: plan.initial_system.clone(),
+            procedure: &plan.rearrangement_procedure,
+            steps: Vec::new(),
+            history: Vec::new(),
+        }
+    }
+
+    pub fn system(&self) -> &CraneSystem {
+        &self.system
+    }
+
+    /// The steps carried out so far, oldest first.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.steps.len() == self.procedure.len()
+    }
+
+    /// Carries out the next command, returning `None` once the plan is done.
+    /// A command naming a missing stack fails and leaves the replay where it
+    /// was.
+    pub fn step(&mut self) -> Result<Option<&Step>, IllegalMove> {
+        let index = self.steps.len();
+        let Some(command) = self.procedure.get(index) else {
+            return Ok(None);
+        };
+        let previous = self.system.clone();
+        let moved = self.system.apply_partial(&self.crane, command)?;
+        self.history.push(previous);
+        self.steps.push(Step {
+            index,
+            command: command.clone(),
+            moved,
         });
-        system.top_items()
+        Ok(self.steps.last())
+    }
+
+    /// Reverts the latest step, returning `None` if there is nothing to undo.
+    pub fn undo(&mut self) -> Option<Step> {
+        let step = self.steps.pop()?;
+        self.system = self.history.pop()?;
+        Some(step)
+    }
+
+    /// Steps through the rest of the plan, returning the snapshot after each
+    /// command.
+    pub fn run(&mut self) -> anyhow::Result<Vec<CraneSystem>> {
+        let mut snapshots = Vec::new();
+        while !self.is_finished() {
+            let index = self.steps.len();
+            self.step()
+                .with_context(|| format!("illegal move on command {}", index + 1))?;
+            snapshots.push(self.system.clone());
+        }
+        Ok(snapshots)
+    }
+
+    /// The steps that moved fewer crates than their command asked for.
+    pub fn short_moves(&self) -> impl Iterator<Item = &Step> {
+        self.steps.iter().filter(|step| step.is_short())
+    }
+}
+
+impl fmt::Display for Plan {
+    /// Writes the plan in the puzzle's input format. Parsing the output gives
+    /// the same plan back, as long as there is at least one crate and one
+    /// command.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\n\n", self.initial_system)?;
+        write!(f, "{}", self.rearrangement_procedure.iter().join("\n"))
     }
 }
 
@@ -102,25 +375,117 @@ impl FromStr for Plan {
     }
 }
 
-pub fn part_one(input: &str) -> Option<String> {
-    let plan = input.parse::<Plan>().ok()?;
-    Some(plan.apply(&CraneType::CrateMover9000))
+pub fn part_one(input: &str) -> anyhow::Result<String> {
+    let plan = input.parse::<Plan>()?;
+    plan.apply(&CraneType::CrateMover9000)
+}
+
+pub fn part_two(input: &str) -> anyhow::Result<String> {
+    let plan = input.parse::<Plan>()?;
+    plan.apply(&CraneType::CrateMover9001)
 }
 
-pub fn part_two(input: &str) -> Option<String> {
-    let plan = input.parse::<Plan>().ok()?;
-    Some(plan.apply(&CraneType::CrateMover9001))
+/// Prints the stacks after every command of the plan, followed by the
+/// commands that moved fewer crates than requested.
+fn trace(input: &str, crane: impl Crane) -> anyhow::Result<()> {
+    let plan = input.parse::<Plan>()?;
+    let mut replay = Replay::new(&plan, crane);
+    println!("{}\n", replay.system());
+    let snapshots = replay.run()?;
+    for (step, snapshot) in replay.steps().iter().zip(&snapshots) {
+        println!("{}\n{}\n", step.command, snapshot);
+    }
+    let short_moves = replay.short_moves().collect::<Vec<_>>();
+    if short_moves.is_empty() {
+        println!("All commands moved the requested number of crates");
+    }
+    for step in short_moves {
+        println!(
+            "Command {} moved {} of {} crates",
+            step.index + 1,
+            step.moved,
+            step.command.quantity
+        );
+    }
+    Ok(())
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 5);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let mut args = pico_args::Arguments::from_env();
+    match args.opt_value_from_str::<_, CraneType>("--trace") {
+        Ok(None) => {
+            advent_of_code::solve!(1, part_one, input);
+            advent_of_code::solve!(2, part_two, input);
+        }
+        Ok(Some(crane_type)) => {
+            if let Err(e) = trace(input, crane_type) {
+                eprintln!("failed: {:#}", e);
+                process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            process::exit(1);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+
+    /// Random stacks, listed top crate first, and the commands of a plan.
+    fn random_plan(rng: &mut Rng) -> (Vec<String>, Vec<Command>) {
+        let stack_count = rng.range(1..=9);
+        let mut stacks: Vec<String> = (0..stack_count)
+            .map(|_| {
+                (0..rng.range(0..=6))
+                    .map(|_| rng.range(b'A'..=b'Z') as char)
+                    .collect()
+            })
+            .collect();
+        // The drawing needs at least one row of crates
+        if stacks.iter().all(String::is_empty) {
+            stacks[0].push('A');
+        }
+        let commands = (0..rng.range(1..=10))
+            .map(|_| Command {
+                quantity: rng.range(1..=6),
+                from_id: rng.range(0..=stack_count - 1),
+                to_id: rng.range(0..=stack_count - 1),
+            })
+            .collect();
+        (stacks, commands)
+    }
+
+    fn format_plan(stacks: &[String], commands: &[Command]) -> String {
+        let height = stacks.iter().map(String::len).max().unwrap_or(0);
+        let drawing = (0..height).map(|row| {
+            stacks
+                .iter()
+                .map(|stack| match stack.len().checked_sub(height - row) {
+                    Some(i) => format!("[{}]", &stack[i..=i]),
+                    None => "   ".to_string(),
+                })
+                .join(" ")
+        });
+        let separator = (1..=stacks.len()).map(|id| format!(" {id} ")).join(" ");
+        let commands = commands.iter().map(|c| {
+            format!(
+                "move {} from {} to {}",
+                c.quantity,
+                c.from_id + 1,
+                c.to_id + 1
+            )
+        });
+        drawing
+            .chain([separator, String::new()])
+            .chain(commands)
+            .join("\n")
+    }
 
     #[test]
     fn crane_system_build() {
@@ -132,14 +497,16 @@ mod tests {
     #[test]
     fn crane_system_move_one_9000() {
         let mut crane_system = CraneSystem::build(&["NZ", "DCM", "P"]);
-        crane_system.apply(
-            &CraneType::CrateMover9000,
-            &Command {
-                quantity: 1,
-                from_id: 1,
-                to_id: 0,
-            },
-        );
+        crane_system
+            .apply(
+                &CraneType::CrateMover9000,
+                &Command {
+                    quantity: 1,
+                    from_id: 1,
+                    to_id: 0,
+                },
+            )
+            .unwrap();
         dbg!(&crane_system);
         assert_eq!(crane_system.top_items(), "DCP".to_string());
     }
@@ -147,14 +514,16 @@ mod tests {
     #[test]
     fn crane_system_move_one_9001() {
         let mut crane_system = CraneSystem::build(&["NZ", "DCM", "P"]);
-        crane_system.apply(
-            &CraneType::CrateMover9001,
-            &Command {
-                quantity: 1,
-                from_id: 1,
-                to_id: 0,
-            },
-        );
+        crane_system
+            .apply(
+                &CraneType::CrateMover9001,
+                &Command {
+                    quantity: 1,
+                    from_id: 1,
+                    to_id: 0,
+                },
+            )
+            .unwrap();
         dbg!(&crane_system);
         assert_eq!(crane_system.top_items(), "DCP".to_string());
     }
@@ -162,14 +531,16 @@ mod tests {
     #[test]
     fn crane_system_move_multi_9000() {
         let mut crane_system = CraneSystem::build(&["DNZ", "CM", "P"]);
-        crane_system.apply(
-            &CraneType::CrateMover9000,
-            &Command {
-                quantity: 3,
-                from_id: 0,
-                to_id: 2,
-            },
-        );
+        crane_system
+            .apply(
+                &CraneType::CrateMover9000,
+                &Command {
+                    quantity: 3,
+                    from_id: 0,
+                    to_id: 2,
+                },
+            )
+            .unwrap();
         dbg!(&crane_system);
         assert_eq!(crane_system.top_items(), "CZ".to_string());
     }
@@ -177,28 +548,240 @@ mod tests {
     #[test]
     fn crane_system_move_multi_9001() {
         let mut crane_system = CraneSystem::build(&["DNZ", "CM", "P"]);
-        crane_system.apply(
-            &CraneType::CrateMover9001,
-            &Command {
-                quantity: 3,
-                from_id: 0,
-                to_id: 2,
-            },
-        );
+        crane_system
+            .apply(
+                &CraneType::CrateMover9001,
+                &Command {
+                    quantity: 3,
+                    from_id: 0,
+                    to_id: 2,
+                },
+            )
+            .unwrap();
         dbg!(&crane_system);
         assert_eq!(crane_system.top_items(), "CD".to_string());
     }
 
+    #[test]
+    fn crane_system_illegal_moves() {
+        let mut crane_system = CraneSystem::build(&["NZ", "DCM", "P"]);
+        let too_many = Command {
+            quantity: 3,
+            from_id: 0,
+            to_id: 2,
+        };
+        let unknown_stack = Command {
+            quantity: 1,
+            from_id: 0,
+            to_id: 3,
+        };
+        for crane_type in [CraneType::CrateMover9000, CraneType::CrateMover9001] {
+            assert_eq!(
+                crane_system.apply(&crane_type, &too_many),
+                Err(IllegalMove::NotEnoughCrates(
+                    0,
+                    StackUnderflow {
+                        requested: 3,
+                        available: 2
+                    }
+                ))
+            );
+            assert_eq!(
+                crane_system.apply(&crane_type, &unknown_stack),
+                Err(IllegalMove::NoSuchStack(3))
+            );
+        }
+        assert_eq!(crane_system, CraneSystem::build(&["NZ", "DCM", "P"]));
+    }
+
+    #[test]
+    fn limited_crane() {
+        assert_eq!(LimitedCrane::new(0), None);
+        let crane_system = CraneSystem::build(&["ABCDEFG", ""]);
+        let command = Command {
+            quantity: 5,
+            from_id: 0,
+            to_id: 1,
+        };
+        let moved = |crane: &dyn Crane| {
+            let mut crane_system = crane_system.clone();
+            crane_system.apply(crane, &command).unwrap();
+            crane_system
+        };
+        assert_eq!(
+            moved(&LimitedCrane::new(2).unwrap()),
+            CraneSystem::build(&["FG", "ECDAB"])
+        );
+        assert_eq!(
+            moved(&LimitedCrane::new(1).unwrap()),
+            moved(&CraneType::CrateMover9000)
+        );
+        assert_eq!(
+            moved(&LimitedCrane::new(5).unwrap()),
+            moved(&CraneType::CrateMover9001)
+        );
+    }
+
+    #[test]
+    fn cranes_reaching_below_the_top() {
+        let crane_system = CraneSystem::build(&["ABCDEFG", "XYZ"]);
+        let moved = |crane: &dyn Crane, quantity| {
+            let mut crane_system = crane_system.clone();
+            let command = Command {
+                quantity,
+                from_id: 0,
+                to_id: 1,
+            };
+            crane_system.apply(crane, &command).unwrap();
+            crane_system
+        };
+        assert_eq!(
+            moved(&BottomCrane, 5),
+            CraneSystem::build(&["AB", "CDEFGXYZ"])
+        );
+        assert_eq!(
+            moved(&SlottingCrane::new(2), 2),
+            CraneSystem::build(&["CDEFG", "XYABZ"])
+        );
+        assert_eq!(
+            moved(&SlottingCrane::new(0), 3),
+            moved(&CraneType::CrateMover9001, 3)
+        );
+        assert_eq!(
+            moved(&SlottingCrane::new(5), 1),
+            CraneSystem::build(&["BCDEFG", "XYZA"])
+        );
+    }
+
+    #[test]
+    fn crane_system_display() {
+        let crane_system = CraneSystem::build(&["D", "NZ", "", "CMP"]);
+        assert_eq!(
+            crane_system.to_string(),
+            "            [C]\n    [N]     [M]\n[D] [Z]     [P]\n 1   2   3   4 "
+        );
+        let input = advent_of_code::read_file("examples", 5);
+        let plan = input.parse::<Plan>().unwrap();
+        assert!(input.starts_with(&plan.initial_system.to_string()));
+    }
+
+    #[test]
+    fn plan_display_round_trip() {
+        let input = advent_of_code::read_file("examples", 5);
+        let plan = input.parse::<Plan>().unwrap();
+        assert_eq!(plan.to_string(), input);
+        let mut rng = Rng::new(5);
+        for _ in 0..100 {
+            let (stacks, commands) = random_plan(&mut rng);
+            let input = format_plan(&stacks, &commands);
+            let plan = input.parse::<Plan>().unwrap();
+            assert_eq!(plan.to_string(), input);
+            assert_eq!(plan.to_string().parse::<Plan>(), Ok(plan));
+        }
+    }
+
+    #[test]
+    fn replay_short_moves() {
+        let plan = "[A]    \n[B] [C]\n 1   2 \n\nmove 3 from 1 to 2\nmove 1 from 2 to 1"
+            .parse::<Plan>()
+            .unwrap();
+        for crane_type in [CraneType::CrateMover9000, CraneType::CrateMover9001] {
+            let mut replay = Replay::new(&plan, crane_type.clone());
+            let snapshots = replay.run().unwrap();
+            assert_eq!(snapshots.len(), 2);
+            assert!(replay.is_finished());
+            assert_eq!(replay.step(), Ok(None));
+            assert_eq!(
+                replay.short_moves().collect::<Vec<_>>(),
+                vec![&Step {
+                    index: 0,
+                    command: plan.rearrangement_procedure[0].clone(),
+                    moved: 2,
+                }]
+            );
+            let top = match crane_type {
+                CraneType::CrateMover9000 => "B",
+                CraneType::CrateMover9001 => "A",
+            };
+            assert_eq!(snapshots[0].top_items(), top);
+        }
+    }
+
+    #[test]
+    fn replay_missing_stack() {
+        let plan = "[A]\n 1 \n\nmove 1 from 1 to 1\nmove 1 from 1 to 2"
+            .parse::<Plan>()
+            .unwrap();
+        let mut replay = Replay::new(&plan, CraneType::CrateMover9000);
+        assert!(replay.step().unwrap().is_some());
+        assert_eq!(replay.step(), Err(IllegalMove::NoSuchStack(1)));
+        assert_eq!(replay.steps().len(), 1);
+        assert!(replay.run().is_err());
+    }
+
+    #[test]
+    fn replay_undo_random_plans() {
+        let mut rng = Rng::new(4);
+        for _ in 0..100 {
+            let (stacks, commands) = random_plan(&mut rng);
+            let stacks = stacks.iter().map(String::as_str).collect::<Vec<_>>();
+            let plan = Plan {
+                initial_system: CraneSystem::build(&stacks),
+                rearrangement_procedure: commands,
+            };
+            for crane_type in [CraneType::CrateMover9000, CraneType::CrateMover9001] {
+                let mut replay = Replay::new(&plan, crane_type);
+                let mut snapshots = vec![replay.system().clone()];
+                snapshots.extend(replay.run().unwrap());
+                while let Some(step) = replay.undo() {
+                    snapshots.pop();
+                    assert_eq!(replay.steps().len(), step.index);
+                    assert_eq!(Some(replay.system()), snapshots.last());
+                }
+                assert_eq!(replay.system(), &plan.initial_system);
+                assert_eq!(replay.undo(), None);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let (stacks, commands) = random_plan(&mut rng);
+            let input = format_plan(&stacks, &commands);
+            let stacks = stacks.iter().map(String::as_str).collect::<Vec<_>>();
+            assert_eq!(
+                input.parse::<Plan>(),
+                Ok(Plan {
+                    initial_system: CraneSystem::build(&stacks),
+                    rearrangement_procedure: commands,
+                }),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let (stacks, commands) = random_plan(&mut rng);
+            let input = rng.mutate(&format_plan(&stacks, &commands), "[] 0123456789\n");
+            let _ = input.parse::<Plan>();
+        }
+    }
+
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 5);
-        assert_eq!(part_one(&input), Some("CMZ".to_string()));
+        assert_eq!(part_one(&input).unwrap(), "CMZ".to_string());
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 5);
-        assert_eq!(part_two(&input), Some("MCD".to_string()));
+        assert_eq!(part_two(&input).unwrap(), "MCD".to_string());
     }
 }
 
@@ -257,6 +840,10 @@ mod plan_parser {
         combinator::map_res(character::complete::digit1, str::parse::<usize>)(input)
     }
 
+    fn stack_id(input: &str) -> IResult<&str, usize> {
+        combinator::map_opt(number, |id| id.checked_sub(1))(input)
+    }
+
     fn command(input: &str) -> IResult<&str, Command> {
         let parser = sequence::tuple((
             bytes::complete::tag("move"),
@@ -265,16 +852,16 @@ mod plan_parser {
             character::complete::space1,
             bytes::complete::tag("from"),
             character::complete::space1,
-            number,
+            stack_id,
             character::complete::space1,
             bytes::complete::tag("to"),
             character::complete::space1,
-            number,
+            stack_id,
         ));
         combinator::map(parser, |(_, _, cnt, _, _, _, frm, _, _, _, t)| Command {
             quantity: cnt,
-            from_id: frm - 1,
-            to_id: t - 1,
+            from_id: frm,
+            to_id: t,
         })(input)
     }
 
@@ -357,5 +944,6 @@ mod plan_parser {
                 }
             ))
         );
+        assert!(command("move 3 from 0 to 3").is_err());
     }
 }
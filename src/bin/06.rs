@@ -1,20 +1,91 @@
-use itertools::Itertools;
+use anyhow::Context;
+use std::collections::VecDeque;
+use std::io::{self, Read};
+
+/// Finds markers, runs of `window` distinct bytes, in a stream that arrives
+/// in pieces. Each byte is handled in constant time by keeping a count of
+/// every byte value in the current window along with the number of values
+/// seen more than once.
+#[derive(Debug, Clone)]
+pub struct MarkerDetector {
+    window: usize,
+    recent: VecDeque<u8>,
+    counts: [usize; 256],
+    duplicates: usize,
+    position: usize,
+}
+
+impl MarkerDetector {
+    /// Returns `None` for an empty window, which cannot mark anything.
+    pub fn new(window: usize) -> Option<MarkerDetector> {
+        (window > 0).then(|| MarkerDetector {
+            window,
+            recent: VecDeque::with_capacity(window),
+            counts: [0; 256],
+            duplicates: 0,
+            position: 0,
+        })
+    }
+
+    /// The number of bytes consumed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Consumes one byte. Returns the position just past it if it completes
+    /// a marker.
+    pub fn push(&mut self, byte: u8) -> Option<usize> {
+        if self.recent.len() == self.window {
+            let old = self.recent.pop_front()?;
+            self.counts[old as usize] -= 1;
+            if self.counts[old as usize] == 1 {
+                self.duplicates -= 1;
+            }
+        }
+        self.recent.push_back(byte);
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.duplicates += 1;
+        }
+        self.position += 1;
+        (self.recent.len() == self.window && self.duplicates == 0).then_some(self.position)
+    }
+
+    /// Consumes a chunk of the stream, yielding the end position of every
+    /// marker completed in it.
+    pub fn feed<'a>(&'a mut self, bytes: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        bytes.iter().filter_map(move |&byte| self.push(byte))
+    }
+
+    /// Consumes the rest of `reader`, returning the end position of every
+    /// marker completed in it.
+    pub fn read(&mut self, mut reader: impl Read) -> io::Result<Vec<usize>> {
+        let mut buffer = [0; 8192];
+        let mut markers = Vec::new();
+        loop {
+            let n = match reader.read(&mut buffer) {
+                Ok(0) => return Ok(markers),
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            markers.extend(self.feed(&buffer[..n]));
+        }
+    }
+}
 
 fn find_signal(marker_count: usize, input: &str) -> Option<u32> {
-    let (enum_count, _) = input
-        .as_bytes()
-        .windows(marker_count)
-        .enumerate()
-        .find(|(_, s)| s.iter().all_unique())?;
-    Some((enum_count + marker_count) as u32)
+    let mut detector = MarkerDetector::new(marker_count)?;
+    let end = detector.feed(input.as_bytes()).next()?;
+    Some(end as u32)
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    find_signal(4, input)
+pub fn part_one(input: &str) -> anyhow::Result<u32> {
+    find_signal(4, input).context("no start-of-packet marker found")
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    find_signal(14, input)
+pub fn part_two(input: &str) -> anyhow::Result<u32> {
+    find_signal(14, input).context("no start-of-message marker found")
 }
 
 fn main() {
@@ -26,16 +97,85 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+
+    fn random_input(rng: &mut Rng) -> String {
+        let alphabet = rng.range(b'c'..=b'z');
+        (0..rng.range(1..=200))
+            .map(|_| rng.range(b'a'..=alphabet) as char)
+            .collect()
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 6);
-        assert_eq!(part_one(&input), Some(10));
+        assert_eq!(part_one(&input).unwrap(), 10);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 6);
-        assert_eq!(part_two(&input), Some(29));
+        assert_eq!(part_two(&input).unwrap(), 29);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let input = random_input(&mut rng);
+            for marker_count in [4, 14] {
+                let windows = input.as_bytes().windows(marker_count).collect::<Vec<_>>();
+                match find_signal(marker_count, &input) {
+                    Some(end) => {
+                        let first = end as usize - marker_count;
+                        assert!(windows[first].iter().all_unique(), "{input}");
+                        assert!(!windows[..first].iter().any(|w| w.iter().all_unique()));
+                    }
+                    None => assert!(!windows.iter().any(|w| w.iter().all_unique())),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn detector_finds_every_marker() {
+        let mut rng = Rng::new(4);
+        for _ in 0..100 {
+            let input = random_input(&mut rng);
+            let bytes = input.as_bytes();
+            for window in [1, 2, 4, 14] {
+                let expected = bytes
+                    .windows(window)
+                    .enumerate()
+                    .filter(|(_, w)| w.iter().all_unique())
+                    .map(|(i, _)| i + window)
+                    .collect::<Vec<_>>();
+                let mut detector = MarkerDetector::new(window).unwrap();
+                let mut markers = Vec::new();
+                let mut rest = bytes;
+                while !rest.is_empty() {
+                    let (chunk, tail) = rest.split_at(rng.range(1..=rest.len()));
+                    markers.extend(detector.feed(chunk));
+                    rest = tail;
+                }
+                assert_eq!(markers, expected, "{input}");
+                assert_eq!(detector.position(), bytes.len());
+                let mut detector = MarkerDetector::new(window).unwrap();
+                assert_eq!(detector.read(bytes).unwrap(), expected);
+            }
+        }
+        assert!(MarkerDetector::new(0).is_none());
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = random_input(&mut rng);
+            let input = rng.mutate(&input, "abcdefghijklmnopqrstuvwxyz");
+            let _ = part_one(&input);
+            let _ = part_two(&input);
+        }
     }
 }
@@ -1,7 +1,40 @@
+use anyhow::{bail, Context};
+use input_parser::{Command, Line, LsResult};
 use std::collections::HashMap;
+use std::fmt::Write;
+use std::process;
 
 type Path = Vec<String>;
 
+const DISK_CAPACITY: usize = 70000000;
+const UPDATE_SIZE: usize = 30000000;
+/// The most memory `smallest_deletion_set` may use, in bytes.
+const CLEANUP_MEMORY_LIMIT: usize = 1 << 30;
+
+fn format_path(path: &[String]) -> String {
+    format!("/{}", path.join("/"))
+}
+
+/// Formats a size the way `du -h` does: rounded up, in powers of 1024, with
+/// one decimal below 10 of a unit.
+fn human_size(size: usize) -> String {
+    let mut value = size as f64;
+    let mut units = ["K", "M", "G", "T", "P"].iter();
+    let mut unit = "";
+    while value.ceil() >= 1024.0 {
+        let Some(next) = units.next() else { break };
+        value /= 1024.0;
+        unit = next;
+    }
+    if unit.is_empty() {
+        size.to_string()
+    } else if (value * 10.0).ceil() < 100.0 {
+        format!("{:.1}{}", (value * 10.0).ceil() / 10.0, unit)
+    } else {
+        format!("{}{}", value.ceil(), unit)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum Content {
     File(usize),
@@ -11,170 +44,1000 @@ enum Content {
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 struct Directory {
     contents: HashMap<String, Content>,
+    /// Total size of everything below, filled in by `compute_sizes`.
+    size: usize,
+    /// Whether an `ls` or `mkdir` has shown all of its contents.
+    listed: bool,
 }
 
 impl Directory {
     fn new() -> Directory {
         let contents = HashMap::new();
-        Directory { contents }
+        Directory {
+            contents,
+            size: 0,
+            listed: false,
+        }
     }
 
-    fn add_file(&mut self, path: &[String], filename: &str, size: usize) {
-        match path.first() {
-            Some(dir_name) => {
-                if let Some(Content::Directory(directory)) = self.contents.get_mut(dir_name) {
-                    directory.add_file(&path[1..], filename, size);
-                }
-            }
+    /// Records a file seen in a listing. Listing it again is fine as long as
+    /// the size matches.
+    fn add_file(&mut self, filename: &str, size: usize) -> anyhow::Result<()> {
+        match self.contents.get(filename) {
             None => {
                 self.contents
                     .insert(filename.to_string(), Content::File(size));
             }
+            Some(Content::File(old_size)) if *old_size == size => {}
+            Some(Content::File(old_size)) => {
+                bail!(
+                    "file {} listed with sizes {} and {}",
+                    filename,
+                    old_size,
+                    size
+                )
+            }
+            Some(Content::Directory(_)) => {
+                bail!("{} listed as both a directory and a file", filename)
+            }
         }
+        Ok(())
     }
 
-    fn add_directory(&mut self, path: &[String], name: &str) {
-        match path.first() {
-            Some(dir_name) => {
-                if let Some(Content::Directory(directory)) = self.contents.get_mut(dir_name) {
-                    directory.add_directory(&path[1..], name);
-                }
-            }
+    /// Records a directory seen in a listing, keeping whatever is already
+    /// known about its contents.
+    fn add_directory(&mut self, name: &str) -> anyhow::Result<()> {
+        match self.contents.get(name) {
             None => {
                 self.contents
                     .insert(name.to_string(), Content::Directory(Directory::new()));
             }
+            Some(Content::Directory(_)) => {}
+            Some(Content::File(_)) => bail!("{} listed as both a file and a directory", name),
         }
+        Ok(())
     }
 
-    fn size(&self, path: &[String]) -> usize {
+    fn directory(&self, path: &[String]) -> Option<&Directory> {
+        match path.first() {
+            Some(dir_name) => match self.contents.get(dir_name) {
+                Some(Content::Directory(directory)) => directory.directory(&path[1..]),
+                _ => None,
+            },
+            None => Some(self),
+        }
+    }
+
+    fn directory_mut(&mut self, path: &[String]) -> Option<&mut Directory> {
         match path.first() {
-            Some(dir_name) => {
-                if let Some(Content::Directory(directory)) = self.contents.get(dir_name) {
-                    directory.size(&path[1..])
-                } else {
-                    0
+            Some(dir_name) => match self.contents.get_mut(dir_name) {
+                Some(Content::Directory(directory)) => directory.directory_mut(&path[1..]),
+                _ => None,
+            },
+            None => Some(self),
+        }
+    }
+
+    /// Every directory below this one along with its path, in alphabetical
+    /// order with parents before their children.
+    fn subdirectories(&self) -> Vec<(Path, &Directory)> {
+        let mut directories = Vec::new();
+        self.collect_subdirectories(&mut Vec::new(), &mut directories);
+        directories
+    }
+
+    fn collect_subdirectories<'a>(
+        &'a self,
+        path: &mut Path,
+        directories: &mut Vec<(Path, &'a Directory)>,
+    ) {
+        let mut names = self.contents.keys().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            if let Some(Content::Directory(directory)) = self.contents.get(name) {
+                path.push(name.clone());
+                directories.push((path.clone(), directory));
+                directory.collect_subdirectories(path, directories);
+                path.pop();
+            }
+        }
+    }
+
+    /// Writes the contents in alphabetical order, one `- name (...)` line
+    /// each, going at most `max_depth` levels further down.
+    fn write_tree(&self, out: &mut String, indent: usize, max_depth: Option<usize>) {
+        if max_depth == Some(0) {
+            return;
+        }
+        let mut names = self.contents.keys().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            let pad = "  ".repeat(indent);
+            match &self.contents[name] {
+                Content::File(size) => {
+                    let _ = writeln!(out, "{pad}- {name} (file, size={size})");
+                }
+                Content::Directory(directory) => {
+                    let _ = writeln!(out, "{pad}- {name} (dir)");
+                    directory.write_tree(out, indent + 1, max_depth.map(|d| d - 1));
                 }
             }
-            None => self
-                .contents
-                .values()
-                .map(|c| match c {
-                    Content::Directory(dir) => dir.size(path),
-                    Content::File(size) => *size,
-                })
-                .sum(),
         }
     }
+
+    /// Fills in the size of this directory and of every one below it in a
+    /// single post-order pass.
+    fn compute_sizes(&mut self) -> usize {
+        self.size = self
+            .contents
+            .values_mut()
+            .map(|c| match c {
+                Content::Directory(dir) => dir.compute_sizes(),
+                Content::File(size) => *size,
+            })
+            .sum();
+        self.size
+    }
+
+    /// The cached size of the directory at `path`, or 0 if there is none.
+    fn size(&self, path: &[String]) -> usize {
+        self.directory(path).map_or(0, |directory| directory.size)
+    }
+
+    /// Whether the contents of this directory and of every one below it are
+    /// known, so that its size is exact rather than a lower bound.
+    fn fully_listed(&self) -> bool {
+        self.listed
+            && self.contents.values().all(|c| match c {
+                Content::Directory(directory) => directory.fully_listed(),
+                Content::File(_) => true,
+            })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 struct FileSystem {
     root_directory: Directory,
-    directory_paths: Vec<Path>,
+    working_directory: Path,
 }
 
 impl FileSystem {
-    fn build(input: input_parser::Input) -> FileSystem {
-        let mut path_marker: Path = Vec::new();
-        let mut root_directory: Directory = Directory::new();
-        let mut directory_paths: Vec<Path> = Vec::new();
-        for line in input.lines {
+    /// Replays a terminal log, failing on the first line that contradicts
+    /// what the log has shown so far. Sizes printed by `du` are checked once
+    /// the whole log is known, unless a later `rm` changed them.
+    fn build(input: input_parser::Input) -> anyhow::Result<FileSystem> {
+        let mut fs = FileSystem::default();
+        let mut reported_usage = Vec::new();
+        for (i, line) in input.lines.iter().enumerate() {
+            fs.run(line)
+                .with_context(|| format!("inconsistent log on line {}", i + 1))?;
             match line {
-                input_parser::Line::Command(input_parser::Command::Cd(path)) => match path.as_str()
-                {
-                    "/" => {
-                        path_marker.clear();
-                    }
-                    ".." => {
-                        path_marker.pop();
-                    }
-                    _ => {
-                        path_marker.push(path);
-                    }
-                },
-                input_parser::Line::Command(input_parser::Command::Ls) => {}
-                input_parser::Line::Output(input_parser::LsResult::File(input_parser::File {
-                    filename,
-                    size,
-                })) => {
-                    root_directory.add_file(&path_marker, &filename, size);
+                Line::DiskUsage(size, path) => reported_usage.push((i, fs.resolve(path), *size)),
+                // Removing a path changes the usage of its parents and itself
+                Line::Command(Command::Rm(path)) => {
+                    let removed = fs.resolve(path);
+                    reported_usage.retain(|(_, path, _)| {
+                        !removed.starts_with(path) && !path.starts_with(&removed)
+                    });
                 }
-                input_parser::Line::Output(input_parser::LsResult::Directory(
-                    input_parser::Directory { name },
-                )) => {
-                    root_directory.add_directory(&path_marker, &name);
-
-                    let mut new_directory = path_marker.clone();
-                    new_directory.push(name.clone());
-                    directory_paths.push(new_directory);
+                _ => {}
+            }
+        }
+        fs.root_directory.compute_sizes();
+        for (i, path, size) in reported_usage {
+            fs.check_usage(&path, size)
+                .with_context(|| format!("inconsistent log on line {}", i + 1))?;
+        }
+        Ok(fs)
+    }
+
+    /// Compares a size printed by `du` with the listings. Directories that
+    /// were never fully listed may hold more than the log shows, and ones
+    /// that were never listed at all can't be checked.
+    fn check_usage(&self, path: &[String], reported: usize) -> anyhow::Result<()> {
+        let Some(directory) = self.root_directory.directory(path) else {
+            return Ok(());
+        };
+        if directory.size > reported || (directory.fully_listed() && directory.size != reported) {
+            bail!(
+                "du printed {} for {}, but its listings add up to {}",
+                reported,
+                format_path(path),
+                directory.size
+            );
+        }
+        Ok(())
+    }
+
+    /// Turns `path`, absolute or relative to the working directory, into the
+    /// path from `/`. Going up from `/` stays there, as in a shell.
+    fn resolve(&self, path: &str) -> Path {
+        let mut resolved = match path.starts_with('/') {
+            true => Vec::new(),
+            false => self.working_directory.clone(),
+        };
+        for segment in path.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => {
+                    resolved.pop();
                 }
+                name => resolved.push(name.to_string()),
             }
         }
-        FileSystem {
-            root_directory,
-            directory_paths,
+        resolved
+    }
+
+    fn existing_directory_mut(&mut self, path: &[String]) -> anyhow::Result<&mut Directory> {
+        self.root_directory
+            .directory_mut(path)
+            .with_context(|| format!("no such directory {}", format_path(path)))
+    }
+
+    /// The working directory, which fails if it has been removed since `cd`.
+    fn working_directory_mut(&mut self) -> anyhow::Result<&mut Directory> {
+        self.existing_directory_mut(&self.working_directory.clone())
+    }
+
+    /// The directory holding `path` and the name of `path` within it.
+    fn parent_mut(&mut self, path: &str) -> anyhow::Result<(&mut Directory, String)> {
+        let path = self.resolve(path);
+        let Some((name, parent)) = path.split_last() else {
+            bail!("cannot change /");
+        };
+        Ok((self.existing_directory_mut(parent)?, name.clone()))
+    }
+
+    fn run(&mut self, line: &Line) -> anyhow::Result<()> {
+        match line {
+            Line::Command(Command::Cd(path)) => {
+                let path = self.resolve(path);
+                self.existing_directory_mut(&path)?;
+                self.working_directory = path;
+            }
+            Line::Command(Command::Ls) => {
+                self.working_directory_mut()?.listed = true;
+            }
+            Line::Command(Command::Pwd) => {}
+            Line::Command(Command::Mkdir(path)) => {
+                let (parent, name) = self.parent_mut(path)?;
+                if parent.contents.contains_key(&name) {
+                    bail!("{} already exists", path);
+                }
+                let directory = Directory {
+                    listed: true,
+                    ..Directory::new()
+                };
+                parent.contents.insert(name, Content::Directory(directory));
+            }
+            Line::Command(Command::Rm(path)) => {
+                let (parent, name) = self.parent_mut(path)?;
+                if parent.contents.remove(&name).is_none() {
+                    bail!("no such file or directory {}", path);
+                }
+            }
+            Line::Command(Command::Du(path)) => {
+                let path = self.resolve(path.as_deref().unwrap_or("."));
+                self.existing_directory_mut(&path)?;
+            }
+            Line::Output(LsResult::File(input_parser::File { filename, size })) => {
+                self.working_directory_mut()?.add_file(filename, *size)?;
+            }
+            Line::Output(LsResult::Directory(input_parser::Directory { name })) => {
+                self.working_directory_mut()?.add_directory(name)?;
+            }
+            Line::WorkingDirectory(path) => {
+                let cwd = format_path(&self.working_directory);
+                if *path != cwd {
+                    bail!("pwd printed {} in {}", path, cwd);
+                }
+            }
+            // Usage depends on listings that may only come later, so `build`
+            // checks it at the end
+            Line::DiskUsage(..) => {}
         }
+        Ok(())
+    }
+
+    /// Draws the tree the way the puzzle text does, showing directories at
+    /// most `max_depth` levels below `/`.
+    fn tree(&self, max_depth: Option<usize>) -> String {
+        let mut out = "- / (dir)\n".to_string();
+        self.root_directory.write_tree(&mut out, 1, max_depth);
+        out
+    }
+
+    /// Every directory at most `max_depth` levels below `/`, including `/`
+    /// itself, with its size. The largest come first, ties in path order.
+    fn disk_usage(&self, max_depth: Option<usize>) -> Vec<(Path, usize)> {
+        let mut usage = self
+            .root_directory
+            .subdirectories()
+            .into_iter()
+            .filter(|(path, _)| max_depth.map_or(true, |depth| path.len() <= depth))
+            .map(|(path, directory)| (path, directory.size))
+            .collect::<Vec<_>>();
+        usage.push((Vec::new(), self.root_directory.size));
+        usage.sort_by(|(p1, s1), (p2, s2)| s2.cmp(s1).then_with(|| p1.cmp(p2)));
+        usage
+    }
+
+    /// Lists `disk_usage` like `du -h` does, a size and path per line.
+    fn du(&self, max_depth: Option<usize>) -> String {
+        self.disk_usage(max_depth)
+            .iter()
+            .map(|(path, size)| format!("{}\t{}\n", human_size(*size), format_path(path)))
+            .collect()
     }
 
     fn sum_dir_sizes_below(&self, threshold: usize) -> usize {
-        self.directory_paths
+        self.root_directory
+            .subdirectories()
             .iter()
-            .map(|path| self.root_directory.size(path))
+            .map(|(_, directory)| directory.size)
             .filter(|size| *size <= threshold)
             .sum()
     }
 
+    /// Picks directories to delete so that at least `needed_space` is free
+    /// on a disk of `fs_capacity`, or `None` if the files do not even fit
+    /// or no choice frees enough. `/` itself is never deleted. Fails if the
+    /// plan would take too much memory to work out.
+    fn plan_cleanup(
+        &self,
+        fs_capacity: usize,
+        needed_space: usize,
+        mode: CleanupMode,
+    ) -> anyhow::Result<Option<CleanupPlan>> {
+        let Some(free_space) = fs_capacity.checked_sub(self.root_directory.size(&[])) else {
+            return Ok(None);
+        };
+        let needed_space = needed_space.saturating_sub(free_space);
+        let directories = self.root_directory.subdirectories();
+        let selection = match mode {
+            _ if needed_space == 0 => Some(Vec::new()),
+            CleanupMode::SingleDirectory => directories
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, directory))| directory.size >= needed_space)
+                .min_by_key(|(_, (_, directory))| directory.size)
+                .map(|(index, _)| vec![index]),
+            CleanupMode::SmallestSet => smallest_deletion_set(&directories, needed_space)?,
+        };
+        let Some(selection) = selection else {
+            return Ok(None);
+        };
+        let freed = selection.iter().map(|&i| directories[i].1.size).sum();
+        let directories = selection
+            .into_iter()
+            .map(|i| directories[i].0.clone())
+            .collect();
+        Ok(Some(CleanupPlan { directories, freed }))
+    }
+
     fn best_deletable_directory_size(
         &self,
         fs_capacity: usize,
         needed_space: usize,
-    ) -> Option<usize> {
-        let free_space = fs_capacity - self.root_directory.size(&[]);
-        let needed_space = needed_space - free_space;
-        self.directory_paths
-            .iter()
-            .map(|path| self.root_directory.size(path))
-            .filter(|size| *size >= needed_space)
-            .min()
+    ) -> anyhow::Result<Option<usize>> {
+        let plan = self.plan_cleanup(fs_capacity, needed_space, CleanupMode::SingleDirectory)?;
+        Ok(plan.map(|plan| plan.freed))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CleanupMode {
+    /// The smallest single directory that frees enough space
+    SingleDirectory,
+    /// The directories, none inside another, that free enough space with the
+    /// smallest total size
+    SmallestSet,
+}
+
+impl std::str::FromStr for CleanupMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "single" => Ok(CleanupMode::SingleDirectory),
+            "set" => Ok(CleanupMode::SmallestSet),
+            _ => bail!("unknown cleanup mode {:?}, expected single or set", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CleanupPlan {
+    directories: Vec<Path>,
+    freed: usize,
+}
+
+/// `bits` moved up by `shift` places, dropping those that reach `len`.
+fn shifted_bits(bits: &[u64], shift: usize, len: usize) -> Vec<u64> {
+    let (words, offset) = (shift / 64, shift % 64);
+    let mut shifted = vec![0; bits.len()];
+    for i in words..bits.len() {
+        shifted[i] = bits[i - words] << offset;
+        if offset > 0 && i > words {
+            shifted[i] |= bits[i - words - 1] >> (64 - offset);
+        }
+    }
+    if len % 64 != 0 {
+        if let Some(last) = shifted.last_mut() {
+            *last &= (1 << (len % 64)) - 1;
+        }
+    }
+    shifted
+}
+
+/// The smallest set bit at or after `from`.
+fn next_bit(bits: &[u64], from: usize) -> Option<usize> {
+    let first = from / 64;
+    let masked = bits.get(first)? & (u64::MAX << (from % 64));
+    std::iter::once(masked)
+        .chain(bits[first + 1..].iter().copied())
+        .enumerate()
+        .find(|(_, word)| *word != 0)
+        .map(|(i, word)| (first + i) * 64 + word.trailing_zeros() as usize)
+}
+
+/// The directories, none inside another, whose sizes add up to at least
+/// `needed_space` with the smallest total, as indices into `directories`.
+///
+/// `directories` lists them in pre-order, so deleting one means skipping
+/// ahead past its subdirectories. Going through them in order, a bitset holds
+/// the totals short of `needed_space` that the directories already passed
+/// can free. Passing a directory never takes a total away, so remembering
+/// which directory first made each total reachable is enough to rebuild the
+/// set afterwards.
+///
+/// This is pseudo-polynomial: it takes about 4 bytes of memory per byte of
+/// `needed_space`, plus a bit per byte for every level of nesting. Sizes are
+/// counted in multiples of their greatest common divisor to keep that down,
+/// and it fails rather than use more than `CLEANUP_MEMORY_LIMIT`.
+fn smallest_deletion_set(
+    directories: &[(Path, &Directory)],
+    needed_space: usize,
+) -> anyhow::Result<Option<Vec<usize>>> {
+    let deletable: usize = directories
+        .iter()
+        .filter(|(path, _)| path.len() == 1)
+        .map(|(_, directory)| directory.size)
+        .sum();
+    if needed_space > deletable {
+        return Ok(None);
+    }
+    // Only multiples of `unit` can be freed, so `needed_space` rounds up
+    let unit = directories
+        .iter()
+        .fold(0, |unit, (_, directory)| {
+            num::integer::gcd(unit, directory.size)
+        })
+        .max(1);
+    let sizes = directories
+        .iter()
+        .map(|(_, directory)| directory.size / unit)
+        .collect::<Vec<_>>();
+    let needed_space = needed_space / unit + usize::from(needed_space % unit != 0);
+    let depth = directories.iter().map(|(path, _)| path.len()).max();
+    let memory = (needed_space / 64 + 1)
+        .saturating_mul(8)
+        .saturating_mul(depth.unwrap_or(0) + 1)
+        .saturating_add(needed_space.saturating_mul(4));
+    if memory > CLEANUP_MEMORY_LIMIT {
+        bail!(
+            "finding the smallest set to free {} bytes would take {} bytes of memory",
+            needed_space.saturating_mul(unit),
+            memory
+        );
+    }
+    let count = directories.len();
+    let mut ends = vec![count; count];
+    let mut open: Vec<usize> = Vec::new();
+    for (i, (path, _)) in directories.iter().enumerate() {
+        while let Some(&parent) = open.last() {
+            if path.starts_with(&directories[parent].0) {
+                break;
+            }
+            ends[parent] = i;
+            open.pop();
+        }
+        open.push(i);
+    }
+    let mut reachable = vec![0_u64; (needed_space + 63) / 64];
+    reachable[0] = 1;
+    let mut first_reached_by = vec![u32::MAX; needed_space];
+    // Totals that deleting a directory adds once its subdirectories are
+    // passed. Those of the innermost directories are on top.
+    let mut pending: Vec<(usize, u32, Vec<u64>)> = Vec::new();
+    let mut best: Option<(usize, usize, usize)> = None;
+    for position in 0..=count {
+        while pending.last().map_or(false, |(end, _, _)| *end == position) {
+            let Some((_, index, totals)) = pending.pop() else {
+                break;
+            };
+            for (word, (reached, added)) in reachable.iter_mut().zip(totals).enumerate() {
+                let mut new = added & !*reached;
+                while new != 0 {
+                    first_reached_by[word * 64 + new.trailing_zeros() as usize] = index;
+                    new &= new - 1;
+                }
+                *reached |= added;
+            }
+        }
+        if position == count || best.map_or(false, |(total, _, _)| total == needed_space) {
+            break;
+        }
+        let size = sizes[position];
+        if let Some(base) = next_bit(&reachable, needed_space.saturating_sub(size)) {
+            if best.map_or(true, |(total, _, _)| base + size < total) {
+                best = Some((base + size, position, base));
+            }
+        }
+        if size < needed_space {
+            let totals = shifted_bits(&reachable, size, needed_space);
+            pending.push((ends[position], position as u32, totals));
+        }
     }
+    let Some((_, last, mut base)) = best else {
+        return Ok(None);
+    };
+    let mut selection = vec![last];
+    while base > 0 {
+        let index = first_reached_by[base] as usize;
+        selection.push(index);
+        base -= sizes[index];
+    }
+    selection.sort_unstable();
+    Ok(Some(selection))
+}
+
+pub fn part_one(input: &str) -> anyhow::Result<usize> {
+    let fs = FileSystem::build(input.parse::<input_parser::Input>()?)?;
+    Ok(fs.sum_dir_sizes_below(100000))
+}
+
+pub fn part_two(input: &str) -> anyhow::Result<usize> {
+    let fs = FileSystem::build(input.parse::<input_parser::Input>()?)?;
+    fs.best_deletable_directory_size(DISK_CAPACITY, UPDATE_SIZE)?
+        .context("no directory frees enough space")
+}
+
+struct Args {
+    tree: bool,
+    du: bool,
+    max_depth: Option<usize>,
+    cleanup: Option<CleanupMode>,
+    capacity: usize,
+    needed: usize,
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
-    let fs = FileSystem::build(input.parse::<input_parser::Input>().ok()?);
-    Some(fs.sum_dir_sizes_below(100000))
+fn parse_args() -> anyhow::Result<Args> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        tree: args.contains("--tree"),
+        du: args.contains("--du"),
+        max_depth: args.opt_value_from_str("--depth")?,
+        cleanup: args.opt_value_from_str("--cleanup")?,
+        capacity: args
+            .opt_value_from_str("--capacity")?
+            .unwrap_or(DISK_CAPACITY),
+        needed: args.opt_value_from_str("--needed")?.unwrap_or(UPDATE_SIZE),
+    })
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
-    let fs = FileSystem::build(input.parse::<input_parser::Input>().ok()?);
-    fs.best_deletable_directory_size(70000000, 30000000)
+/// Prints the views of the file system asked for on the command line.
+fn report(input: &str, args: &Args) -> anyhow::Result<()> {
+    let fs = FileSystem::build(input.parse()?)?;
+    if args.tree {
+        print!("{}", fs.tree(args.max_depth));
+    }
+    if args.du {
+        print!("{}", fs.du(args.max_depth));
+    }
+    if let Some(mode) = args.cleanup {
+        let plan = fs
+            .plan_cleanup(args.capacity, args.needed, mode)?
+            .context("no cleanup frees enough space")?;
+        for path in &plan.directories {
+            println!("rm {}", format_path(path));
+        }
+        println!("frees {}", plan.freed);
+    }
+    Ok(())
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 7);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {:#}", e);
+            process::exit(1);
+        }
+    };
+    if !args.tree && !args.du && args.cleanup.is_none() {
+        advent_of_code::solve!(1, part_one, input);
+        advent_of_code::solve!(2, part_two, input);
+    } else if let Err(e) = report(input, &args) {
+        eprintln!("failed: {:#}", e);
+        process::exit(1);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+
+    /// Appends the terminal output of listing the current directory and
+    /// walking into each subdirectory, returning the size of all files seen.
+    fn random_session(rng: &mut Rng, depth: usize, lines: &mut Vec<String>) -> usize {
+        lines.push("$ ls".to_string());
+        let dirs = (0..rng.range(0..=3_usize).min(depth))
+            .map(|i| format!("d{i}"))
+            .collect::<Vec<_>>();
+        for dir in &dirs {
+            lines.push(format!("dir {dir}"));
+        }
+        let mut total = 0;
+        for i in 0..rng.range(0..=4) {
+            let size = rng.range(1..=300_000_usize);
+            lines.push(format!("{size} f{i}.txt"));
+            total += size;
+        }
+        for dir in dirs {
+            lines.push(format!("$ cd {dir}"));
+            total += random_session(rng, depth - 1, lines);
+            lines.push("$ cd ..".to_string());
+        }
+        total
+    }
+
+    fn random_input(rng: &mut Rng) -> (String, usize) {
+        let mut lines = vec!["$ cd /".to_string()];
+        let total = random_session(rng, 3, &mut lines);
+        (lines.join("\n"), total)
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 7);
-        assert_eq!(part_one(&input), Some(95437));
+        assert_eq!(part_one(&input).unwrap(), 95437);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 7);
-        assert_eq!(part_two(&input), Some(24933642));
+        assert_eq!(part_two(&input).unwrap(), 24933642);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let (input, total) = random_input(&mut rng);
+            let fs = FileSystem::build(input.parse().unwrap()).unwrap();
+            assert_eq!(fs.root_directory.size(&[]), total, "{input}");
+        }
+    }
+
+    fn walked_size(directory: &Directory) -> usize {
+        directory
+            .contents
+            .values()
+            .map(|c| match c {
+                Content::Directory(dir) => walked_size(dir),
+                Content::File(size) => *size,
+            })
+            .sum()
+    }
+
+    #[test]
+    fn cached_sizes_match_walked_sizes() {
+        let mut rng = Rng::new(4);
+        for _ in 0..100 {
+            let (input, _) = random_input(&mut rng);
+            let fs = FileSystem::build(input.parse().unwrap()).unwrap();
+            for (path, directory) in fs.root_directory.subdirectories() {
+                assert_eq!(directory.size, walked_size(directory));
+                assert_eq!(fs.root_directory.size(&path), directory.size);
+            }
+            assert_eq!(fs.root_directory.size(&["missing".to_string()]), 0);
+        }
+    }
+
+    fn build(lines: &[&str]) -> anyhow::Result<FileSystem> {
+        FileSystem::build(lines.join("\n").parse()?)
+    }
+
+    #[test]
+    fn shell_session() {
+        let fs = build(&[
+            "$ cd /",
+            "$ mkdir a",
+            "$ cd a/",
+            "$ mkdir b",
+            "$ cd ./b",
+            "$ ls",
+            "10 x.txt",
+            "dir c",
+            "$ cd c/../../..",
+            "$ pwd",
+            "/",
+            "$ cd /a/b/c",
+            "$ ls",
+            "20 y.txt",
+            "5 z.txt",
+            "$ du ..",
+            "25 /a/b/c",
+            "35 /a/b",
+            "$ rm z.txt",
+            "$ pwd",
+            "/a/b/c",
+            "$ cd ../../..",
+            "$ ls",
+            "dir a",
+            "dir d",
+            "$ rm d",
+        ])
+        .unwrap();
+        let path = |p: &str| fs.resolve(p);
+        assert_eq!(fs.working_directory, path("/"));
+        assert_eq!(fs.root_directory.size(&path("/")), 30);
+        assert_eq!(fs.root_directory.size(&path("/a/b/c")), 20);
+        assert!(fs.root_directory.directory(&path("/d")).is_none());
+        assert_eq!(
+            fs.root_directory
+                .subdirectories()
+                .into_iter()
+                .map(|(path, _)| format_path(&path))
+                .collect::<Vec<_>>(),
+            ["/a", "/a/b", "/a/b/c"]
+        );
+    }
+
+    #[test]
+    fn inconsistent_logs() {
+        let inconsistent: [&[&str]; 11] = [
+            &["$ cd a"],
+            &["$ ls", "1 a", "2 a"],
+            &["$ ls", "1 a", "dir a"],
+            &["$ ls", "dir a", "1 a"],
+            &["$ mkdir a", "$ mkdir a"],
+            &["$ rm a"],
+            &["$ mkdir a", "$ cd a", "$ rm /a", "$ ls", "1 b"],
+            &["$ mkdir a", "$ cd a", "$ pwd", "/"],
+            &["$ ls", "1 a", "$ du", "2 /"],
+            &["$ du", "0 /", "$ ls", "1 a"],
+            &["$ ls", "5 a", "dir b", "$ du", "3 /"],
+        ];
+        for lines in inconsistent {
+            assert!(build(lines).is_err(), "{lines:?}");
+        }
+        let err = build(&["$ ls", "dir a", "$ cd b"]).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "inconsistent log on line 3: no such directory /b"
+        );
+        assert!(build(&["$ ls", "1 a", "1 a", "$ cd ..", "$ rm a"]).is_ok());
+        let err = build(&["$ mkdir a", "$ du a", "1 /a"]).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "inconsistent log on line 3: du printed 1 for /a, but its listings add up to 0"
+        );
+        // /b may hold the missing bytes, and the rm makes the usage stale
+        assert!(build(&["$ ls", "5 a", "dir b", "$ du", "9 /"]).is_ok());
+        assert!(build(&["$ ls", "5 a", "$ du", "9 /", "$ rm a"]).is_ok());
+    }
+
+    #[test]
+    fn tree_view() {
+        let input = advent_of_code::read_file("examples", 7);
+        let fs = FileSystem::build(input.parse().unwrap()).unwrap();
+        assert_eq!(
+            fs.tree(None),
+            "- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)
+"
+        );
+        assert_eq!(fs.tree(Some(0)), "- / (dir)\n");
+        assert_eq!(fs.tree(Some(1)).lines().count(), 5);
+    }
+
+    #[test]
+    fn du_listing() {
+        let input = advent_of_code::read_file("examples", 7);
+        let fs = FileSystem::build(input.parse().unwrap()).unwrap();
+        assert_eq!(fs.du(None), "47M\t/\n24M\t/d\n93K\t/a\n584\t/a/e\n");
+        assert_eq!(fs.du(Some(0)), "47M\t/\n");
+        assert_eq!(fs.disk_usage(Some(1)).len(), 3);
+    }
+
+    #[test]
+    fn human_sizes() {
+        let sizes = [
+            (0, "0"),
+            (1023, "1023"),
+            (1024, "1.0K"),
+            (1025, "1.1K"),
+            (10 * 1024 - 1, "10K"),
+            (10 * 1024, "10K"),
+            (1024 * 1024 - 1, "1.0M"),
+            (3 << 30, "3.0G"),
+        ];
+        for (size, expected) in sizes {
+            assert_eq!(human_size(size), expected, "{size}");
+        }
+    }
+
+    #[test]
+    fn cleanup_plans() {
+        let input = advent_of_code::read_file("examples", 7);
+        let fs = FileSystem::build(input.parse().unwrap()).unwrap();
+        let plan = |needed, mode| fs.plan_cleanup(DISK_CAPACITY, needed, mode).unwrap();
+        let path = |p: &str| fs.resolve(p);
+        assert_eq!(
+            plan(UPDATE_SIZE, CleanupMode::SingleDirectory),
+            Some(CleanupPlan {
+                directories: vec![path("/d")],
+                freed: 24933642
+            })
+        );
+        // 21618835 is free already, and no single directory holds the other
+        // 25000000, but /a and /d together do
+        assert_eq!(
+            plan(46_618_835, CleanupMode::SmallestSet),
+            Some(CleanupPlan {
+                directories: vec![path("/a"), path("/d")],
+                freed: 25028495
+            })
+        );
+        assert_eq!(plan(46_618_835, CleanupMode::SingleDirectory), None);
+        assert_eq!(plan(47_000_000, CleanupMode::SmallestSet), None);
+        // Far more than the disk holds, so nothing is allocated for it
+        assert_eq!(plan(usize::MAX, CleanupMode::SmallestSet), None);
+        assert_eq!(
+            plan(21_000_000, CleanupMode::SmallestSet),
+            Some(CleanupPlan {
+                directories: vec![],
+                freed: 0
+            })
+        );
+    }
+
+    #[test]
+    fn cleanup_plans_for_large_targets() {
+        let fs = build(&[
+            "$ ls",
+            "dir a",
+            "dir b",
+            "$ cd a",
+            "$ ls",
+            "30000000001 x",
+            "$ cd ../b",
+            "$ ls",
+            "20000000000 y",
+        ])
+        .unwrap();
+        let capacity = 60_000_000_000;
+        let plan = |needed, mode| fs.plan_cleanup(capacity, needed, mode);
+        let single = plan(30_000_000_000, CleanupMode::SingleDirectory).unwrap();
+        assert_eq!(single.map(|plan| plan.freed), Some(30_000_000_001));
+        // Counting up to 20000000001 a byte at a time would go over the limit
+        let err = plan(30_000_000_000, CleanupMode::SmallestSet).unwrap_err();
+        assert!(err.to_string().contains("20000000001 bytes"), "{err}");
+
+        // Block sized files only need to be counted in blocks
+        let fs = build(&[
+            "$ ls",
+            "dir a",
+            "dir b",
+            "$ cd a",
+            "$ ls",
+            "30720000000 x",
+            "$ cd ../b",
+            "$ ls",
+            "20480000000 y",
+        ])
+        .unwrap();
+        let plan = fs
+            .plan_cleanup(capacity, 40_000_000_000, CleanupMode::SmallestSet)
+            .unwrap();
+        assert_eq!(plan.map(|plan| plan.freed), Some(51_200_000_000));
+    }
+
+    /// The smallest total of non-nested directories freeing `needed`, by
+    /// trying every subset.
+    fn brute_force_cleanup(fs: &FileSystem, needed: usize) -> Option<usize> {
+        let directories = fs.root_directory.subdirectories();
+        (0..1_usize << directories.len())
+            .filter(|set| {
+                let chosen = (0..directories.len()).filter(|i| set & (1 << i) != 0);
+                let paths = chosen.map(|i| &directories[i].0).collect::<Vec<_>>();
+                paths
+                    .iter()
+                    .all(|a| paths.iter().all(|b| a == b || !b.starts_with(a.as_slice())))
+            })
+            .map(|set| {
+                (0..directories.len())
+                    .filter(|i| set & (1 << i) != 0)
+                    .map(|i| directories[i].1.size)
+                    .sum::<usize>()
+            })
+            .filter(|total| *total >= needed)
+            .min()
+    }
+
+    #[test]
+    fn smallest_set_matches_brute_force() {
+        let mut rng = Rng::new(5);
+        for _ in 0..100 {
+            let (input, total) = random_input(&mut rng);
+            let fs = FileSystem::build(input.parse().unwrap()).unwrap();
+            if fs.root_directory.subdirectories().len() > 12 {
+                continue;
+            }
+            let needed = rng.range(1..=total.max(1));
+            let capacity = total;
+            let plan = fs
+                .plan_cleanup(capacity, needed, CleanupMode::SmallestSet)
+                .unwrap();
+            assert_eq!(
+                plan.as_ref().map(|plan| plan.freed),
+                brute_force_cleanup(&fs, needed),
+                "{input}"
+            );
+            if let Some(plan) = plan {
+                let sizes = plan
+                    .directories
+                    .iter()
+                    .map(|path| fs.root_directory.size(path));
+                assert_eq!(sizes.sum::<usize>(), plan.freed);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let (input, _) = random_input(&mut rng);
+            let input = rng.mutate(&input, "$ cdls./0123456789\n");
+            let _ = part_one(&input);
+            let _ = part_two(&input);
+        }
+    }
+
+    #[test]
+    fn full_file_system_has_nothing_to_delete() {
+        let input = "$ cd /\n$ ls\n80000000 huge.bin";
+        assert!(part_two(input).is_err());
     }
 }
 
-mod input_parser {
-    use nom::{Finish, IResult};
+pub mod input_parser {
+    use nom::{
+        branch::alt,
+        bytes::complete::tag,
+        character::complete::{digit1, multispace0, newline, not_line_ending, space1},
+        combinator::{all_consuming, map, map_opt, opt, recognize},
+        multi::{many0, separated_list1},
+        sequence::{pair, preceded, separated_pair, terminated},
+        Finish, IResult,
+    };
     use std::str::FromStr;
 
     #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -184,12 +1047,8 @@ mod input_parser {
     }
 
     fn file(input: &str) -> IResult<&str, LsResult> {
-        let parser = nom::sequence::separated_pair(
-            nom::character::complete::digit1,
-            nom::character::complete::space1,
-            nom::character::complete::not_line_ending,
-        );
-        nom::combinator::map_opt(parser, |(size_s, filename): (&str, &str)| {
+        let parser = separated_pair(digit1, space1, not_line_ending);
+        map_opt(parser, |(size_s, filename): (&str, &str)| {
             let size: usize = size_s.parse().ok()?;
             let filename = filename.to_string();
             Some(LsResult::File(File { filename, size }))
@@ -202,12 +1061,8 @@ mod input_parser {
     }
 
     fn directory(input: &str) -> IResult<&str, LsResult> {
-        let parser = nom::sequence::separated_pair(
-            nom::bytes::complete::tag("dir"),
-            nom::character::complete::space1,
-            nom::character::complete::not_line_ending,
-        );
-        nom::combinator::map(parser, |(_, name): (&str, &str)| {
+        let parser = separated_pair(tag("dir"), space1, not_line_ending);
+        map(parser, |(_, name): (&str, &str)| {
             let name = name.to_string();
             LsResult::Directory(Directory { name })
         })(input)
@@ -220,55 +1075,83 @@ mod input_parser {
     }
 
     fn ls_result(input: &str) -> IResult<&str, LsResult> {
-        nom::branch::alt((file, directory))(input)
+        alt((file, directory))(input)
     }
 
     #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub enum Command {
         Ls,
         Cd(String),
+        Pwd,
+        Mkdir(String),
+        Rm(String),
+        Du(Option<String>),
     }
 
-    fn cd_command(input: &str) -> IResult<&str, Command> {
-        let parser = nom::sequence::separated_pair(
-            nom::bytes::complete::tag("cd"),
-            nom::character::complete::space1,
-            nom::character::complete::not_line_ending,
-        );
-        nom::combinator::map(parser, |(_cmd, path): (&str, &str)| {
-            Command::Cd(path.to_string())
-        })(input)
-    }
-
-    fn ls_command(input: &str) -> IResult<&str, Command> {
-        nom::combinator::map(nom::bytes::complete::tag("ls"), |_| Command::Ls)(input)
+    fn argument<'a>(name: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, String> {
+        map(
+            separated_pair(tag(name), space1, not_line_ending),
+            |(_cmd, path): (&str, &str)| path.to_string(),
+        )
     }
 
     fn command(input: &str) -> IResult<&str, Command> {
-        let parser = nom::sequence::separated_pair(
-            nom::bytes::complete::tag("$"),
-            nom::character::complete::space1,
-            nom::branch::alt((cd_command, ls_command)),
-        );
-        nom::combinator::map(parser, |(_, cmd)| cmd)(input)
+        let parser = alt((
+            map(argument("cd"), Command::Cd),
+            map(tag("ls"), |_| Command::Ls),
+            map(tag("pwd"), |_| Command::Pwd),
+            map(argument("mkdir"), Command::Mkdir),
+            map(argument("rm"), Command::Rm),
+            map(argument("du"), |path| Command::Du(Some(path))),
+            map(tag("du"), |_| Command::Du(None)),
+        ));
+        map(separated_pair(tag("$"), space1, parser), |(_, cmd)| cmd)(input)
     }
 
     #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub enum Line {
         Command(Command),
+        /// An entry printed by `ls`
         Output(LsResult),
+        /// The path printed by `pwd`
+        WorkingDirectory(String),
+        /// A size and path printed by `du`
+        DiskUsage(usize, String),
     }
 
-    fn line(input: &str) -> IResult<&str, Line> {
-        nom::branch::alt((
-            nom::combinator::map(command, Line::Command),
-            nom::combinator::map(ls_result, Line::Output),
-        ))(input)
+    fn working_directory(input: &str) -> IResult<&str, Line> {
+        map(recognize(pair(tag("/"), not_line_ending)), |path: &str| {
+            Line::WorkingDirectory(path.to_string())
+        })(input)
+    }
+
+    fn disk_usage(input: &str) -> IResult<&str, Line> {
+        let parser = separated_pair(digit1, space1, not_line_ending);
+        map_opt(parser, |(size, path): (&str, &str)| {
+            Some(Line::DiskUsage(size.parse().ok()?, path.to_string()))
+        })(input)
+    }
+
+    /// A command followed by the lines it printed, which only `ls`, `pwd`
+    /// and `du` do.
+    fn invocation(input: &str) -> IResult<&str, Vec<Line>> {
+        let (input, cmd) = command(input)?;
+        let (input, output) = match cmd {
+            Command::Ls => many0(preceded(newline, map(ls_result, Line::Output)))(input)?,
+            Command::Pwd => map(opt(preceded(newline, working_directory)), |line| {
+                line.into_iter().collect()
+            })(input)?,
+            Command::Du(_) => many0(preceded(newline, disk_usage))(input)?,
+            Command::Cd(_) | Command::Mkdir(_) | Command::Rm(_) => (input, Vec::new()),
+        };
+        let lines = [Line::Command(cmd)].into_iter().chain(output).collect();
+        Ok((input, lines))
     }
 
     fn program(input: &str) -> IResult<&str, Input> {
-        let parser = nom::multi::separated_list1(nom::character::complete::newline, line);
-        nom::combinator::map(parser, |lines| Input { lines })(input)
+        map(separated_list1(newline, invocation), |invocations| Input {
+            lines: invocations.into_iter().flatten().collect(),
+        })(input)
     }
 
     #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -280,7 +1163,7 @@ mod input_parser {
         type Err = nom::error::Error<String>;
 
         fn from_str(s: &str) -> Result<Self, Self::Err> {
-            match program(s).finish() {
+            match all_consuming(terminated(program, multispace0))(s).finish() {
                 Ok((_remaining, plan)) => Ok(plan),
                 Err(nom::error::Error { input, code }) => Err(Self::Err {
                     input: input.to_string(),
@@ -289,4 +1172,33 @@ mod input_parser {
             }
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn parse_outputs_by_command() {
+            let input = "$ ls\n12 a\ndir b\n$ pwd\n/\n$ du b\n0 /b\n$ rm a";
+            assert_eq!(
+                input.parse::<Input>().unwrap().lines,
+                vec![
+                    Line::Command(Command::Ls),
+                    Line::Output(LsResult::File(File {
+                        filename: "a".to_string(),
+                        size: 12
+                    })),
+                    Line::Output(LsResult::Directory(Directory {
+                        name: "b".to_string()
+                    })),
+                    Line::Command(Command::Pwd),
+                    Line::WorkingDirectory("/".to_string()),
+                    Line::Command(Command::Du(Some("b".to_string()))),
+                    Line::DiskUsage(0, "/b".to_string()),
+                    Line::Command(Command::Rm("a".to_string())),
+                ]
+            );
+            assert!("$ cd a\n12 a".parse::<Input>().is_err());
+        }
+    }
 }
@@ -1,4 +1,7 @@
 use ::take_until::TakeUntilExt;
+use anyhow::{bail, Context};
+use itertools::Itertools;
+use std::process;
 use std::str::FromStr;
 
 pub type TreeHeight = u32;
@@ -9,6 +12,140 @@ pub struct Position {
     pub y: usize,
 }
 
+/// Per-tree results for a whole grid, indexed `[y][x]`.
+pub type Matrix<T> = Vec<Vec<T>>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ForestSurvey {
+    pub visible: Matrix<bool>,
+    pub scenic_scores: Matrix<u64>,
+}
+
+impl ForestSurvey {
+    /// The tree with the highest scenic score, the first in reading order on
+    /// a tie.
+    pub fn best_tree(&self) -> Option<(Position, u64)> {
+        let mut best: Option<(Position, u64)> = None;
+        for (y, row) in self.scenic_scores.iter().enumerate() {
+            for (x, &score) in row.iter().enumerate() {
+                if best
+                    .as_ref()
+                    .map_or(true, |(_, best_score)| score > *best_score)
+                {
+                    best = Some((Position { x, y }, score));
+                }
+            }
+        }
+        best
+    }
+
+    /// Scenic scores scaled to 0..=255 against the highest one.
+    fn shades(&self) -> Matrix<u64> {
+        let max = self
+            .scenic_scores
+            .iter()
+            .flatten()
+            .max()
+            .copied()
+            .unwrap_or(0)
+            .max(1);
+        self.scenic_scores
+            .iter()
+            .map(|row| row.iter().map(|score| score * 255 / max).collect())
+            .collect()
+    }
+
+    /// A plain PGM image of the scenic scores, brighter for higher scores.
+    pub fn to_pgm(&self) -> String {
+        let shades = self.shades();
+        let (width, height) = (shades.first().map_or(0, Vec::len), shades.len());
+        let mut image = format!("P2\n{width} {height}\n255\n");
+        // Plain PNM readers expect lines of at most 70 characters
+        for line in &shades.iter().flatten().chunks(17) {
+            image.push_str(&line.map(|shade| format!("{shade:3}")).join(" "));
+            image.push('\n');
+        }
+        image
+    }
+
+    /// A plain PPM image of the scenic scores running from black through red
+    /// to yellow, with full blue added to the trees visible from outside.
+    pub fn to_ppm(&self) -> String {
+        let shades = self.shades();
+        let (width, height) = (shades.first().map_or(0, Vec::len), shades.len());
+        let mut image = format!("P3\n{width} {height}\n255\n");
+        let pixels = shades
+            .iter()
+            .flatten()
+            .zip(self.visible.iter().flatten())
+            .map(|(shade, visible)| {
+                let red = (shade * 2).min(255);
+                let green = (shade * 2).saturating_sub(255);
+                let blue = if *visible { 255 } else { 0 };
+                format!("{red:3} {green:3} {blue:3}")
+            });
+        for mut line in &pixels.chunks(5) {
+            image.push_str(&line.join("  "));
+            image.push('\n');
+        }
+        image
+    }
+
+    /// One `x,y,visible,scenic_score` line per tree, after a header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = "x,y,visible,scenic_score\n".to_string();
+        for (y, (visible, scores)) in self.visible.iter().zip(&self.scenic_scores).enumerate() {
+            for (x, (visible, score)) in visible.iter().zip(scores).enumerate() {
+                csv.push_str(&format!("{x},{y},{visible},{score}\n"));
+            }
+        }
+        csv
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Pgm,
+    Ppm,
+    Csv,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pgm" => Ok(ExportFormat::Pgm),
+            "ppm" => Ok(ExportFormat::Ppm),
+            "csv" => Ok(ExportFormat::Csv),
+            _ => bail!("unknown export format {:?}, expected pgm, ppm or csv", s),
+        }
+    }
+}
+
+/// For each tree along a line, how far it can see back towards the start of
+/// the line and whether it sees past the start. The stack holds the trees not
+/// yet hidden behind a taller or equal one, so each tree is pushed and popped
+/// at most once.
+fn look_back(heights: &[TreeHeight]) -> Vec<(usize, bool)> {
+    let mut stack: Vec<usize> = Vec::new();
+    heights
+        .iter()
+        .enumerate()
+        .map(|(i, height)| {
+            while stack.last().map_or(false, |&j| heights[j] < *height) {
+                stack.pop();
+            }
+            let view = match stack.last() {
+                Some(&j) => (i - j, false),
+                None => (i, true),
+            };
+            stack.push(i);
+            view
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct TreeGrid {
     yx_grid: Vec<Vec<TreeHeight>>,
@@ -86,68 +223,153 @@ impl TreeGrid {
             .any(|taller| taller)
     }
 
-    pub fn scenic_score(&self, position: &Position) -> u32 {
+    pub fn scenic_score(&self, position: &Position) -> u64 {
         let height = self.get_tree(position);
-        let score = self
-            .views(position)
+        self.views(position)
             .into_iter()
-            .map(|iter| iter.take_until(|other| *other >= height).count())
-            .reduce(|a, b| a * b)
-            .unwrap_or(0);
-        score as u32
+            .map(|iter| iter.take_until(|other| *other >= height).count() as u64)
+            .product()
+    }
+
+    pub fn width(&self) -> usize {
+        self.yx_grid.first().map_or(0, Vec::len)
+    }
+
+    pub fn height(&self) -> usize {
+        self.yx_grid.len()
+    }
+
+    /// Visibility and scenic scores of every tree at once, looking along each
+    /// row and column in both directions.
+    pub fn survey(&self) -> ForestSurvey {
+        let (width, height) = (self.width(), self.height());
+        let mut visible = vec![vec![false; width]; height];
+        let mut scenic_scores = vec![vec![1; width]; height];
+        let mut record = |x: usize, y: usize, (distance, edge): (usize, bool)| {
+            visible[y][x] |= edge;
+            scenic_scores[y][x] *= distance as u64;
+        };
+        for (y, row) in self.yx_grid.iter().enumerate() {
+            let mut row = row.clone();
+            for (x, view) in look_back(&row).into_iter().enumerate() {
+                record(x, y, view);
+            }
+            row.reverse();
+            for (i, view) in look_back(&row).into_iter().enumerate() {
+                record(width - 1 - i, y, view);
+            }
+        }
+        for x in 0..width {
+            let mut column = self.yx_grid.iter().map(|row| row[x]).collect::<Vec<_>>();
+            for (y, view) in look_back(&column).into_iter().enumerate() {
+                record(x, y, view);
+            }
+            column.reverse();
+            for (i, view) in look_back(&column).into_iter().enumerate() {
+                record(x, height - 1 - i, view);
+            }
+        }
+        ForestSurvey {
+            visible,
+            scenic_scores,
+        }
     }
 }
 
 impl FromStr for TreeGrid {
-    type Err = ();
+    type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let tree_grid =
-            s.lines()
-                .enumerate()
-                .flat_map(|(y, line)| {
-                    line.chars().filter_map(|c| c.to_digit(10)).enumerate().map(
-                        move |(x, height)| {
-                            let pos = Position { x, y };
-                            (pos, height)
-                        },
-                    )
-                })
-                .fold(TreeGrid::new(), |mut tree_grid, (position, height)| {
-                    tree_grid.add_tree(&position, height);
-                    tree_grid
-                });
+        let mut tree_grid = TreeGrid::new();
+        for (y, line) in s.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let height = c
+                    .to_digit(10)
+                    .with_context(|| format!("invalid tree height '{c}' at x={x}, y={y}"))?;
+                tree_grid.add_tree(&Position { x, y }, height);
+            }
+        }
+        if !tree_grid.yx_grid.iter().map(Vec::len).all_equal() {
+            bail!("tree grid rows have different lengths");
+        }
         Ok(tree_grid)
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let tree_grid: TreeGrid = input.parse().ok()?;
+pub fn part_one(input: &str) -> anyhow::Result<u32> {
+    let tree_grid: TreeGrid = input.parse()?;
     let count_visible = tree_grid
+        .survey()
+        .visible
         .iter()
-        .filter(|(position, _)| tree_grid.visible_from_outside(position))
+        .flatten()
+        .filter(|v| **v)
         .count();
-    Some(count_visible as u32)
+    Ok(count_visible as u32)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let tree_grid: TreeGrid = input.parse().ok()?;
-    let max_score = tree_grid
-        .iter()
-        .map(|(position, _)| tree_grid.scenic_score(&position))
-        .max();
-    max_score
+pub fn best_tree(input: &str) -> anyhow::Result<(Position, u64)> {
+    let tree_grid: TreeGrid = input.parse()?;
+    tree_grid.survey().best_tree().context("empty tree grid")
+}
+
+pub fn part_two(input: &str) -> anyhow::Result<u64> {
+    let (_, score) = best_tree(input)?;
+    Ok(score)
+}
+
+fn export(input: &str, format: ExportFormat) -> anyhow::Result<String> {
+    let survey = input.parse::<TreeGrid>()?.survey();
+    Ok(match format {
+        ExportFormat::Pgm => survey.to_pgm(),
+        ExportFormat::Ppm => survey.to_ppm(),
+        ExportFormat::Csv => survey.to_csv(),
+    })
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 8);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let mut args = pico_args::Arguments::from_env();
+    let best = args.contains("--best");
+    let format = match args.opt_value_from_str::<_, ExportFormat>("--export") {
+        Ok(format) => format,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            process::exit(1);
+        }
+    };
+    let result = match (format, best) {
+        (Some(format), _) => export(input, format).map(|image| print!("{image}")),
+        (None, true) => best_tree(input).map(|(Position { x, y }, score)| {
+            println!("best tree at x={x}, y={y} with scenic score {score}")
+        }),
+        (None, false) => {
+            advent_of_code::solve!(1, part_one, input);
+            advent_of_code::solve!(2, part_two, input);
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("failed: {:#}", e);
+        process::exit(1);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+
+    fn random_input(rng: &mut Rng) -> String {
+        let width = rng.range(1..=12);
+        (0..rng.range(1..=12))
+            .map(|_| {
+                (0..width)
+                    .map(|_| rng.range(b'0'..=b'9') as char)
+                    .collect::<String>()
+            })
+            .join("\n")
+    }
 
     #[test]
     fn tree_score() {
@@ -160,12 +382,96 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 8);
-        assert_eq!(part_one(&input), Some(21));
+        assert_eq!(part_one(&input).unwrap(), 21);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 8);
-        assert_eq!(part_two(&input), Some(8));
+        assert_eq!(part_two(&input).unwrap(), 8);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let input = random_input(&mut rng);
+            let tree_grid: TreeGrid = input.parse().unwrap();
+            let printed = tree_grid
+                .yx_grid
+                .iter()
+                .map(|row| row.iter().join(""))
+                .join("\n");
+            assert_eq!(printed, input);
+        }
+    }
+
+    #[test]
+    fn survey_matches_per_tree_views() {
+        let mut rng = Rng::new(4);
+        for _ in 0..100 {
+            let tree_grid: TreeGrid = random_input(&mut rng).parse().unwrap();
+            let survey = tree_grid.survey();
+            for (position, _) in tree_grid.iter() {
+                let Position { x, y } = position;
+                assert_eq!(
+                    survey.visible[y][x],
+                    tree_grid.visible_from_outside(&position)
+                );
+                assert_eq!(
+                    survey.scenic_scores[y][x],
+                    tree_grid.scenic_score(&position)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn best_tree_position() {
+        let input = advent_of_code::read_file("examples", 8);
+        assert_eq!(best_tree(&input).unwrap(), (Position { x: 2, y: 3 }, 8));
+        assert!(best_tree("").is_err());
+    }
+
+    #[test]
+    fn exports() {
+        let survey = "30\n25".parse::<TreeGrid>().unwrap().survey();
+        assert_eq!(survey.to_pgm(), "P2\n2 2\n255\n  0   0   0   0\n");
+        assert_eq!(
+            survey.to_ppm(),
+            "P3\n2 2\n255\n  0   0 255    0   0 255    0   0 255    0   0 255\n"
+        );
+        assert_eq!(
+            survey.to_csv(),
+            "x,y,visible,scenic_score\n0,0,true,0\n1,0,true,0\n0,1,true,0\n1,1,true,0\n"
+        );
+        let input = advent_of_code::read_file("examples", 8);
+        let survey = input.parse::<TreeGrid>().unwrap().survey();
+        let pgm = survey.to_pgm();
+        let shades = pgm.split_whitespace().skip(4).collect::<Vec<_>>();
+        assert_eq!(shades.len(), 25);
+        assert_eq!(shades[3 * 5 + 2], "255");
+        assert!(pgm.lines().all(|line| line.len() <= 70));
+        assert!(survey.to_ppm().lines().all(|line| line.len() <= 70));
+        assert_eq!(survey.to_csv().lines().count(), 26);
+    }
+
+    #[test]
+    fn look_back_along_line() {
+        assert_eq!(
+            look_back(&[3, 0, 3, 7, 3]),
+            vec![(0, true), (1, false), (2, false), (3, true), (1, false)]
+        );
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = random_input(&mut rng);
+            let input = rng.mutate(&input, "0123456789\n");
+            let _ = part_one(&input);
+            let _ = part_two(&input);
+        }
     }
 }
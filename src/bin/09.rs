@@ -1,6 +1,12 @@
+use advent_of_code::animation::Recorder;
+use anyhow::{anyhow, bail, Context};
 use std::collections::HashSet;
-use std::error::Error;
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
 use std::str::FromStr;
+use std::time::Duration;
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Hash)]
 pub struct Position {
@@ -14,55 +20,79 @@ impl Position {
     }
 
     pub fn step(&mut self, direction: &Direction) {
-        match direction {
-            Direction::Up => {
-                self.y += 1;
-            }
-            Direction::Down => {
-                self.y -= 1;
-            }
-            Direction::Right => {
-                self.x += 1;
-            }
-            Direction::Left => {
-                self.x -= 1;
-            }
-        }
+        let (dx, dy) = direction.offset();
+        self.x += dx;
+        self.y += dy;
     }
 
     pub fn touches(&self, other: &Position) -> bool {
-        (self.x - other.x).abs() <= 1 && (self.y - other.y).abs() <= 1
+        self.within(other, 1)
+    }
+
+    /// Whether `other` is at most `distance` steps away, counting diagonal
+    /// steps as one.
+    pub fn within(&self, other: &Position, distance: i32) -> bool {
+        (self.x - other.x).abs() <= distance && (self.y - other.y).abs() <= distance
     }
 
     pub fn follow(&mut self, head: &Position) {
-        if !self.touches(head) {
-            let offset = (head.x == self.x || head.y == self.y) as i32;
-            if head.y > self.y + offset {
-                self.step(&Direction::Up);
-            }
-            if head.y < self.y - offset {
-                self.step(&Direction::Down);
-            }
-            if head.x > self.x + offset {
-                self.step(&Direction::Right);
-            }
-            if head.x < self.x - offset {
-                self.step(&Direction::Left);
-            }
+        self.follow_with(head, &FollowRule::default());
+    }
+
+    /// Moves one step straight or diagonally towards `head` if it has moved
+    /// out of reach of the rule's slack.
+    pub fn follow_with(&mut self, head: &Position, rule: &FollowRule) {
+        if !self.within(head, rule.slack) {
+            self.x += (head.x - self.x).signum();
+            self.y += (head.y - self.y).signum();
         }
     }
 }
 
+/// How a knot follows the one in front of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FollowRule {
+    /// How far a knot lets the one in front get, straight or diagonally,
+    /// before moving.
+    pub slack: i32,
+}
+
+impl Default for FollowRule {
+    fn default() -> Self {
+        FollowRule { slack: 1 }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Direction {
     Up,
     Down,
     Left,
     Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    /// The change in x and y of one step, with y going up.
+    pub fn offset(&self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, 1),
+            Direction::UpRight => (1, 1),
+            Direction::DownLeft => (-1, -1),
+            Direction::DownRight => (1, -1),
+        }
+    }
 }
 
 impl FromStr for Direction {
-    type Err = Box<dyn Error>;
+    type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
@@ -70,11 +100,31 @@ impl FromStr for Direction {
             "D" => Ok(Self::Down),
             "L" => Ok(Self::Left),
             "R" => Ok(Self::Right),
-            _ => Err(format!("{s}: Unkown direction"))?,
+            "UL" => Ok(Self::UpLeft),
+            "UR" => Ok(Self::UpRight),
+            "DL" => Ok(Self::DownLeft),
+            "DR" => Ok(Self::DownRight),
+            _ => Err(anyhow!("{s}: Unkown direction")),
         }
     }
 }
 
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Direction::Up => "U",
+            Direction::Down => "D",
+            Direction::Left => "L",
+            Direction::Right => "R",
+            Direction::UpLeft => "UL",
+            Direction::UpRight => "UR",
+            Direction::DownLeft => "DL",
+            Direction::DownRight => "DR",
+        };
+        write!(f, "{name}")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Move {
     pub direction: Direction,
@@ -82,93 +132,474 @@ pub struct Move {
 }
 
 impl FromStr for Move {
-    type Err = Box<dyn Error>;
+    type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (dir_s, steps_s) = s.split_once(' ').ok_or("Did not find two arguments")?;
+        let (dir_s, steps_s) = s.split_once(' ').context("Did not find two arguments")?;
         let direction = dir_s.parse()?;
         let steps = steps_s.parse()?;
         Ok(Move { direction, steps })
     }
 }
 
+/// The rectangle of positions drawn by `Rope::render`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Area {
+    pub min: Position,
+    pub max: Position,
+}
+
+impl Area {
+    /// The smallest area holding all of `positions` and the origin.
+    pub fn covering<'a>(positions: impl IntoIterator<Item = &'a Position>) -> Area {
+        let mut area = Area {
+            min: Position::new(),
+            max: Position::new(),
+        };
+        for position in positions {
+            area.min.x = area.min.x.min(position.x);
+            area.min.y = area.min.y.min(position.y);
+            area.max.x = area.max.x.max(position.x);
+            area.max.y = area.max.y.max(position.y);
+        }
+        area
+    }
+
+    /// Draws `cell` for every position, rows top down.
+    fn draw(&self, cell: impl Fn(&Position) -> char) -> String {
+        (self.min.y..=self.max.y)
+            .rev()
+            .map(|y| {
+                (self.min.x..=self.max.x)
+                    .map(|x| cell(&Position { x, y }))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Rope {
     knots: Vec<Position>,
+    rule: FollowRule,
+    visited: Vec<HashSet<Position>>,
 }
 
 impl Rope {
     pub fn with_knots(num_knots: usize) -> Rope {
+        Rope::with_rule(num_knots, FollowRule::default())
+    }
+
+    pub fn with_rule(num_knots: usize, rule: FollowRule) -> Rope {
         assert!(
             num_knots >= 2,
             "Rope::with_knots called with less than 2 knots"
         );
         let mut knots = Vec::with_capacity(num_knots);
         knots.resize(num_knots, Position::new());
-        Rope { knots }
+        let visited = vec![HashSet::from([Position::new()]); num_knots];
+        Rope {
+            knots,
+            rule,
+            visited,
+        }
     }
 
     pub fn do_move(&mut self, direction: &Direction) {
         let head = self.knots.first_mut().unwrap();
         head.step(direction);
+        let rule = &self.rule;
         self.knots.iter_mut().reduce(|prev, knot| {
-            knot.follow(prev);
+            knot.follow_with(prev, rule);
             knot
         });
+        for (visited, knot) in self.visited.iter_mut().zip(&self.knots) {
+            visited.insert(knot.clone());
+        }
+    }
+
+    pub fn knots(&self) -> &[Position] {
+        &self.knots
     }
 
     pub fn tail(&self) -> &Position {
         self.knots.last().unwrap()
     }
-}
 
-pub fn solve(input: &str, rope_len: usize) -> Option<u32> {
-    let tail_unique_positions = input
-        .lines()
-        .filter_map(|l| l.parse::<Move>().ok())
-        .fold(
-            (Rope::with_knots(rope_len), HashSet::new()),
-            |(mut rope, mut visited_positions): (Rope, HashSet<Position>),
-             Move { direction, steps }| {
-                for _ in 0..steps {
-                    rope.do_move(&direction);
-                    visited_positions.insert(rope.tail().clone());
-                }
-                (rope, visited_positions)
+    /// Every position the knot at `index` has been in, the head being 0.
+    pub fn visited(&self, index: usize) -> Option<&HashSet<Position>> {
+        self.visited.get(index)
+    }
+
+    /// The knots in `area` drawn as in the puzzle: `H` for the head, then
+    /// `T` for the tail of a two-knot rope or the knot numbers otherwise,
+    /// with `s` marking the start. Knots in front hide those behind them.
+    pub fn render(&self, area: &Area) -> String {
+        area.draw(
+            |position| match self.knots.iter().position(|knot| knot == position) {
+                Some(0) => 'H',
+                Some(_) if self.knots.len() == 2 => 'T',
+                Some(i) => char::from_digit(i as u32 % 10, 10).unwrap_or('?'),
+                None if *position == Position::new() => 's',
+                None => '.',
             },
         )
-        .1
-        .len() as u32;
-    Some(tail_unique_positions)
+    }
+
+    /// The positions the knot at `index` has visited drawn as `#`, with `s`
+    /// marking the start.
+    pub fn render_trail(&self, index: usize, area: &Area) -> Option<String> {
+        let visited = self.visited(index)?;
+        Some(area.draw(|position| match position {
+            p if *p == Position::new() => 's',
+            p if visited.contains(p) => '#',
+            _ => '.',
+        }))
+    }
+
+    /// The area every knot has been in so far.
+    pub fn area(&self) -> Area {
+        Area::covering(self.visited.iter().flatten())
+    }
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(&self.area()))
+    }
+}
+
+fn parse_moves(input: &str) -> anyhow::Result<Vec<Move>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, l)| {
+            l.parse::<Move>()
+                .with_context(|| format!("invalid move on line {}", i + 1))
+        })
+        .collect()
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
+/// Runs every move on a rope of `rope_len` knots following `rule`.
+pub fn simulate(input: &str, rope_len: usize, rule: FollowRule) -> anyhow::Result<Rope> {
+    let mut rope = Rope::with_rule(rope_len, rule);
+    for Move { direction, steps } in parse_moves(input)? {
+        for _ in 0..steps {
+            rope.do_move(&direction);
+        }
+    }
+    Ok(rope)
+}
+
+pub fn solve(input: &str, rope_len: usize) -> anyhow::Result<u32> {
+    let rope = simulate(input, rope_len, FollowRule::default())?;
+    let tail_unique_positions = rope.visited(rope_len - 1).map_or(0, HashSet::len);
+    Ok(tail_unique_positions as u32)
+}
+
+pub fn part_one(input: &str) -> anyhow::Result<u32> {
     solve(input, 2)
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
+pub fn part_two(input: &str) -> anyhow::Result<u32> {
     solve(input, 10)
 }
 
+/// Prints the rope after every step under a `== R 4 ==` header per move,
+/// then the trail of its tail.
+fn render(input: &str, rope_len: usize, rule: FollowRule) -> anyhow::Result<()> {
+    if rope_len < 2 {
+        bail!("a rope needs at least 2 knots");
+    }
+    // Run it once to know the area every frame has to cover
+    let area = simulate(input, rope_len, rule.clone())?.area();
+    let mut rope = Rope::with_rule(rope_len, rule);
+    println!("== Initial State ==\n\n{}\n", rope.render(&area));
+    for Move { direction, steps } in parse_moves(input)? {
+        println!("== {direction} {steps} ==\n");
+        for _ in 0..steps {
+            rope.do_move(&direction);
+            println!("{}\n", rope.render(&area));
+        }
+    }
+    let trail = rope.render_trail(rope_len - 1, &area).unwrap_or_default();
+    println!("== Tail Trail ==\n\n{trail}");
+    Ok(())
+}
+
+const FRAME_DELAY: Duration = Duration::from_millis(50);
+
+/// The rope after every step as animation frames, ending on the trail of its
+/// tail.
+fn animate<W: Write>(
+    input: &str,
+    rope_len: usize,
+    rule: FollowRule,
+    mut recorder: Recorder<W>,
+) -> anyhow::Result<W> {
+    if rope_len < 2 {
+        bail!("a rope needs at least 2 knots");
+    }
+    let area = simulate(input, rope_len, rule.clone())?.area();
+    let mut rope = Rope::with_rule(rope_len, rule);
+    recorder.record(|| rope.render(&area))?;
+    for Move { direction, steps } in parse_moves(input)? {
+        for _ in 0..steps {
+            rope.do_move(&direction);
+            recorder.record(|| rope.render(&area))?;
+        }
+    }
+    recorder.record_last(|| rope.render(&area))?;
+    recorder.record_frame(&rope.render_trail(rope_len - 1, &area).unwrap_or_default())?;
+    recorder.finish()
+}
+
+struct Args {
+    render: bool,
+    animate: Option<PathBuf>,
+    every: usize,
+    knots: usize,
+    slack: i32,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        render: args.contains("--render"),
+        animate: args.opt_value_from_str("--animate")?,
+        every: args.opt_value_from_str("--every")?.unwrap_or(1),
+        knots: args.opt_value_from_str("--knots")?.unwrap_or(10),
+        slack: args.opt_value_from_str("--slack")?.unwrap_or(1),
+    })
+}
+
 fn main() {
     let input = &advent_of_code::read_file("inputs", 9);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    match parse_args() {
+        Ok(Args {
+            render: false,
+            animate: None,
+            ..
+        }) => {
+            advent_of_code::solve!(1, part_one, input);
+            advent_of_code::solve!(2, part_two, input);
+        }
+        Ok(Args {
+            animate: animation,
+            every,
+            knots,
+            slack,
+            ..
+        }) => {
+            let rule = FollowRule { slack };
+            let result = match animation {
+                Some(path) => Recorder::create(&path, FRAME_DELAY)
+                    .and_then(|r| animate(input, knots, rule, r.with_sampling(every)).map(drop)),
+                None => render(input, knots, rule),
+            };
+            if let Err(e) = result {
+                eprintln!("failed: {:#}", e);
+                process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            process::exit(1);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::animation::{asciicast_frames, AnimationFormat};
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+
+    const DIRECTIONS: [(&str, Direction); 4] = [
+        ("U", Direction::Up),
+        ("D", Direction::Down),
+        ("L", Direction::Left),
+        ("R", Direction::Right),
+    ];
+
+    fn random_moves(rng: &mut Rng) -> Vec<(&'static str, Move)> {
+        (0..rng.range(1..=20))
+            .map(|_| {
+                let (name, direction) = rng.choose(&DIRECTIONS).clone();
+                let steps = rng.range(1..=20);
+                (name, Move { direction, steps })
+            })
+            .collect()
+    }
+
+    fn format_moves(moves: &[(&str, Move)]) -> String {
+        moves
+            .iter()
+            .map(|(name, m)| format!("{name} {}", m.steps))
+            .join("\n")
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 9);
-        assert_eq!(part_one(&input), Some(13));
+        assert_eq!(part_one(&input).unwrap(), 13);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 9);
-        assert_eq!(part_two(&input), Some(1));
+        assert_eq!(part_two(&input).unwrap(), 1);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let moves = random_moves(&mut rng);
+            let input = format_moves(&moves);
+            let parsed = input
+                .lines()
+                .map(|l| l.parse::<Move>().unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                parsed,
+                moves.into_iter().map(|(_, m)| m).collect::<Vec<_>>()
+            );
+            assert!(part_two(&input).is_ok());
+        }
+    }
+
+    #[test]
+    fn render_example() {
+        let input = advent_of_code::read_file("examples", 9);
+        let rope = simulate(&input, 2, FollowRule::default()).unwrap();
+        let area = Area {
+            min: Position { x: 0, y: 0 },
+            max: Position { x: 5, y: 4 },
+        };
+        assert_eq!(rope.area(), area);
+        assert_eq!(rope.render(&area), "......\n......\n.TH...\n......\ns.....");
+        assert_eq!(
+            rope.render_trail(1, &area).unwrap(),
+            "..##..\n...##.\n.####.\n....#.\ns###.."
+        );
+        assert_eq!(rope.visited(1).unwrap().len(), 13);
+        assert_eq!(rope.visited(0).unwrap().len(), 21);
+        assert_eq!(rope.render_trail(2, &area), None);
+    }
+
+    #[test]
+    fn render_knot_numbers() {
+        let mut rope = Rope::with_knots(10);
+        for _ in 0..4 {
+            rope.do_move(&Direction::Right);
+        }
+        let area = Area {
+            min: Position { x: 0, y: 0 },
+            max: Position { x: 5, y: 0 },
+        };
+        assert_eq!(rope.render(&area), "4321H.");
+    }
+
+    #[test]
+    fn animate_example() {
+        let input = advent_of_code::read_file("examples", 9);
+        let recorder = Recorder::new(Vec::new(), AnimationFormat::Asciicast, FRAME_DELAY);
+        let cast = animate(&input, 2, FollowRule::default(), recorder).unwrap();
+        let frames = asciicast_frames(&String::from_utf8(cast).unwrap()).unwrap();
+        // The initial state, one frame per step and the tail trail
+        assert_eq!(frames.len(), 1 + 24 + 1);
+        assert_eq!(frames[0], "......\n......\n......\n......\nH.....");
+        assert_eq!(
+            frames[frames.len() - 2],
+            "......\n......\n.TH...\n......\ns....."
+        );
+        assert_eq!(
+            frames[frames.len() - 1],
+            "..##..\n...##.\n.####.\n....#.\ns###.."
+        );
+    }
+
+    /// Counts what is written instead of keeping it.
+    #[derive(Default)]
+    struct WriteCounter {
+        bytes: usize,
+        lines: usize,
+    }
+
+    impl Write for WriteCounter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.bytes += buf.len();
+            self.lines += buf.iter().filter(|b| **b == b'\n').count();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn animate_generated_input_within_budget() {
+        let size = advent_of_code::generate::default_size(9).unwrap();
+        let input = advent_of_code::generate::day09(&mut Rng::new(1), size);
+        let steps: usize = parse_moves(&input)
+            .unwrap()
+            .iter()
+            .map(|m| m.steps as usize)
+            .sum();
+        let recorder = || {
+            Recorder::new(
+                WriteCounter::default(),
+                AnimationFormat::Asciicast,
+                FRAME_DELAY,
+            )
+        };
+
+        // Too many frames is an error rather than an endless write
+        let limited = recorder().with_frame_limit(10);
+        assert!(animate(&input, 10, FollowRule::default(), limited).is_err());
+
+        let sampled = recorder().with_sampling(500);
+        let written = animate(&input, 10, FollowRule::default(), sampled).unwrap();
+        // The header, the sampled steps, the last step and the trail
+        assert!(written.lines <= 1 + steps / 500 + 1 + 2);
+        assert!(written.bytes < 16 << 20);
+    }
+
+    #[test]
+    fn follow_rules() {
+        let mut rope = Rope::with_rule(3, FollowRule { slack: 2 });
+        for _ in 0..3 {
+            rope.do_move(&Direction::UpRight);
+        }
+        assert_eq!(
+            rope.knots(),
+            [
+                Position { x: 3, y: 3 },
+                Position { x: 1, y: 1 },
+                Position { x: 0, y: 0 }
+            ]
+        );
+        let mut rope = Rope::with_knots(2);
+        rope.do_move(&Direction::DownLeft);
+        rope.do_move(&Direction::DownLeft);
+        assert_eq!(rope.tail(), &Position { x: -1, y: -1 });
+        assert_eq!(
+            "DR 3".parse::<Move>().unwrap().direction,
+            Direction::DownRight
+        );
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = format_moves(&random_moves(&mut rng));
+            let input = rng.mutate(&input, "UDLR 0123456789\n");
+            let _ = input.lines().map(str::parse::<Move>).collect::<Vec<_>>();
+        }
     }
 }
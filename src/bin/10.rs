@@ -1,134 +1,161 @@
+use advent_of_code::cpu::{Breakpoint, Cpu, Decoder, Flow, Stop};
+use advent_of_code::helpers::CheckedNum;
+use advent_of_code::ocr;
+use anyhow::Context;
 use itertools::Itertools;
-use std::str::FromStr;
+use std::iter;
+use std::process;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum Instruction {
-    NoOp,
-    AddX(i32),
+/// The instruction set of the handheld device: `noop` and the two-cycle
+/// `addx`, both working on register `x`.
+pub fn decoder() -> Decoder {
+    Decoder::new()
+        .with_opcode("noop", 0, 1, |_, _| Ok(Flow::Next))
+        .with_opcode("addx", 1, 2, |registers, operands| {
+            let value = operands[0].value(registers);
+            let x = registers.entry('x').or_default();
+            *x = x.try_add(&value)?;
+            Ok(Flow::Next)
+        })
 }
 
-impl FromStr for Instruction {
-    type Err = nom::error::Error<String>;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use nom::{
-            branch::alt,
-            bytes::complete::tag,
-            character::complete::{anychar, space1},
-            combinator::{map, map_opt},
-            multi::many1,
-            sequence::separated_pair,
-            Finish,
-        };
-        let mut parser = alt((
-            map_opt(
-                separated_pair(tag("addx"), space1::<&str, _>, many1(anychar)),
-                |(_, num_str)| {
-                    Some(Instruction::AddX(
-                        num_str.iter().collect::<String>().parse::<i32>().ok()?,
-                    ))
-                },
-            ),
-            map(tag("noop"), |_| Instruction::NoOp),
-        ));
-        match parser(s).finish() {
-            Ok((_remaining, plan)) => Ok(plan),
-            Err(nom::error::Error { input, code }) => Err(Self::Err {
-                input: input.to_string(),
-                code,
-            }),
-        }
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Program {
-    cycles: Vec<i32>,
+/// A CPU loaded with `input`, with `x` starting at 1.
+pub fn load(decoder: &Decoder, input: &str) -> anyhow::Result<Cpu> {
+    let program = decoder.decode_program(input)?;
+    Ok(Cpu::new(program).with_register('x', 1))
 }
 
-impl Program {
-    pub fn with_instructions(instructions: &[Instruction]) -> Program {
-        let cycles = instructions.iter().fold(vec![1], |mut cycles, instr| {
-            let register = *cycles.last().unwrap();
-            cycles.push(register);
-            match instr {
-                Instruction::NoOp => {}
-                Instruction::AddX(addx) => {
-                    cycles.push(register + addx);
-                }
-            }
-            cycles
-        });
-        Program { cycles }
+/// The sum of each interesting cycle times `x` during that cycle.
+pub fn signal_strength(cpu: &mut Cpu, interesting_cycles: &[usize]) -> anyhow::Result<i64> {
+    for cycle in interesting_cycles {
+        cpu.add_breakpoint(Breakpoint::Cycle(*cycle));
     }
-
-    pub fn signal_strength(&self, interesting_cycles: &[usize]) -> i32 {
-        interesting_cycles
-            .iter()
-            .filter_map(|c| Some(self.cycles.get(c - 1)? * (*c as i32)))
-            .sum()
+    let mut strength = 0_i64;
+    while let Stop::Breakpoint(Breakpoint::Cycle(cycle)) = cpu.run()? {
+        let signal = cpu.register('x').try_mul(&i64::try_from(cycle)?)?;
+        strength = strength.try_add(&signal)?;
     }
+    Ok(strength)
+}
 
-    pub fn crt_plot(&self) -> String {
-        const COLUMNS: usize = 40;
-        let rows = self.cycles.len() / COLUMNS;
-        (0..rows)
-            .map(|row| {
-                (0..COLUMNS)
-                    .map(|column| {
-                        let signal: i32 = self.cycles[row * COLUMNS + column];
-                        if ((column as i32) - signal).abs() <= 1 {
-                            '#'
-                        } else {
-                            '.'
-                        }
-                    })
-                    .collect::<String>()
-            })
-            .join("\n")
-    }
+/// Draws a pixel whenever the 3 pixel wide sprite centred on `x` covers the
+/// column the CRT is drawing during a cycle.
+pub fn crt_plot(cpu: &mut Cpu) -> anyhow::Result<String> {
+    const COLUMNS: usize = 40;
+    let mut positions = iter::from_fn(|| cpu.tick().transpose())
+        .map_ok(|tick| tick.registers.get(&'x').copied().unwrap_or(0))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    // The CRT keeps drawing with the final `x` once the program has ended
+    positions.push(cpu.register('x'));
+    let rows = positions.len() / COLUMNS;
+    Ok(positions
+        .chunks(COLUMNS)
+        .take(rows)
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .map(
+                    |(column, sprite)| match (column as i64).abs_diff(*sprite) <= 1 {
+                        true => '#',
+                        false => '.',
+                    },
+                )
+                .collect::<String>()
+        })
+        .join("\n"))
 }
 
-type Input = Vec<Instruction>;
+pub fn part_one(input: &str) -> anyhow::Result<i64> {
+    let mut cpu = load(&decoder(), input)?;
+    signal_strength(&mut cpu, &[20, 60, 100, 140, 180, 220])
+}
 
-fn parse_input(input: &str) -> Input {
-    input
-        .lines()
-        .filter_map(|l| l.parse::<Instruction>().ok())
-        .collect()
+pub fn part_two(input: &str) -> anyhow::Result<String> {
+    let mut cpu = load(&decoder(), input)?;
+    ocr::read(&crt_plot(&mut cpu)?).context("the CRT doesn't show letters, try --raw")
 }
 
-pub fn part_one(input: &str) -> Option<i32> {
-    let instructions = parse_input(input);
-    let program = Program::with_instructions(&instructions);
-    let strength = program.signal_strength(&[20, 60, 100, 140, 180, 220]);
-    Some(strength)
+struct Args {
+    raw: bool,
 }
 
-pub fn part_two(input: &str) -> Option<String> {
-    let instructions = parse_input(input);
-    let program = Program::with_instructions(&instructions);
-    Some(program.crt_plot())
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        raw: args.contains("--raw"),
+    })
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 10);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    match parse_args() {
+        Ok(Args { raw: false }) => {
+            advent_of_code::solve!(1, part_one, input);
+            advent_of_code::solve!(2, part_two, input);
+        }
+        Ok(Args { raw: true }) => {
+            match load(&decoder(), input).and_then(|mut cpu| crt_plot(&mut cpu)) {
+                Ok(plot) => println!("{}", plot),
+                Err(e) => {
+                    eprintln!("failed: {:#}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            process::exit(1);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+
+    /// `None` for `noop`, otherwise the value added by `addx`.
+    fn random_instructions(rng: &mut Rng) -> Vec<Option<i64>> {
+        (0..rng.range(1..=250))
+            .map(|_| match rng.chance(1, 2) {
+                true => None,
+                false => Some(rng.range(-20..=20)),
+            })
+            .collect()
+    }
+
+    fn format_instructions(instructions: &[Option<i64>]) -> String {
+        instructions
+            .iter()
+            .map(|i| match i {
+                None => "noop".to_string(),
+                Some(v) => format!("addx {v}"),
+            })
+            .join("\n")
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 10);
-        assert_eq!(part_one(&input), Some(13140));
+        assert_eq!(part_one(&input).unwrap(), 13140);
     }
 
     #[test]
     fn test_part_two() {
+        let input = advent_of_code::generate::day10(&mut Rng::new(4), 6);
+        let letters = part_two(&input).unwrap();
+        assert_eq!(letters.len(), 8);
+        let mut cpu = load(&decoder(), &input).unwrap();
+        assert_eq!(ocr::render(&letters).unwrap(), crt_plot(&mut cpu).unwrap());
+        // Sizes that aren't whole lines of letters still draw whole lines
+        for size in [1, 5, 7] {
+            let input = advent_of_code::generate::day10(&mut Rng::new(4), size);
+            assert!(part_two(&input).is_ok(), "size {size}");
+        }
+    }
+
+    #[test]
+    fn test_crt_plot() {
         let input = advent_of_code::read_file("examples", 10);
         let expected = "##..##..##..##..##..##..##..##..##..##..
 ###...###...###...###...###...###...###.
@@ -137,6 +164,85 @@ mod tests {
 ######......######......######......####
 #######.......#######.......#######....."
             .to_string();
-        assert_eq!(part_two(&input), Some(expected));
+        let mut cpu = load(&decoder(), &input).unwrap();
+        assert_eq!(crt_plot(&mut cpu).unwrap(), expected);
+        // The example draws stripes rather than letters
+        assert!(part_two(&input).is_err());
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let instructions = random_instructions(&mut rng);
+            let input = format_instructions(&instructions);
+            let program = decoder().decode_program(&input).unwrap();
+            let parsed = program
+                .iter()
+                .map(|i| i.operands.first().map(|o| o.value(&Default::default())))
+                .collect_vec();
+            assert_eq!(parsed, instructions);
+            assert_eq!(program.iter().join("\n"), input);
+            let mut cpu = load(&decoder(), &input).unwrap();
+            assert!(crt_plot(&mut cpu)
+                .unwrap()
+                .lines()
+                .all(|row| row.len() == 40));
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = format_instructions(&random_instructions(&mut rng));
+            let _ = decoder().decode_program(&rng.mutate(&input, "noopaddx -0123456789\n"));
+        }
+    }
+
+    #[test]
+    fn step_through_small_program() {
+        let mut cpu = load(&decoder(), "noop\naddx 3\naddx -5").unwrap();
+        assert_eq!(cpu.step().unwrap(), Some(1));
+        assert_eq!((cpu.cycle(), cpu.register('x')), (2, 1));
+        assert_eq!(cpu.step().unwrap(), Some(2));
+        assert_eq!((cpu.cycle(), cpu.register('x')), (4, 4));
+        cpu.add_breakpoint(Breakpoint::Register('x', -1));
+        assert_eq!(
+            cpu.run().unwrap(),
+            Stop::Breakpoint(Breakpoint::Register('x', -1))
+        );
+        assert_eq!(cpu.cycle(), 6);
+        assert_eq!(cpu.run().unwrap(), Stop::Halted);
+    }
+
+    #[test]
+    fn overflowing_programs() {
+        let input = format!("addx {}\naddx 1", i64::MAX - 1);
+        let error = part_one(&input).unwrap_err();
+        assert_eq!(
+            format!("{error:#}"),
+            format!("cycle 4: addx 1: {} + 1 overflows", i64::MAX)
+        );
+        assert!(part_two(&input).is_err());
+        // x stays in range, but the signal strength doesn't
+        let input = format!("noop\naddx {}\n{}", i64::MAX / 10, "noop\n".repeat(20));
+        assert!(part_one(&input).is_err());
+    }
+
+    #[test]
+    fn extended_instruction_set() {
+        let extended = decoder().with_opcode("mulx", 1, 3, |registers, operands| {
+            let value = operands[0].value(registers);
+            let x = registers.entry('x').or_default();
+            *x = x.try_mul(&value)?;
+            Ok(Flow::Next)
+        });
+        assert!(load(&decoder(), "mulx 2").is_err());
+        let mut cpu = load(&extended, "addx 2\nmulx 4\nnoop").unwrap();
+        let ticks = iter::from_fn(|| cpu.tick().unwrap())
+            .map(|tick| tick.registers[&'x'])
+            .collect_vec();
+        assert_eq!(ticks, [1, 1, 3, 3, 3, 12]);
     }
 }
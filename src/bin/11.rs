@@ -1,3 +1,4 @@
+use advent_of_code::helpers::{ArithmeticError, CheckedNum};
 use itertools::Itertools;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -7,10 +8,10 @@ pub enum OperationToken {
 }
 
 impl OperationToken {
-    pub fn apply(&self, old: u64) -> u64 {
+    pub fn apply<T: CheckedNum>(&self, old: &T) -> Result<T, ArithmeticError> {
         match self {
-            OperationToken::Old => old,
-            OperationToken::UnsignedInt(n) => *n,
+            OperationToken::Old => Ok(old.clone()),
+            OperationToken::UnsignedInt(n) => T::try_from_u64(*n),
         }
     }
 }
@@ -31,14 +32,14 @@ pub struct Operation {
 }
 
 impl Operation {
-    pub fn apply(&self, old: u64) -> u64 {
-        let lhs = self.lhs.apply(old);
-        let rhs = self.rhs.apply(old);
+    pub fn apply<T: CheckedNum>(&self, old: &T) -> Result<T, ArithmeticError> {
+        let lhs = self.lhs.apply(old)?;
+        let rhs = self.rhs.apply(old)?;
         match self.operator {
-            Operator::Add => lhs + rhs,
-            Operator::Multiply => lhs * rhs,
-            Operator::Divide => lhs / rhs,
-            Operator::Subtract => lhs - rhs,
+            Operator::Add => lhs.try_add(&rhs),
+            Operator::Multiply => lhs.try_mul(&rhs),
+            Operator::Divide => lhs.try_div(&rhs),
+            Operator::Subtract => lhs.try_sub(&rhs),
         }
     }
 }
@@ -51,89 +52,118 @@ pub struct Test {
 }
 
 impl Test {
-    pub fn apply(&self, n: u64) -> usize {
-        if n % self.divisible_by == 0 {
-            self.true_monkey_id
+    pub fn apply<T: CheckedNum>(&self, n: &T) -> Result<usize, ArithmeticError> {
+        let divisor = T::try_from_u64(self.divisible_by)?;
+        if n.try_mod_floor(&divisor)?.is_zero() {
+            Ok(self.true_monkey_id)
         } else {
-            self.false_monkey_id
+            Ok(self.false_monkey_id)
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Monkey {
-    pub items: Vec<u64>,
+pub struct Monkey<T = u64> {
+    pub items: Vec<T>,
     pub operation: Operation,
     pub test: Test,
 }
 
-impl Monkey {
-    pub fn receive_item(&mut self, item: u64) {
+impl<T: CheckedNum> Monkey<T> {
+    pub fn receive_item(&mut self, item: T) {
         self.items.push(item)
     }
 
-    pub fn throw_item(&mut self, reducer: impl Fn(u64) -> u64) -> Option<(usize, u64)> {
-        let mut item = self.items.pop()?;
-        item = self.operation.apply(item);
-        item = reducer(item);
-        let monkey_id = self.test.apply(item);
-        Some((monkey_id, item))
+    pub fn throw_item(
+        &mut self,
+        reducer: impl Fn(T) -> Result<T, ArithmeticError>,
+    ) -> Result<Option<(usize, T)>, ArithmeticError> {
+        let Some(item) = self.items.pop() else {
+            return Ok(None);
+        };
+        let item = reducer(self.operation.apply(&item)?)?;
+        let monkey_id = self.test.apply(&item)?;
+        Ok(Some((monkey_id, item)))
     }
 }
 
-pub struct MonkeyBusiness {
-    monkeys: Vec<Monkey>,
+/// Keeps track of the monkeys' worry levels as `T`, so the simulation can run
+/// on a wider or arbitrary-precision type when `u64` would overflow.
+pub struct MonkeyBusiness<T = u64> {
+    monkeys: Vec<Monkey<T>>,
     inspect_counts: Vec<u64>,
 }
 
-impl MonkeyBusiness {
-    pub fn with_monkeys(monkeys: Vec<Monkey>) -> MonkeyBusiness {
-        let mut inspect_counts = Vec::new();
-        inspect_counts.resize(monkeys.len(), 0);
-        MonkeyBusiness {
+impl<T: CheckedNum> MonkeyBusiness<T> {
+    pub fn with_monkeys(monkeys: Vec<Monkey>) -> Result<MonkeyBusiness<T>, ArithmeticError> {
+        let inspect_counts = vec![0; monkeys.len()];
+        let monkeys = monkeys
+            .into_iter()
+            .map(|m| {
+                Ok(Monkey {
+                    items: m
+                        .items
+                        .into_iter()
+                        .map(T::try_from_u64)
+                        .collect::<Result<_, _>>()?,
+                    operation: m.operation,
+                    test: m.test,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(MonkeyBusiness {
             monkeys,
             inspect_counts,
-        }
+        })
     }
 
-    pub fn run_round(&mut self, reducer: impl Fn(u64) -> u64) {
+    pub fn run_round(
+        &mut self,
+        reducer: impl Fn(T) -> Result<T, ArithmeticError>,
+    ) -> Result<(), ArithmeticError> {
         for throwing_monkey_id in 0..self.monkeys.len() {
             while let Some((receiving_monkey_id, item)) =
-                self.monkeys[throwing_monkey_id].throw_item(&reducer)
+                self.monkeys[throwing_monkey_id].throw_item(&reducer)?
             {
                 assert_ne!(throwing_monkey_id, receiving_monkey_id);
                 self.inspect_counts[throwing_monkey_id] += 1;
                 self.monkeys[receiving_monkey_id].receive_item(item);
             }
         }
+        Ok(())
     }
 
-    pub fn monkey_business_score(&self) -> u64 {
+    pub fn monkey_business_score(&self) -> Result<u64, ArithmeticError> {
         const NUM_MONKEYS: usize = 2;
         self.inspect_counts
             .iter()
             .sorted()
             .rev()
             .take(NUM_MONKEYS)
-            .fold(1, |acc, c| c * acc)
+            .try_fold(1, |acc, c| acc.try_mul(c))
     }
 }
 
-pub fn part_one(input: &str) -> Option<u64> {
-    let mut mb = MonkeyBusiness::with_monkeys(input.parse::<input_parser::Input>().ok()?.monkeys);
+pub fn part_one(input: &str) -> anyhow::Result<u64> {
+    let mut mb =
+        MonkeyBusiness::<u64>::with_monkeys(input.parse::<input_parser::Input>()?.monkeys)?;
     for _ in 0..20 {
-        mb.run_round(|n| n / 3);
+        mb.run_round(|n| n.try_div(&3))?;
     }
-    Some(mb.monkey_business_score())
+    Ok(mb.monkey_business_score()?)
 }
 
-pub fn part_two(input: &str) -> Option<u64> {
-    let mut mb = MonkeyBusiness::with_monkeys(input.parse::<input_parser::Input>().ok()?.monkeys);
-    let base: u64 = mb.monkeys.iter().map(|m| m.test.divisible_by).product();
+pub fn part_two(input: &str) -> anyhow::Result<u64> {
+    let mut mb =
+        MonkeyBusiness::<u64>::with_monkeys(input.parse::<input_parser::Input>()?.monkeys)?;
+    let base = mb
+        .monkeys
+        .iter()
+        .try_fold(1, |acc: u64, m| acc.try_mul(&m.test.divisible_by))?;
     for _ in 0..10000 {
-        mb.run_round(|n| n % base);
+        mb.run_round(|n| n.try_mod_floor(&base))?;
     }
-    Some(mb.monkey_business_score())
+    Ok(mb.monkey_business_score()?)
 }
 
 fn main() {
@@ -145,27 +175,138 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+
+    fn random_token(rng: &mut Rng) -> OperationToken {
+        match rng.chance(1, 3) {
+            true => OperationToken::Old,
+            false => OperationToken::UnsignedInt(rng.range(0..=20)),
+        }
+    }
+
+    fn random_monkeys(rng: &mut Rng) -> Vec<Monkey> {
+        let count = rng.range(2..=8);
+        (0..count)
+            .map(|id| {
+                let mut targets = (0..count).filter(|t| *t != id).collect::<Vec<_>>();
+                rng.shuffle(&mut targets);
+                Monkey {
+                    items: (0..rng.range(1..=5)).map(|_| rng.range(1..=99)).collect(),
+                    operation: Operation {
+                        lhs: random_token(rng),
+                        operator: rng
+                            .choose(&[Operator::Add, Operator::Multiply, Operator::Subtract])
+                            .clone(),
+                        rhs: random_token(rng),
+                    },
+                    test: Test {
+                        divisible_by: rng.range(1..=23),
+                        true_monkey_id: targets[0],
+                        false_monkey_id: *rng.choose(&targets),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    fn format_monkeys(monkeys: &[Monkey]) -> String {
+        let token = |t: &OperationToken| match t {
+            OperationToken::Old => "old".to_string(),
+            OperationToken::UnsignedInt(n) => n.to_string(),
+        };
+        monkeys
+            .iter()
+            .enumerate()
+            .map(|(id, m)| {
+                let operator = match m.operation.operator {
+                    Operator::Add => '+',
+                    Operator::Multiply => '*',
+                    Operator::Divide => '/',
+                    Operator::Subtract => '-',
+                };
+                format!(
+                    "Monkey {id}:\n  Starting items: {}\n  Operation: new = {} {operator} {}\n  Test: divisible by {}\n    If true: throw to monkey {}\n    If false: throw to monkey {}",
+                    m.items.iter().join(", "),
+                    token(&m.operation.lhs),
+                    token(&m.operation.rhs),
+                    m.test.divisible_by,
+                    m.test.true_monkey_id,
+                    m.test.false_monkey_id,
+                )
+            })
+            .join("\n\n")
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 11);
-        assert_eq!(part_one(&input), Some(10605));
+        assert_eq!(part_one(&input).unwrap(), 10605);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 11);
-        assert_eq!(part_two(&input), Some(2713310158));
+        assert_eq!(part_two(&input).unwrap(), 2713310158);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let monkeys = random_monkeys(&mut rng);
+            let input = format_monkeys(&monkeys);
+            assert_eq!(
+                input.parse::<input_parser::Input>().unwrap().monkeys,
+                monkeys
+            );
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = format_monkeys(&random_monkeys(&mut rng));
+            let input = rng.mutate(&input, "0123456789 ,:+-*/\n");
+            let _ = part_one(&input);
+        }
+    }
+
+    #[test]
+    fn unreduced_worry_overflows_u64() {
+        let input = advent_of_code::read_file("examples", 11);
+        let monkeys = input.parse::<input_parser::Input>().unwrap().monkeys;
+        let mut mb = MonkeyBusiness::<u64>::with_monkeys(monkeys).unwrap();
+        let result = (0..20).try_for_each(|_| mb.run_round(Ok));
+        assert!(matches!(result, Err(ArithmeticError::Overflow { .. })));
+    }
+
+    #[test]
+    fn unreduced_worry_with_big_integers() {
+        let input = advent_of_code::read_file("examples", 11);
+        let monkeys = input.parse::<input_parser::Input>().unwrap().monkeys;
+        let mut mb = MonkeyBusiness::<num::BigUint>::with_monkeys(monkeys).unwrap();
+        for _ in 0..5 {
+            mb.run_round(Ok).unwrap();
+        }
+        let mut reduced = MonkeyBusiness::<u64>::with_monkeys(
+            input.parse::<input_parser::Input>().unwrap().monkeys,
+        )
+        .unwrap();
+        for _ in 0..5 {
+            reduced.run_round(|n| n.try_mod_floor(&96577)).unwrap();
+        }
+        assert_eq!(mb.inspect_counts, reduced.inspect_counts);
     }
 }
 
-mod input_parser {
+pub mod input_parser {
     use super::{Monkey, Operation, OperationToken, Operator, Test};
     use nom::{
         branch::alt,
         bytes::complete::tag,
         character::complete::{digit1, newline, space0, space1},
-        combinator::{map, map_res},
+        combinator::{map, map_res, verify},
         multi::separated_list1,
         sequence::tuple,
         Finish, IResult,
@@ -295,23 +436,35 @@ mod input_parser {
         )(input)
     }
 
+    /// Every monkey must throw to another monkey that exists.
+    fn valid_targets(monkeys: &[Monkey]) -> bool {
+        monkeys.iter().enumerate().all(|(id, m)| {
+            [m.test.true_monkey_id, m.test.false_monkey_id]
+                .iter()
+                .all(|target| *target != id && *target < monkeys.len())
+        })
+    }
+
     fn input(input: &str) -> IResult<&str, Input> {
         map(
-            separated_list1(
-                tuple((newline, newline)),
-                map(
-                    tuple((
-                        space0,
-                        tag("Monkey"),
-                        space1,
-                        number_usize,
-                        space0,
-                        tag(":"),
-                        newline,
-                        monkey,
-                    )),
-                    |t| t.7,
+            verify(
+                separated_list1(
+                    tuple((newline, newline)),
+                    map(
+                        tuple((
+                            space0,
+                            tag("Monkey"),
+                            space1,
+                            number_usize,
+                            space0,
+                            tag(":"),
+                            newline,
+                            monkey,
+                        )),
+                        |t| t.7,
+                    ),
                 ),
+                |monkeys: &Vec<Monkey>| valid_targets(monkeys),
             ),
             |monkeys| Input { monkeys },
         )(input)
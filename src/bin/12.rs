@@ -1,9 +1,9 @@
+use anyhow::{bail, Context};
 use petgraph::algo::dijkstra;
 use petgraph::prelude::*;
 use petgraph::Graph;
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::error::Error;
 use std::str::FromStr;
 
 #[derive(Debug, Clone)]
@@ -15,31 +15,32 @@ pub struct HeightMap {
 }
 
 impl FromStr for HeightMap {
-    type Err = Box<dyn Error>;
+    type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut map: HashMap<(i32, i32), (char, NodeIndex)> = HashMap::new();
         let mut graph: Graph<(), (), Directed> = Graph::new();
         let mut low_points: HashSet<NodeIndex> = HashSet::new();
-        let mut start: NodeIndex = NodeIndex::new(0);
-        let mut goal: NodeIndex = NodeIndex::new(0);
+        let mut start: Option<NodeIndex> = None;
+        let mut goal: Option<NodeIndex> = None;
         for (col, l) in s.lines().enumerate() {
             for (row, c) in l.chars().enumerate() {
                 let node = graph.add_node(());
                 let height = match c {
                     'S' => {
-                        start = node;
+                        start = Some(node);
                         'a'
                     }
                     'E' => {
-                        goal = node;
+                        goal = Some(node);
                         'z'
                     }
                     'a' => {
                         low_points.insert(node);
                         'a'
                     }
-                    x => x,
+                    x @ 'b'..='z' => x,
+                    x => bail!("invalid height '{x}' on line {}", col + 1),
                 };
                 map.insert((col as i32, row as i32), (height, node));
             }
@@ -62,8 +63,8 @@ impl FromStr for HeightMap {
         }
         Ok(HeightMap {
             graph,
-            start,
-            goal,
+            start: start.context("no start position")?,
+            goal: goal.context("no goal position")?,
             low_points,
         })
     }
@@ -92,14 +93,18 @@ impl HeightMap {
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let height_map: HeightMap = input.parse().ok()?;
-    height_map.shortest_start_goal_path()
+pub fn part_one(input: &str) -> anyhow::Result<u32> {
+    let height_map: HeightMap = input.parse()?;
+    height_map
+        .shortest_start_goal_path()
+        .context("no path from start to goal")
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let height_map: HeightMap = input.parse().ok()?;
-    height_map.shortest_hiking_trail()
+pub fn part_two(input: &str) -> anyhow::Result<u32> {
+    let height_map: HeightMap = input.parse()?;
+    height_map
+        .shortest_hiking_trail()
+        .context("no hiking trail reaches the goal")
 }
 
 fn main() {
@@ -111,16 +116,56 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+
+    fn random_input(rng: &mut Rng) -> String {
+        let (width, height) = (rng.range(2..=12), rng.range(2..=12));
+        let mut grid = (0..height)
+            .map(|_| {
+                (0..width)
+                    .map(|_| rng.range(b'a'..=b'z'))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        grid[rng.range(0..=height - 1)][rng.range(0..=width - 1)] = b'S';
+        grid[rng.range(0..=height - 1)][rng.range(0..=width - 1)] = b'E';
+        grid.into_iter()
+            .map(|row| String::from_utf8(row).unwrap())
+            .join("\n")
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 12);
-        assert_eq!(part_one(&input), Some(31));
+        assert_eq!(part_one(&input).unwrap(), 31);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 12);
-        assert_eq!(part_two(&input), Some(29));
+        assert_eq!(part_two(&input).unwrap(), 29);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let input = random_input(&mut rng);
+            let height_map: Result<HeightMap, _> = input.parse();
+            // The goal may have overwritten the start
+            assert_eq!(height_map.is_ok(), input.contains('S'), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = random_input(&mut rng);
+            let input = rng.mutate(&input, "abcdefghijklmnopqrstuvwxyzSE\n");
+            let _ = part_one(&input);
+            let _ = part_two(&input);
+        }
     }
 }
@@ -1,5 +1,6 @@
 use std::cmp::Ordering;
 
+use anyhow::Context;
 use itertools::Itertools;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -76,14 +77,16 @@ impl DistressSignal {
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let distress_signal: DistressSignal = input.parse().ok()?;
-    Some(distress_signal.check())
+pub fn part_one(input: &str) -> anyhow::Result<u32> {
+    let distress_signal: DistressSignal = input.parse()?;
+    Ok(distress_signal.check())
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let distress_signal: DistressSignal = input.parse().ok()?;
-    distress_signal.decoder_key()
+pub fn part_two(input: &str) -> anyhow::Result<u32> {
+    let distress_signal: DistressSignal = input.parse()?;
+    distress_signal
+        .decoder_key()
+        .context("divider packets not found")
 }
 
 fn main() {
@@ -95,17 +98,76 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+
+    fn random_packet(rng: &mut Rng, depth: usize) -> PacketData {
+        if depth == 0 || rng.chance(1, 3) {
+            PacketData::Number(rng.range(0..=10))
+        } else {
+            PacketData::List(
+                (0..rng.range(0..=4))
+                    .map(|_| random_packet(rng, depth - 1))
+                    .collect(),
+            )
+        }
+    }
+
+    fn format_packet(packet: &PacketData) -> String {
+        match packet {
+            PacketData::Number(n) => n.to_string(),
+            PacketData::List(items) => format!("[{}]", items.iter().map(format_packet).join(",")),
+        }
+    }
+
+    fn random_signal(rng: &mut Rng) -> DistressSignal {
+        let signal = (0..rng.range(1..=8))
+            .map(|_| {
+                let mut list = || PacketData::List(vec![random_packet(rng, 4)]);
+                (list(), list())
+            })
+            .collect();
+        DistressSignal { signal }
+    }
+
+    fn format_signal(distress_signal: &DistressSignal) -> String {
+        distress_signal
+            .signal
+            .iter()
+            .map(|(a, b)| format!("{}\n{}", format_packet(a), format_packet(b)))
+            .join("\n\n")
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 13);
-        assert_eq!(part_one(&input), Some(13));
+        assert_eq!(part_one(&input).unwrap(), 13);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 13);
-        assert_eq!(part_two(&input), Some(140));
+        assert_eq!(part_two(&input).unwrap(), 140);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let distress_signal = random_signal(&mut rng);
+            let input = format_signal(&distress_signal);
+            assert_eq!(input.parse::<DistressSignal>(), Ok(distress_signal));
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = format_signal(&random_signal(&mut rng));
+            let input = rng.mutate(&input, "[],0123456789\n");
+            let _ = part_one(&input);
+            let _ = part_two(&input);
+        }
     }
 }
 
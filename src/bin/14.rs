@@ -1,10 +1,15 @@
 use std::collections::HashMap;
-use std::error::Error;
 use std::fmt;
 use std::hash::Hash;
+use std::io::Write;
 use std::iter::from_fn;
+use std::path::PathBuf;
+use std::process;
 use std::str::FromStr;
+use std::time::Duration;
 
+use advent_of_code::animation::Recorder;
+use anyhow::{bail, Context};
 use itertools::Itertools;
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Hash)]
@@ -33,27 +38,33 @@ pub struct Grid {
 }
 
 impl FromStr for Grid {
-    type Err = Box<dyn Error>;
+    type Err = anyhow::Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut grid = Grid::new();
-        s.lines().for_each(|l| {
-            l.split(" -> ")
-                .filter_map(|p| p.split_once(','))
-                .filter_map(|(x_s, y_s)| {
-                    Some(Position {
-                        x: x_s.parse::<i32>().ok()?,
-                        y: y_s.parse::<i32>().ok()?,
+        for l in s.lines() {
+            let path = l
+                .split(" -> ")
+                .map(|p| {
+                    let (x_s, y_s) = p
+                        .split_once(',')
+                        .with_context(|| format!("invalid point \"{p}\""))?;
+                    Ok(Position {
+                        x: x_s.parse::<i32>()?,
+                        y: y_s.parse::<i32>()?,
                     })
                 })
-                .tuple_windows()
-                .for_each(|(start, end)| {
-                    for x in start.x.min(end.x)..=start.x.max(end.x) {
-                        for y in start.y.min(end.y)..=start.y.max(end.y) {
-                            grid.add_object(Position { x, y }, Object::Rock);
-                        }
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            for (start, end) in path.into_iter().tuple_windows() {
+                if start.x != end.x && start.y != end.y {
+                    bail!("rock path segment {start:?} -> {end:?} is diagonal");
+                }
+                for x in start.x.min(end.x)..=start.x.max(end.x) {
+                    for y in start.y.min(end.y)..=start.y.max(end.y) {
+                        grid.add_object(Position { x, y }, Object::Rock);
                     }
-                })
-        });
+                }
+            }
+        }
         Ok(grid)
     }
 }
@@ -148,38 +159,151 @@ impl Grid {
     }
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
-    let mut grid: Grid = input.parse().ok()?;
+pub fn part_one(input: &str) -> anyhow::Result<usize> {
+    let mut grid: Grid = input.parse()?;
     let grains_of_sand = from_fn(|| grid.add_sand()).count();
-    Some(grains_of_sand)
+    Ok(grains_of_sand)
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
-    let mut grid: Grid = input.parse().ok()?;
+pub fn part_two(input: &str) -> anyhow::Result<usize> {
+    let mut grid: Grid = input.parse()?;
     grid.set_floor(2);
     let grains_of_sand = from_fn(|| grid.add_sand()).count();
-    Some(grains_of_sand + 1)
+    Ok(grains_of_sand + 1)
+}
+
+const FRAME_DELAY: Duration = Duration::from_millis(40);
+
+/// The cave of part one after every grain of sand comes to rest.
+fn animate<W: Write>(input: &str, mut recorder: Recorder<W>) -> anyhow::Result<W> {
+    let mut grid: Grid = input.parse()?;
+    recorder.record(|| &grid)?;
+    while grid.add_sand().is_some() {
+        recorder.record(|| &grid)?;
+    }
+    recorder.record_last(|| &grid)?;
+    recorder.finish()
+}
+
+struct Args {
+    animate: Option<PathBuf>,
+    every: usize,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        animate: args.opt_value_from_str("--animate")?,
+        every: args.opt_value_from_str("--every")?.unwrap_or(1),
+    })
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 14);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    match parse_args() {
+        Ok(Args { animate: None, .. }) => {
+            advent_of_code::solve!(1, part_one, input);
+            advent_of_code::solve!(2, part_two, input);
+        }
+        Ok(Args {
+            animate: Some(path),
+            every,
+        }) => {
+            let recorder = Recorder::create(&path, FRAME_DELAY);
+            if let Err(e) = recorder.and_then(|r| animate(input, r.with_sampling(every))) {
+                eprintln!("failed: {:#}", e);
+                process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            process::exit(1);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::animation::{asciicast_frames, AnimationFormat};
+    use advent_of_code::helpers::Rng;
+
+    fn random_paths(rng: &mut Rng) -> Vec<Vec<Position>> {
+        (0..rng.range(1..=6))
+            .map(|_| {
+                let mut position = Position {
+                    x: rng.range(480..=520),
+                    y: rng.range(1..=30),
+                };
+                let mut path = vec![position.clone()];
+                for _ in 0..rng.range(1..=4) {
+                    match rng.chance(1, 2) {
+                        true => position.x += rng.range(-6..=6),
+                        false => position.y = (position.y + rng.range(-6..=6)).max(1),
+                    }
+                    path.push(position.clone());
+                }
+                path
+            })
+            .collect()
+    }
+
+    fn format_paths(paths: &[Vec<Position>]) -> String {
+        paths
+            .iter()
+            .map(|path| path.iter().map(|p| format!("{},{}", p.x, p.y)).join(" -> "))
+            .join("\n")
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 14);
-        assert_eq!(part_one(&input), Some(24));
+        assert_eq!(part_one(&input).unwrap(), 24);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 14);
-        assert_eq!(part_two(&input), Some(93));
+        assert_eq!(part_two(&input).unwrap(), 93);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let paths = random_paths(&mut rng);
+            let grid: Grid = format_paths(&paths).parse().unwrap();
+            assert!(paths
+                .iter()
+                .flatten()
+                .all(|p| grid.objects.get(p) == Some(&Object::Rock)));
+            assert!(grid.objects.values().all(|o| *o == Object::Rock));
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = format_paths(&random_paths(&mut rng));
+            let _ = rng.mutate(&input, "0123456789,-> \n").parse::<Grid>();
+        }
+    }
+
+    #[test]
+    fn diagonal_rock_paths_are_rejected() {
+        assert!("498,4 -> 500,6".parse::<Grid>().is_err());
+    }
+
+    #[test]
+    fn animate_example() {
+        let input = advent_of_code::read_file("examples", 14);
+        let recorder = Recorder::new(Vec::new(), AnimationFormat::Asciicast, FRAME_DELAY);
+        let cast = animate(&input, recorder).unwrap();
+        let frames = asciicast_frames(&String::from_utf8(cast).unwrap()).unwrap();
+        assert_eq!(frames.len(), 1 + 24);
+        assert_eq!(frames[0].matches('o').count(), 0);
+        assert_eq!(frames[24].matches('o').count(), 24);
+        assert!(frames[24].starts_with("......+...\n"));
     }
 }
@@ -1,4 +1,5 @@
-use anyhow::anyhow;
+use advent_of_code::helpers::{Interval, IntervalSet};
+use anyhow::{anyhow, bail, Context};
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::HashSet;
@@ -72,35 +73,33 @@ impl FromStr for SensorReading {
     }
 }
 
-pub fn covered_per_row(input: &str, row_y: i64) -> Option<usize> {
-    let mut beacons: HashSet<Position> = HashSet::new();
-    let mut covered: HashSet<Position> = HashSet::new();
+fn parse_input(input: &str) -> anyhow::Result<Vec<SensorReading>> {
     input
         .lines()
-        .filter_map(|l| l.parse::<SensorReading>().ok())
-        .for_each(|SensorReading { sensor, beacon }| {
-            let dist = sensor.manhattan_distance(&beacon);
-            let mut marker = Position {
-                x: sensor.x,
-                y: row_y,
-            };
-            while sensor.manhattan_distance(&marker) <= dist {
-                covered.insert(marker.clone());
-                marker.x += 1;
-            }
-            let mut marker = Position {
-                x: sensor.x,
-                y: row_y,
-            };
-            while sensor.manhattan_distance(&marker) <= dist {
-                covered.insert(marker.clone());
-                marker.x -= 1;
-            }
-            if row_y == beacon.y {
-                beacons.insert(beacon);
-            }
-        });
-    Some(covered.len() - beacons.len())
+        .enumerate()
+        .map(|(i, l)| {
+            l.parse::<SensorReading>()
+                .with_context(|| format!("invalid sensor reading on line {}", i + 1))
+        })
+        .collect()
+}
+
+pub fn covered_per_row(input: &str, row_y: i64) -> anyhow::Result<usize> {
+    let readings = parse_input(input)?;
+    let covered = readings
+        .iter()
+        .filter_map(|reading| {
+            let reach = reading.strength() - (reading.sensor.y - row_y).abs();
+            Interval::new(reading.sensor.x - reach, reading.sensor.x + reach)
+        })
+        .collect::<IntervalSet<i64>>();
+    // Every beacon lies within reach of its own sensor
+    let beacons = readings
+        .iter()
+        .filter(|reading| reading.beacon.y == row_y)
+        .map(|reading| reading.beacon.x)
+        .collect::<HashSet<_>>();
+    Ok(covered.len() as usize - beacons.len())
 }
 
 fn abs(val: z3::ast::Int) -> z3::ast::Int {
@@ -114,7 +113,7 @@ fn within_range<'a>(val: &z3::ast::Int<'a>, low: i64, high: i64) -> z3::ast::Boo
     z3::ast::Bool::and(val.get_ctx(), &[&val.ge(&low), &val.le(&high)])
 }
 
-pub fn find_beacon(input: &str, search_space: i64) -> Option<i64> {
+pub fn find_beacon(input: &str, search_space: i64) -> anyhow::Result<i64> {
     use z3::*;
     let ctx = Context::new(&Config::new());
     let goal_x = ast::Int::new_const(&ctx, "x");
@@ -122,29 +121,32 @@ pub fn find_beacon(input: &str, search_space: i64) -> Option<i64> {
     let solver = Solver::new(&ctx);
     solver.assert(&within_range(&goal_x, 0, search_space));
     solver.assert(&within_range(&goal_y, 0, search_space));
-    for reading in input
-        .lines()
-        .filter_map(|l| l.parse::<SensorReading>().ok())
-    {
+    for reading in parse_input(input)? {
         let x = ast::Int::from_i64(&ctx, reading.sensor.x);
         let y = ast::Int::from_i64(&ctx, reading.sensor.y);
         let strength = ast::Int::from_i64(&ctx, reading.strength());
         solver.assert(&(abs(&goal_x - x) + abs(&goal_y - y)).gt(&strength));
     }
     if solver.check() != SatResult::Sat {
-        return None;
+        bail!("no position within the search space is out of sensor range");
     }
-    let model = solver.get_model()?;
-    let xv = model.eval(&goal_x, true)?.as_i64()?;
-    let yv = model.eval(&goal_y, true)?.as_i64()?;
-    Some(4000000 * xv + yv)
+    let model = solver.get_model().context("solver produced no model")?;
+    let xv = model
+        .eval(&goal_x, true)
+        .and_then(|x| x.as_i64())
+        .context("could not evaluate beacon x")?;
+    let yv = model
+        .eval(&goal_y, true)
+        .and_then(|y| y.as_i64())
+        .context("could not evaluate beacon y")?;
+    Ok(4000000 * xv + yv)
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
+pub fn part_one(input: &str) -> anyhow::Result<usize> {
     covered_per_row(input, 2000000)
 }
 
-pub fn part_two(input: &str) -> Option<i64> {
+pub fn part_two(input: &str) -> anyhow::Result<i64> {
     find_beacon(input, 4000000)
 }
 
@@ -157,16 +159,62 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+
+    fn random_readings(rng: &mut Rng) -> Vec<SensorReading> {
+        let count = rng.range(1..=20);
+        let mut position = || Position {
+            x: rng.range(-10..=4_000_000),
+            y: rng.range(-10..=4_000_000),
+        };
+        (0..count)
+            .map(|_| SensorReading {
+                sensor: position(),
+                beacon: position(),
+            })
+            .collect()
+    }
+
+    fn format_readings(readings: &[SensorReading]) -> String {
+        readings
+            .iter()
+            .map(|SensorReading { sensor, beacon }| {
+                format!(
+                    "Sensor at x={}, y={}: closest beacon is at x={}, y={}",
+                    sensor.x, sensor.y, beacon.x, beacon.y
+                )
+            })
+            .join("\n")
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 15);
-        assert_eq!(covered_per_row(&input, 10), Some(26));
+        assert_eq!(covered_per_row(&input, 10).unwrap(), 26);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 15);
-        assert_eq!(find_beacon(&input, 20), Some(56000011));
+        assert_eq!(find_beacon(&input, 20).unwrap(), 56000011);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let readings = random_readings(&mut rng);
+            assert_eq!(parse_input(&format_readings(&readings)).unwrap(), readings);
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = format_readings(&random_readings(&mut rng));
+            let _ = parse_input(&rng.mutate(&input, "0123456789-=,: \n"));
+        }
     }
 }
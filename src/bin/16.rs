@@ -31,7 +31,7 @@ impl FromStr for ValveSystem {
             static ref RE: Regex = Regex::new(r"^Valve (?P<name>[A-Z]{2}) has flow rate=(?P<rate>\d+); tunnels? leads? to valves? (?P<tunnels>.+)$").expect("Regex did not compile");
         }
         let mut graph = Graph::new();
-        let mut start = NodeIndex::new(0);
+        let mut start = None;
         let mut node_map: HashMap<&str, NodeIndex> = HashMap::new();
         let mut raw_edges: Vec<(&str, &str)> = Vec::new();
         for l in s.lines() {
@@ -61,7 +61,7 @@ impl FromStr for ValveSystem {
             });
             node_map.insert(name, node);
             if name == START_VALVE {
-                start = node;
+                start = Some(node);
             }
         }
         for (from_raw, to_raw) in raw_edges {
@@ -73,6 +73,7 @@ impl FromStr for ValveSystem {
                 .ok_or_else(|| anyhow!("Valve {to_raw} not found"))?;
             graph.add_edge(from, to, 1);
         }
+        let start = start.ok_or_else(|| anyhow!("Valve {START_VALVE} not found"))?;
         Ok(ValveSystem { start, graph })
     }
 }
@@ -194,18 +195,18 @@ impl ValveSystem {
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let mut valve_system = input.parse::<ValveSystem>().ok()?;
+pub fn part_one(input: &str) -> anyhow::Result<u32> {
+    let mut valve_system = input.parse::<ValveSystem>()?;
     //println!("{:?}", petgraph::dot::Dot::new(&valve_system.graph));
     valve_system.optimize();
     //println!("{:?}", petgraph::dot::Dot::new(&optimized.graph));
-    Some(valve_system.max_pressure(30, None))
+    Ok(valve_system.max_pressure(30, None))
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let mut valve_system = input.parse::<ValveSystem>().ok()?;
+pub fn part_two(input: &str) -> anyhow::Result<u32> {
+    let mut valve_system = input.parse::<ValveSystem>()?;
     valve_system.optimize();
-    Some(valve_system.max_pressure(26, Some(26)))
+    Ok(valve_system.max_pressure(26, Some(26)))
 }
 
 fn main() {
@@ -217,16 +218,80 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+
+    /// Valve lines and the number of tunnels they describe.
+    fn random_input(rng: &mut Rng) -> (String, usize) {
+        let mut names = (0..rng.range(1..=15))
+            .map(|_| {
+                let mut letter = || rng.range(b'A'..=b'Z') as char;
+                format!("{}{}", letter(), letter())
+            })
+            .chain(["AA".to_string()])
+            .unique()
+            .collect::<Vec<_>>();
+        rng.shuffle(&mut names);
+        let mut tunnel_count = 0;
+        let lines = names
+            .iter()
+            .map(|name| {
+                let tunnels = (0..rng.range(1..=3))
+                    .map(|_| rng.choose(&names).clone())
+                    .collect::<Vec<_>>();
+                tunnel_count += tunnels.len();
+                let rate = if rng.chance(1, 2) {
+                    0
+                } else {
+                    rng.range(1..=25)
+                };
+                match tunnels.len() {
+                    1 => format!(
+                        "Valve {name} has flow rate={rate}; tunnel leads to valve {}",
+                        tunnels[0]
+                    ),
+                    _ => format!(
+                        "Valve {name} has flow rate={rate}; tunnels lead to valves {}",
+                        tunnels.join(", ")
+                    ),
+                }
+            })
+            .join("\n");
+        (lines, tunnel_count)
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 16);
-        assert_eq!(part_one(&input), Some(1651));
+        assert_eq!(part_one(&input).unwrap(), 1651);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 16);
-        assert_eq!(part_two(&input), Some(1707));
+        assert_eq!(part_two(&input).unwrap(), 1707);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let (input, tunnel_count) = random_input(&mut rng);
+            let valve_system = input.parse::<ValveSystem>().unwrap();
+            assert_eq!(valve_system.graph.node_count(), input.lines().count());
+            assert_eq!(valve_system.graph.edge_count(), tunnel_count);
+            assert_eq!(valve_system.graph[valve_system.start].name, "AA");
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let (input, _) = random_input(&mut rng);
+            let _ = rng
+                .mutate(&input, "ABCZ0123456789=;, \n")
+                .parse::<ValveSystem>();
+        }
     }
 }
@@ -1,7 +1,13 @@
 use std::collections::BTreeSet;
 use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
 use std::str::FromStr;
+use std::time::Duration;
 
+use advent_of_code::animation::Recorder;
+use anyhow::{anyhow, bail, Context};
 use itertools::Itertools;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -19,13 +25,17 @@ impl FromStr for Pattern {
     type Err = anyhow::Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let shifts = s
+            .trim_end()
             .chars()
-            .filter_map(|c| match c {
-                '<' => Some(Shift::Left),
-                '>' => Some(Shift::Right),
-                _ => None,
+            .map(|c| match c {
+                '<' => Ok(Shift::Left),
+                '>' => Ok(Shift::Right),
+                _ => Err(anyhow!("'{c}' is not a valid shift")),
             })
-            .collect();
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if shifts.is_empty() {
+            bail!("Pattern is empty");
+        }
         Ok(Pattern { shifts })
     }
 }
@@ -228,9 +238,7 @@ impl fmt::Display for TetrisChamber {
             None => BTreeSet::new(),
             Some(shape) => shape.iter().collect::<BTreeSet<_>>(),
         };
-        let falling_rocks_height = falling_rocks.last().map(|p| p.y + 1).unwrap_or(1);
-        let rows = self.height().max(falling_rocks_height);
-        for y in (0..rows).rev() {
+        for y in (0..self.rows()).rev() {
             write!(f, "|")?;
             for x in 0..Self::WIDTH {
                 if self.rocks.contains(&Position { x, y }) {
@@ -280,12 +288,18 @@ impl TetrisChamber {
     }
 
     pub fn shape_fall(&mut self) {
+        self.shape_fall_traced(&mut |_| {});
+    }
+
+    /// Drops the next rock like `shape_fall`, calling `trace` with the
+    /// chamber when the rock appears, after every jet push and fall, and once
+    /// it comes to rest.
+    pub fn shape_fall_traced(&mut self, trace: &mut dyn FnMut(&TetrisChamber)) {
         const FALL_HEIGHT: i64 = 3;
         let shape_height = self.height() + FALL_HEIGHT;
         let shape_type = self.shape_iter.next().expect("Infinite iterator");
         self.falling_shape = Some(Shape::new(shape_type, shape_height));
-        //println!("The rock begins falling:");
-        //println!("{self}");
+        trace(self);
         loop {
             // Shift
             let direction = self.shift_iter.next().expect("Infinite iterator");
@@ -294,11 +308,8 @@ impl TetrisChamber {
                 shifted.shift(&direction);
                 if !self.collides(&shifted) {
                     self.falling_shape = Some(shifted);
-                    //println!("Jet of gas pushes rock {direction:?}:");
-                } else {
-                    //println!("Jet of gas pushes rock {direction:?}, but nothing happens:");
                 }
-                //println!("{self}");
+                trace(self);
             }
             // Fall
             {
@@ -306,30 +317,37 @@ impl TetrisChamber {
                 fell.fall();
                 if !self.collides(&fell) {
                     self.falling_shape = Some(fell);
-                    //println!("Rock falls 1 unit:");
-                    //println!("{self}");
+                    trace(self);
                 } else {
                     break;
                 }
             }
         }
-        //println!("Rock falls 1 unit, causing it to come to rest:");
-        //println!("{self}");
         self.rocks
             .extend(self.falling_shape.take().unwrap().into_iter());
+        trace(self);
     }
 
     pub fn height(&self) -> i64 {
         self.rocks.last().map(|p| p.y + 1).unwrap_or(0)
     }
+
+    /// Rows drawn above the floor, enough for the rocks and the falling rock.
+    pub fn rows(&self) -> i64 {
+        let falling_rocks_height = match &self.falling_shape {
+            None => 1,
+            Some(shape) => shape.iter().map(|p| p.y + 1).max().unwrap_or(1),
+        };
+        self.height().max(falling_rocks_height)
+    }
 }
 
-pub fn part_one(input: &str) -> Option<i64> {
-    let mut tetris_chamber = TetrisChamber::new(input.parse::<Pattern>().unwrap());
+pub fn part_one(input: &str) -> anyhow::Result<i64> {
+    let mut tetris_chamber = TetrisChamber::new(input.parse::<Pattern>()?);
     for _ in 0..2022 {
         tetris_chamber.shape_fall();
     }
-    Some(tetris_chamber.height())
+    Ok(tetris_chamber.height())
 }
 
 pub fn find_cycle_to_run_n<F>(
@@ -371,35 +389,163 @@ where
     Some(offset_sum + cycle_count * cycle_sum + reminder_sum)
 }
 
-pub fn part_two(input: &str) -> Option<i64> {
-    let mut tetris_chamber = TetrisChamber::new(input.parse::<Pattern>().unwrap());
+pub fn part_two(input: &str) -> anyhow::Result<i64> {
+    let mut tetris_chamber = TetrisChamber::new(input.parse::<Pattern>()?);
 
     find_cycle_to_run_n(1_000_000_000_000, 3000, 250, || {
         let h = tetris_chamber.height();
         tetris_chamber.shape_fall();
         h
     })
+    .context("no repeating cycle in tower heights")
+}
+
+const FRAME_DELAY: Duration = Duration::from_millis(30);
+
+/// Every step of the first `rocks` rocks falling, padded to the final height
+/// so the floor stays in place.
+fn animate<W: Write>(input: &str, rocks: usize, mut recorder: Recorder<W>) -> anyhow::Result<W> {
+    let pattern = input.parse::<Pattern>()?;
+    // Run it once to know the rows every frame has to cover
+    let mut tetris_chamber = TetrisChamber::new(pattern.clone());
+    let mut rows = 0;
+    for _ in 0..rocks {
+        tetris_chamber.shape_fall_traced(&mut |chamber| rows = rows.max(chamber.rows()));
+    }
+    let empty_row = format!("|{}|\n", ".".repeat(TetrisChamber::WIDTH as usize));
+    let draw = |chamber: &TetrisChamber| {
+        let padding = empty_row.repeat((rows - chamber.rows()) as usize);
+        padding + &chamber.to_string()
+    };
+    let mut tetris_chamber = TetrisChamber::new(pattern);
+    let mut recorded = Ok(());
+    for _ in 0..rocks {
+        tetris_chamber.shape_fall_traced(&mut |chamber| {
+            if recorded.is_ok() {
+                recorded = recorder.record(|| draw(chamber));
+            }
+        });
+    }
+    recorded?;
+    recorder.record_last(|| draw(&tetris_chamber))?;
+    recorder.finish()
+}
+
+struct Args {
+    animate: Option<PathBuf>,
+    every: usize,
+    rocks: usize,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        animate: args.opt_value_from_str("--animate")?,
+        every: args.opt_value_from_str("--every")?.unwrap_or(1),
+        rocks: args.opt_value_from_str("--rocks")?.unwrap_or(10),
+    })
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 17);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    match parse_args() {
+        Ok(Args { animate: None, .. }) => {
+            advent_of_code::solve!(1, part_one, input);
+            advent_of_code::solve!(2, part_two, input);
+        }
+        Ok(Args {
+            animate: Some(path),
+            every,
+            rocks,
+        }) => {
+            let recorder = Recorder::create(&path, FRAME_DELAY);
+            if let Err(e) = recorder.and_then(|r| animate(input, rocks, r.with_sampling(every))) {
+                eprintln!("failed: {:#}", e);
+                process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            process::exit(1);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::animation::{asciicast_frames, AnimationFormat};
+    use advent_of_code::helpers::Rng;
+
+    fn random_input(rng: &mut Rng) -> String {
+        (0..rng.range(1..=100))
+            .map(|_| *rng.choose(&['<', '>']))
+            .collect()
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 17);
-        assert_eq!(part_one(&input), Some(3068));
+        assert_eq!(part_one(&input).unwrap(), 3068);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 17);
-        assert_eq!(part_two(&input), Some(1514285714288));
+        assert_eq!(part_two(&input).unwrap(), 1514285714288);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let input = random_input(&mut rng);
+            let pattern = format!("{input}\n").parse::<Pattern>().unwrap();
+            let printed = pattern
+                .shifts
+                .iter()
+                .map(|s| match s {
+                    Shift::Left => '<',
+                    Shift::Right => '>',
+                })
+                .collect::<String>();
+            assert_eq!(printed, input);
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = random_input(&mut rng);
+            let _ = rng.mutate(&input, "<>").parse::<Pattern>();
+        }
+    }
+
+    #[test]
+    fn empty_pattern_is_rejected() {
+        assert!("".parse::<Pattern>().is_err());
+        assert!("\n".parse::<Pattern>().is_err());
+    }
+
+    #[test]
+    fn animate_example() {
+        let input = advent_of_code::read_file("examples", 17);
+        let recorder = Recorder::new(Vec::new(), AnimationFormat::Asciicast, FRAME_DELAY);
+        let cast = animate(&input, 1, recorder).unwrap();
+        let frames = asciicast_frames(&String::from_utf8(cast).unwrap()).unwrap();
+        assert_eq!(frames.len(), 9);
+        assert_eq!(
+            frames[0],
+            "|..@@@@.|\n|.......|\n|.......|\n|.......|\n+-------+\n"
+        );
+        assert_eq!(
+            frames[1],
+            "|...@@@@|\n|.......|\n|.......|\n|.......|\n+-------+\n"
+        );
+        assert_eq!(
+            frames[8],
+            "|.......|\n|.......|\n|.......|\n|..####.|\n+-------+\n"
+        );
     }
 }
@@ -1,6 +1,10 @@
 use std::collections::HashSet;
+use std::ops::RangeInclusive;
 use std::str::FromStr;
 
+use anyhow::{anyhow, bail, Context};
+use itertools::Itertools;
+
 #[derive(Debug, Clone, PartialEq, Eq, Default, Hash, PartialOrd, Ord)]
 pub struct Cube {
     pub x: i32,
@@ -53,20 +57,22 @@ impl Cube {
 impl FromStr for Cube {
     type Err = anyhow::Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let vals = s
+        // Leaves room for the neighbours of the range surrounding the cube
+        const LIMITS: RangeInclusive<i32> = (i32::MIN + 2)..=(i32::MAX - 2);
+        let (x, y, z) = s
             .split(',')
-            .filter_map(|s| s.parse::<i32>().ok())
-            .collect::<Vec<_>>();
-        let x = *vals
-            .first()
-            .ok_or_else(|| anyhow::anyhow!("Cannot parse x"))?;
-        let y = *vals
-            .get(1)
-            .ok_or_else(|| anyhow::anyhow!("Cannot parse y"))?;
-        let z = *vals
-            .get(2)
-            .ok_or_else(|| anyhow::anyhow!("Cannot parse z"))?;
-        Ok(Cube { x, y, z })
+            .map(|s| s.parse::<i32>())
+            .collect_tuple()
+            .ok_or_else(|| anyhow!("Expected three coordinates"))?;
+        let cube = Cube {
+            x: x.context("Cannot parse x")?,
+            y: y.context("Cannot parse y")?,
+            z: z.context("Cannot parse z")?,
+        };
+        if ![cube.x, cube.y, cube.z].iter().all(|c| LIMITS.contains(c)) {
+            bail!("Cube {s} is out of range");
+        }
+        Ok(cube)
     }
 }
 
@@ -166,17 +172,17 @@ impl Grid {
     }
 }
 
-pub fn part_one(input: &str) -> Option<i32> {
-    let grid = input.parse::<Grid>().ok()?;
-    Some(grid.exposed_area())
+pub fn part_one(input: &str) -> anyhow::Result<i32> {
+    let grid = input.parse::<Grid>()?;
+    Ok(grid.exposed_area())
 }
 
-pub fn part_two(input: &str) -> Option<i32> {
-    let mut grid = input.parse::<Grid>().ok()?;
+pub fn part_two(input: &str) -> anyhow::Result<i32> {
+    let mut grid = input.parse::<Grid>()?;
     let area_before_flood = grid.exposed_area();
     grid.flood_exterior();
     let area_after_flood = grid.exposed_area();
-    Some(area_before_flood - area_after_flood)
+    Ok(area_before_flood - area_after_flood)
 }
 
 fn main() {
@@ -188,16 +194,61 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+
+    fn random_cubes(rng: &mut Rng) -> HashSet<Cube> {
+        (0..rng.range(1..=50))
+            .map(|_| Cube {
+                x: rng.range(0..=20),
+                y: rng.range(0..=20),
+                z: rng.range(0..=20),
+            })
+            .collect()
+    }
+
+    fn format_cubes(cubes: &HashSet<Cube>) -> String {
+        cubes
+            .iter()
+            .map(|c| format!("{},{},{}", c.x, c.y, c.z))
+            .join("\n")
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 18);
-        assert_eq!(part_one(&input), Some(64));
+        assert_eq!(part_one(&input).unwrap(), 64);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 18);
-        assert_eq!(part_two(&input), Some(58));
+        assert_eq!(part_two(&input).unwrap(), 58);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let cubes = random_cubes(&mut rng);
+            let grid = format_cubes(&cubes).parse::<Grid>().unwrap();
+            assert_eq!(grid.cubes, cubes);
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = format_cubes(&random_cubes(&mut rng));
+            let _ = rng.mutate(&input, "-0123456789,\n").parse::<Grid>();
+        }
+    }
+
+    #[test]
+    fn cubes_at_the_edge_of_space_are_rejected() {
+        assert!("2147483647,1,1".parse::<Cube>().is_err());
+        assert!("1,-2147483648,1".parse::<Cube>().is_err());
+        assert!("1,2".parse::<Cube>().is_err());
+        assert!("1,x,2,3".parse::<Cube>().is_err());
     }
 }
@@ -1,3 +1,4 @@
+use anyhow::Context;
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 use std::collections::HashMap;
@@ -193,25 +194,34 @@ impl Blueprint {
     }
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
-    let cumulative_score = input
+fn parse_input(input: &str) -> anyhow::Result<Vec<Blueprint>> {
+    input
         .lines()
-        .filter_map(|l| l.parse::<Blueprint>().ok())
+        .enumerate()
+        .map(|(i, l)| {
+            l.parse::<Blueprint>()
+                .with_context(|| format!("invalid blueprint on line {}", i + 1))
+        })
+        .collect()
+}
+
+pub fn part_one(input: &str) -> anyhow::Result<usize> {
+    let cumulative_score = parse_input(input)?
+        .iter()
         .map(|b| b.max_geodes_in_minutes(24))
         .enumerate()
         .map(|(n, s)| (n + 1) * s)
         .sum();
-    Some(cumulative_score)
+    Ok(cumulative_score)
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
-    let max_product = input
-        .lines()
-        .filter_map(|l| l.parse::<Blueprint>().ok())
+pub fn part_two(input: &str) -> anyhow::Result<usize> {
+    let max_product = parse_input(input)?
+        .iter()
         .take(3)
         .map(|b| b.max_geodes_in_minutes(32))
         .product();
-    Some(max_product)
+    Ok(max_product)
 }
 
 fn main() {
@@ -223,17 +233,83 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+
+    fn random_blueprint(rng: &mut Rng) -> (String, Blueprint) {
+        let mut cost = || rng.range(1..=20);
+        let costs = [
+            (Resource::Ore, vec![(Resource::Ore, cost())]),
+            (Resource::Clay, vec![(Resource::Ore, cost())]),
+            (
+                Resource::Obsidian,
+                vec![(Resource::Ore, cost()), (Resource::Clay, cost())],
+            ),
+            (
+                Resource::Geode,
+                vec![(Resource::Ore, cost()), (Resource::Obsidian, cost())],
+            ),
+        ];
+        let robots = costs
+            .iter()
+            .map(|(robot, costs)| {
+                let costs = costs
+                    .iter()
+                    .map(|(r, n)| format!("{n} {}", format!("{r:?}").to_lowercase()))
+                    .join(" and ");
+                format!(
+                    "Each {} robot costs {costs}.",
+                    format!("{robot:?}").to_lowercase()
+                )
+            })
+            .join(" ");
+        let blueprint = Blueprint {
+            robot_costs: costs
+                .into_iter()
+                .map(|(robot, costs)| (robot, costs.into_iter().collect()))
+                .collect(),
+        };
+        (robots, blueprint)
+    }
+
+    fn random_input(rng: &mut Rng) -> (String, Vec<Blueprint>) {
+        let (lines, blueprints): (Vec<_>, Vec<_>) = (1..=rng.range(1..=5))
+            .map(|id| {
+                let (robots, blueprint) = random_blueprint(rng);
+                (format!("Blueprint {id}: {robots}"), blueprint)
+            })
+            .unzip();
+        (lines.join("\n"), blueprints)
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 19);
-        assert_eq!(part_one(&input), Some(33));
+        assert_eq!(part_one(&input).unwrap(), 33);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 19);
-        assert_eq!(part_two(&input), Some(3472));
+        assert_eq!(part_two(&input).unwrap(), 3472);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let (input, blueprints) = random_input(&mut rng);
+            assert_eq!(parse_input(&input).unwrap(), blueprints);
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let (input, _) = random_input(&mut rng);
+            let _ = parse_input(&rng.mutate(&input, "0123456789 .:orecaybsidng\n"));
+        }
     }
 
     #[test]
@@ -1,27 +1,34 @@
+use advent_of_code::helpers::{ArithmeticError, CheckedNum};
+use anyhow::Context;
 use std::collections::VecDeque;
 use std::str::FromStr;
 
+/// Encrypted file whose numbers are held as `T`, so large keys can be
+/// applied with a wider or arbitrary-precision type instead of `i64`.
 #[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
-pub struct EncryptedFile {
-    codes: VecDeque<(usize, i64)>,
+pub struct EncryptedFile<T = i64> {
+    codes: VecDeque<(usize, T)>,
 }
 
-impl EncryptedFile {
-    pub fn new() -> EncryptedFile {
+impl<T: CheckedNum> EncryptedFile<T> {
+    pub fn new() -> EncryptedFile<T> {
         EncryptedFile {
             codes: VecDeque::new(),
         }
     }
 
-    pub fn apply_key(&mut self, key: i64) {
-        self.codes.iter_mut().for_each(|v| v.1 *= key);
+    pub fn apply_key(&mut self, key: &T) -> Result<(), ArithmeticError> {
+        for v in self.codes.iter_mut() {
+            v.1 = v.1.try_mul(key)?;
+        }
+        Ok(())
     }
 
-    pub fn mix(&mut self, iterations: usize) {
+    pub fn mix(&mut self, iterations: usize) -> Result<(), ArithmeticError> {
         // For every iteration
-        (0..iterations).for_each(|_| {
+        for _ in 0..iterations {
             // Iterate through all message ids
-            (0..self.codes.len()).for_each(|id| {
+            for id in 0..self.codes.len() {
                 // Find the message id O(N), extract current position in the deque
                 let pos = self
                     .codes
@@ -37,60 +44,71 @@ impl EncryptedFile {
                 let message = self.codes.pop_front().expect("Collection cannot be empty");
 
                 // Compute new position accounting for circular buffer
-                let rotation = message.1.rem_euclid(self.codes.len() as i64) as usize;
+                if !self.codes.is_empty() {
+                    let len = T::try_from_usize(self.codes.len())?;
+                    let rotation = message.1.try_mod_floor(&len)?.try_to_usize()?;
 
-                // Rotate to new position
-                self.codes.rotate_left(rotation);
+                    // Rotate to new position
+                    self.codes.rotate_left(rotation);
+                }
 
                 // Re-insert the message
                 self.codes.push_front(message);
-            });
-        });
+            }
+        }
+        Ok(())
     }
 
-    pub fn coordinate(&self) -> Option<i64> {
+    pub fn coordinate(&self) -> anyhow::Result<T> {
         // Find the zero
         let zero_position = self
             .codes
             .iter()
             .enumerate()
-            .find_map(|(pos, (_, n))| (*n == 0).then_some(pos))?;
+            .find_map(|(pos, (_, n))| n.is_zero().then_some(pos))
+            .context("no 0 in encrypted file")?;
 
         // Sum the "interesting" positions in relations to the zero accounting for the circular buffer
-        Some(
-            [1000, 2000, 3000]
-                .iter()
-                .map(|th| self.codes[(th + zero_position) % self.codes.len()].1)
-                .sum(),
-        )
+        Ok([1000, 2000, 3000]
+            .iter()
+            .map(|th| &self.codes[(th + zero_position) % self.codes.len()].1)
+            .try_fold(T::zero(), |acc, n| acc.try_add(n))?)
     }
 }
 
-impl FromStr for EncryptedFile {
+impl<T> FromStr for EncryptedFile<T>
+where
+    T: CheckedNum + FromStr,
+    <T as FromStr>::Err: std::error::Error + Send + Sync + 'static,
+{
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let codes = s
             .lines()
-            .filter_map(|l| l.parse::<i64>().ok())
             .enumerate()
-            .collect::<VecDeque<_>>();
+            .map(|(i, l)| {
+                l.parse::<T>()
+                    .with_context(|| format!("invalid number on line {}", i + 1))
+                    .map(|code| (i, code))
+            })
+            .collect::<anyhow::Result<VecDeque<_>>>()?;
         Ok(EncryptedFile { codes })
     }
 }
 
-pub fn part_one(input: &str) -> Option<i64> {
-    let mut file = input.parse::<EncryptedFile>().ok()?;
-    file.mix(1);
+pub fn part_one(input: &str) -> anyhow::Result<i64> {
+    let mut file = input.parse::<EncryptedFile>()?;
+    file.mix(1)?;
     file.coordinate()
 }
 
-pub fn part_two(input: &str) -> Option<i64> {
+pub fn part_two(input: &str) -> anyhow::Result<i64> {
     const DECRYPTION_KEY: i64 = 811_589_153;
     const ITERATIONS: usize = 10;
-    let mut file = input.parse::<EncryptedFile>().ok()?;
-    file.apply_key(DECRYPTION_KEY);
-    file.mix(ITERATIONS);
+    let mut file = input.parse::<EncryptedFile>()?;
+    file.apply_key(&DECRYPTION_KEY)?;
+    file.mix(ITERATIONS)?;
     file.coordinate()
 }
 
@@ -103,16 +121,70 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+
+    fn random_numbers(rng: &mut Rng) -> Vec<i64> {
+        let mut numbers = (0..rng.range(0..=50))
+            .map(|_| rng.range(-10_000..=10_000))
+            .chain([0])
+            .collect::<Vec<_>>();
+        rng.shuffle(&mut numbers);
+        numbers
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 20);
-        assert_eq!(part_one(&input), Some(3));
+        assert_eq!(part_one(&input).unwrap(), 3);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 20);
-        assert_eq!(part_two(&input), Some(1623178306));
+        assert_eq!(part_two(&input).unwrap(), 1623178306);
+    }
+
+    #[test]
+    fn oversized_key_overflows_i64() {
+        let input = advent_of_code::read_file("examples", 20);
+        let mut file = input.parse::<EncryptedFile>().unwrap();
+        assert!(matches!(
+            file.apply_key(&i64::MAX),
+            Err(ArithmeticError::Overflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_part_two_with_big_integers() {
+        let input = advent_of_code::read_file("examples", 20);
+        let mut file = input.parse::<EncryptedFile<num::BigInt>>().unwrap();
+        file.apply_key(&num::BigInt::from(811_589_153)).unwrap();
+        file.mix(10).unwrap();
+        assert_eq!(file.coordinate().unwrap(), num::BigInt::from(1623178306));
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let numbers = random_numbers(&mut rng);
+            let file = numbers.iter().join("\n").parse::<EncryptedFile>().unwrap();
+            assert_eq!(
+                file.codes,
+                numbers.into_iter().enumerate().collect::<VecDeque<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = random_numbers(&mut rng).iter().join("\n");
+            let input = rng.mutate(&input, "-0123456789\n");
+            let _ = part_one(&input);
+            let _ = part_two(&input);
+        }
     }
 }
@@ -1,3 +1,5 @@
+use advent_of_code::helpers::CheckedNum;
+use anyhow::Context;
 use std::collections::HashMap;
 use z3::ast::Ast;
 
@@ -26,16 +28,21 @@ impl MonkeyMath {
         MonkeyMath { expressions }
     }
 
-    pub fn eval(&self, monkey: &String) -> Option<u64> {
-        let expr = self.expressions.get(monkey)?;
+    /// Evaluates `monkey` with every intermediate result held as `T`, reporting
+    /// overflow or division by zero instead of wrapping or panicking.
+    pub fn eval<T: CheckedNum>(&self, monkey: &String) -> anyhow::Result<T> {
+        let expr = self
+            .expressions
+            .get(monkey)
+            .with_context(|| format!("unknown monkey \"{monkey}\""))?;
         let result = match expr {
-            Expression::Num(n) => *n,
-            Expression::Sum(a, b) => self.eval(a)? + self.eval(b)?,
-            Expression::Sub(a, b) => self.eval(a)? - self.eval(b)?,
-            Expression::Mul(a, b) => self.eval(a)? * self.eval(b)?,
-            Expression::Div(a, b) => self.eval(a)? / self.eval(b)?,
+            Expression::Num(n) => T::try_from_u64(*n)?,
+            Expression::Sum(a, b) => self.eval::<T>(a)?.try_add(&self.eval(b)?)?,
+            Expression::Sub(a, b) => self.eval::<T>(a)?.try_sub(&self.eval(b)?)?,
+            Expression::Mul(a, b) => self.eval::<T>(a)?.try_mul(&self.eval(b)?)?,
+            Expression::Div(a, b) => self.eval::<T>(a)?.try_div(&self.eval(b)?)?,
         };
-        Some(result)
+        Ok(result)
     }
 
     fn eval_z3<'a>(&self, monkey: &String, ctx: &'a z3::Context, humn: &'a z3::ast::Int) -> Option<z3::ast::Int<'a>> {
@@ -85,14 +92,16 @@ impl MonkeyMath {
     }
 }
 
-pub fn part_one(input: &str) -> Option<u64> {
-    let monkey_math = input.parse::<MonkeyMath>().ok()?;
+pub fn part_one(input: &str) -> anyhow::Result<u64> {
+    let monkey_math = input.parse::<MonkeyMath>()?;
     monkey_math.eval(&"root".to_string())
 }
 
-pub fn part_two(input: &str) -> Option<u64> {
-    let monkey_math = input.parse::<MonkeyMath>().ok()?;
-    monkey_math.find_human_value()
+pub fn part_two(input: &str) -> anyhow::Result<u64> {
+    let monkey_math = input.parse::<MonkeyMath>()?;
+    monkey_math
+        .find_human_value()
+        .context("no value for humn satisfies root")
 }
 
 fn main() {
@@ -104,17 +113,94 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+
+    /// Adds the monkey `name` and everything it depends on.
+    fn random_monkey(rng: &mut Rng, depth: usize, name: String, lines: &mut Vec<String>) {
+        if depth == 0 || rng.chance(1, 3) {
+            lines.push(format!("{name}: {}", rng.range(1..=20)));
+            return;
+        }
+        let operand = |side: u8| format!("{name}{}", side as char);
+        let (a, b) = (operand(b'a'), operand(b'b'));
+        lines.push(format!(
+            "{name}: {a} {} {b}",
+            rng.choose(&['+', '-', '*', '/'])
+        ));
+        random_monkey(rng, depth - 1, a, lines);
+        random_monkey(rng, depth - 1, b, lines);
+    }
+
+    fn random_input(rng: &mut Rng) -> String {
+        let mut lines = Vec::new();
+        random_monkey(rng, 5, "root".to_string(), &mut lines);
+        rng.shuffle(&mut lines);
+        lines.join("\n")
+    }
+
+    fn format_expression(expression: &Expression) -> String {
+        match expression {
+            Expression::Num(n) => n.to_string(),
+            Expression::Sum(a, b) => format!("{a} + {b}"),
+            Expression::Sub(a, b) => format!("{a} - {b}"),
+            Expression::Mul(a, b) => format!("{a} * {b}"),
+            Expression::Div(a, b) => format!("{a} / {b}"),
+        }
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 21);
-        assert_eq!(part_one(&input), Some(152));
+        assert_eq!(part_one(&input).unwrap(), 152);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 21);
-        assert_eq!(part_two(&input), Some(301));
+        assert_eq!(part_two(&input).unwrap(), 301);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let input = random_input(&mut rng);
+            let monkey_math = input.parse::<MonkeyMath>().unwrap();
+            let printed = monkey_math
+                .expressions
+                .iter()
+                .map(|(monkey, expression)| format!("{monkey}: {}", format_expression(expression)))
+                .sorted()
+                .collect::<Vec<_>>();
+            assert_eq!(printed, input.lines().sorted().collect::<Vec<_>>());
+            let _ = monkey_math.eval::<i64>(&"root".to_string());
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = random_input(&mut rng);
+            let _ = rng
+                .mutate(&input, "abot0123456789+-*/: \n")
+                .parse::<MonkeyMath>();
+        }
+    }
+
+    #[test]
+    fn eval_reports_overflow() {
+        let monkey_math = "root: aaaa - bbbb\naaaa: 2\nbbbb: 3"
+            .parse::<MonkeyMath>()
+            .unwrap();
+        let root = "root".to_string();
+        assert!(monkey_math.eval::<u64>(&root).is_err());
+        assert_eq!(monkey_math.eval::<i64>(&root).unwrap(), -1);
+        assert_eq!(
+            monkey_math.eval::<num::BigInt>(&root).unwrap(),
+            num::BigInt::from(-1)
+        );
     }
 }
 
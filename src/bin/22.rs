@@ -33,7 +33,7 @@ impl TryFrom<char> for Tile {
             ' ' => Ok(Self::Void),
             '.' => Ok(Self::Open),
             '#' => Ok(Self::Wall),
-            _ => panic!("{value} is not a valid tile"),
+            _ => Err(anyhow::anyhow!("{value} is not a valid tile")),
         }
     }
 }
@@ -543,14 +543,14 @@ impl FromStr for Board {
             .lines()
             .enumerate()
             .flat_map(|(y, line)| {
-                line.chars().enumerate().filter_map(move |(x, c)| {
+                line.chars().enumerate().map(move |(x, c)| {
                     let x = x as u32;
                     let y = y as u32;
-                    let tile = Tile::try_from(c).ok()?;
-                    Some((Position { x, y }, tile))
+                    let tile = Tile::try_from(c)?;
+                    Ok((Position { x, y }, tile))
                 })
             })
-            .collect::<BTreeMap<_, _>>();
+            .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
         let position = grid
             .iter()
             .find(|(_, v)| **v == Tile::Open)
@@ -589,7 +589,7 @@ impl Display for Board {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Input {
+pub struct Input {
     board: Board,
     commands: Vec<Command>,
 }
@@ -606,26 +606,26 @@ impl FromStr for Input {
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let mut input = input.parse::<Input>().ok()?;
+pub fn part_one(input: &str) -> anyhow::Result<u32> {
+    let mut input = input.parse::<Input>()?;
     //println!("{}", input.board);
     for command in input.commands {
         //println!("Applying {:?}:", command);
         input.board.apply(&WrapStyle::Flat, &command);
         //println!("{}", input.board);
     }
-    Some(input.board.score())
+    Ok(input.board.score())
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let mut input = input.parse::<Input>().ok()?;
+pub fn part_two(input: &str) -> anyhow::Result<u32> {
+    let mut input = input.parse::<Input>()?;
     //println!("{}", input.board);
     for command in input.commands {
         //println!("Applying {:?}:", command);
         input.board.apply(&WrapStyle::Cube, &command);
         //println!("{}", input.board);
     }
-    Some(input.board.score())
+    Ok(input.board.score())
 }
 
 fn main() {
@@ -637,17 +637,68 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+
+    fn random_commands(rng: &mut Rng) -> Vec<Command> {
+        (0..rng.range(1..=20))
+            .map(|i| match i % 2 {
+                0 => Command::GoForward(rng.range(0..=50)),
+                _ => rng.choose(&[Command::TurnLeft, Command::TurnRight]).clone(),
+            })
+            .collect()
+    }
+
+    fn random_input(rng: &mut Rng) -> (String, Vec<Command>) {
+        let width = rng.range(1..=16);
+        let board = (0..rng.range(1..=16))
+            .map(|_| {
+                (0..width)
+                    .map(|_| *rng.choose(&[' ', '.', '.', '#']))
+                    .collect::<String>()
+            })
+            .chain(["..".to_string()])
+            .join("\n");
+        let commands = random_commands(rng);
+        let commands_str = commands
+            .iter()
+            .map(|c| match c {
+                Command::TurnLeft => "L".to_string(),
+                Command::TurnRight => "R".to_string(),
+                Command::GoForward(n) => n.to_string(),
+            })
+            .join("");
+        (format!("{board}\n\n{commands_str}"), commands)
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 22);
-        assert_eq!(part_one(&input), Some(6032));
+        assert_eq!(part_one(&input).unwrap(), 6032);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 22);
-        assert_eq!(part_two(&input), Some(5031));
+        assert_eq!(part_two(&input).unwrap(), 5031);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let (input, commands) = random_input(&mut rng);
+            assert_eq!(input.parse::<Input>().unwrap().commands, commands);
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let (input, _) = random_input(&mut rng);
+            let _ = rng.mutate(&input, " .#LR0123456789\n").parse::<Input>();
+        }
     }
 }
 
@@ -1,9 +1,15 @@
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 use std::fmt::Display;
+use std::io::Write;
 use std::iter;
 use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::process;
 use std::str::FromStr;
+use std::time::Duration;
+
+use advent_of_code::animation::Recorder;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Direction {
@@ -182,6 +188,22 @@ impl Grid {
         }
     }
 
+    /// The elves within `range`, which may reach beyond the smallest
+    /// rectangle containing them.
+    pub fn render(&self, range: &RangeInclusive<Position>) -> String {
+        let mut rendered = String::new();
+        for y in range.start().y..=range.end().y {
+            for x in range.start().x..=range.end().x {
+                match self.elves.contains(&Position { y, x }) {
+                    true => rendered.push('#'),
+                    false => rendered.push('.'),
+                }
+            }
+            rendered.push('\n');
+        }
+        rendered
+    }
+
     pub fn empty_tiles_in_rectangle(&self) -> u32 {
         let range = self.range();
         let area = (1 + range.end().y - range.start().y) * (1 + range.end().x - range.start().x);
@@ -256,67 +278,168 @@ impl FromStr for Grid {
             .flat_map(|(y, line)| {
                 line.chars()
                     .enumerate()
-                    .filter(|(_, c)| *c == '#')
-                    .map(move |(x, _)| {
+                    .filter(|(_, c)| *c != '.')
+                    .map(move |(x, c)| {
+                        if c != '#' {
+                            anyhow::bail!("'{c}' is neither an elf nor ground");
+                        }
                         let x = x as i32;
                         let y = y as i32;
-                        Position { x, y }
+                        Ok(Position { x, y })
                     })
             })
-            .collect::<BTreeSet<_>>();
+            .collect::<anyhow::Result<BTreeSet<_>>>()?;
+        if elves.is_empty() {
+            anyhow::bail!("No elves in grid");
+        }
         Ok(Grid { elves, round: 0 })
     }
 }
 
 impl Display for Grid {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let range = self.range();
-        for y in range.start().y..=range.end().y {
-            for x in range.start().x..=range.end().x {
-                if self.elves.contains(&Position { y, x }) {
-                    write!(f, "#")
-                } else {
-                    write!(f, ".")
-                }?;
-            }
-            writeln!(f)?;
-        }
-        Ok(())
+        write!(f, "{}", self.render(&self.range()))
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let mut grid = input.parse::<Grid>().ok()?;
+pub fn part_one(input: &str) -> anyhow::Result<u32> {
+    let mut grid = input.parse::<Grid>()?;
     for _ in 0..10 {
         grid.run_round();
     }
-    Some(grid.empty_tiles_in_rectangle())
+    Ok(grid.empty_tiles_in_rectangle())
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
-    let mut grid = input.parse::<Grid>().ok()?;
-    Some(iter::from_fn(|| grid.run_round()).count() + 1)
+pub fn part_two(input: &str) -> anyhow::Result<usize> {
+    let mut grid = input.parse::<Grid>()?;
+    Ok(iter::from_fn(|| grid.run_round()).count() + 1)
+}
+
+const FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// The elves after every round until none of them moves, all drawn over the
+/// area they spread across.
+fn animate<W: Write>(input: &str, mut recorder: Recorder<W>) -> anyhow::Result<W> {
+    // Run it once to know the area every frame has to cover
+    let mut grid = input.parse::<Grid>()?;
+    let mut footprint = Grid {
+        elves: grid.elves.clone(),
+        round: 0,
+    };
+    while grid.run_round().is_some() {
+        footprint.elves.extend(grid.elves.iter().cloned());
+    }
+    let area = footprint.range();
+    let mut grid = input.parse::<Grid>()?;
+    recorder.record(|| grid.render(&area))?;
+    while grid.run_round().is_some() {
+        recorder.record(|| grid.render(&area))?;
+    }
+    recorder.record_last(|| grid.render(&area))?;
+    recorder.finish()
+}
+
+struct Args {
+    animate: Option<PathBuf>,
+    every: usize,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        animate: args.opt_value_from_str("--animate")?,
+        every: args.opt_value_from_str("--every")?.unwrap_or(1),
+    })
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 23);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    match parse_args() {
+        Ok(Args { animate: None, .. }) => {
+            advent_of_code::solve!(1, part_one, input);
+            advent_of_code::solve!(2, part_two, input);
+        }
+        Ok(Args {
+            animate: Some(path),
+            every,
+        }) => {
+            let recorder = Recorder::create(&path, FRAME_DELAY);
+            if let Err(e) = recorder.and_then(|r| animate(input, r.with_sampling(every))) {
+                eprintln!("failed: {:#}", e);
+                process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            process::exit(1);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::animation::{asciicast_frames, AnimationFormat};
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+
+    fn random_input(rng: &mut Rng) -> String {
+        let width = rng.range(1..=12);
+        (0..rng.range(1..=12))
+            .map(|_| {
+                (0..width)
+                    .map(|_| *rng.choose(&['.', '#']))
+                    .collect::<String>()
+            })
+            .chain(["#".to_string()])
+            .join("\n")
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 23);
-        assert_eq!(part_one(&input), Some(110));
+        assert_eq!(part_one(&input).unwrap(), 110);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 23);
-        assert_eq!(part_two(&input), Some(20));
+        assert_eq!(part_two(&input).unwrap(), 20);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let input = random_input(&mut rng);
+            let grid = input.parse::<Grid>().unwrap();
+            let printed = grid.to_string();
+            let reparsed = printed.parse::<Grid>().unwrap();
+            assert_eq!(reparsed.elves.len(), input.matches('#').count());
+            assert_eq!(reparsed.to_string(), printed);
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = random_input(&mut rng);
+            let input = rng.mutate(&input, ".#\n");
+            let _ = part_one(&input);
+        }
+    }
+
+    #[test]
+    fn animate_example() {
+        let input = advent_of_code::read_file("examples", 23);
+        let recorder = Recorder::new(Vec::new(), AnimationFormat::Asciicast, FRAME_DELAY);
+        let cast = animate(&input, recorder).unwrap();
+        let frames = asciicast_frames(&String::from_utf8(cast).unwrap()).unwrap();
+        // The initial state and one frame per round that moved an elf
+        assert_eq!(frames.len(), 20);
+        assert!(frames.iter().map(|f| f.len()).all_equal());
+        assert_eq!(frames[0].matches('#').count(), 22);
+        assert_eq!(frames[19].matches('#').count(), 22);
     }
 }
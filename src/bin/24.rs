@@ -1,3 +1,4 @@
+use anyhow::Context;
 use num::integer::lcm;
 use petgraph::algo::astar;
 use petgraph::prelude::*;
@@ -22,7 +23,7 @@ impl TryFrom<char> for Direction {
             '<' => Ok(Self::West),
             'v' => Ok(Self::South),
             '>' => Ok(Self::East),
-            _ => panic!("{value} is not a valid direction"),
+            _ => Err(anyhow::anyhow!("{value} is not a valid direction")),
         }
     }
 }
@@ -251,8 +252,18 @@ impl FromStr for Valley {
             .lines()
             .last()
             .ok_or_else(|| anyhow::anyhow!("Empty input"))?;
-        let width = first_line.len() - 2;
-        let height = s.lines().count() - 2;
+        let width = first_line
+            .chars()
+            .count()
+            .checked_sub(2)
+            .filter(|w| *w > 0)
+            .ok_or_else(|| anyhow::anyhow!("Valley is too narrow"))?;
+        let height = s
+            .lines()
+            .count()
+            .checked_sub(2)
+            .filter(|h| *h > 0)
+            .ok_or_else(|| anyhow::anyhow!("Valley is too short"))?;
         let blizzards = s
             .lines()
             .skip(1)
@@ -264,19 +275,19 @@ impl FromStr for Valley {
                     .take(width)
                     .enumerate()
                     .filter(|(_, c)| *c != '.')
-                    .filter_map(move |(x_u, c)| {
+                    .map(move |(x_u, c)| {
                         let initial_position = Position {
                             x: x_u as i32,
                             y: y_u as i32,
                         };
-                        let direction = Direction::try_from(c).ok()?;
-                        Some(Blizzard {
+                        let direction = Direction::try_from(c)?;
+                        Ok(Blizzard {
                             initial_position,
                             direction,
                         })
                     })
             })
-            .collect::<HashSet<_>>();
+            .collect::<anyhow::Result<HashSet<_>>>()?;
         let start = first_line
             .chars()
             .skip(1)
@@ -308,7 +319,7 @@ impl FromStr for Valley {
                     None
                 }
             })
-            .ok_or_else(|| anyhow::anyhow!("No start position"))?;
+            .ok_or_else(|| anyhow::anyhow!("No end position"))?;
         Ok(Valley {
             width,
             height,
@@ -319,18 +330,22 @@ impl FromStr for Valley {
     }
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
-    let valley = input.parse::<Valley>().ok()?;
+pub fn part_one(input: &str) -> anyhow::Result<usize> {
+    let valley = input.parse::<Valley>()?;
     let valley_graph = valley.graph();
-    let path = valley_graph.single_leg_path()?;
-    Some(path.len() - 1)
+    let path = valley_graph
+        .single_leg_path()
+        .context("no path through the valley")?;
+    Ok(path.len() - 1)
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
-    let valley = input.parse::<Valley>().ok()?;
+pub fn part_two(input: &str) -> anyhow::Result<usize> {
+    let valley = input.parse::<Valley>()?;
     let valley_graph = valley.graph();
-    let path = valley_graph.three_leg_path()?;
-    Some(path.len() - 1)
+    let path = valley_graph
+        .three_leg_path()
+        .context("no path through the valley and back")?;
+    Ok(path.len() - 1)
 }
 
 fn main() {
@@ -342,16 +357,100 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+    use itertools::Itertools;
+
+    fn random_valley(rng: &mut Rng) -> Valley {
+        let (width, height) = (rng.range(1..=12_usize), rng.range(1..=8_usize));
+        let directions = [
+            Direction::North,
+            Direction::West,
+            Direction::South,
+            Direction::East,
+        ];
+        let mut blizzards = HashSet::new();
+        for (x, y) in (0..width).cartesian_product(0..height) {
+            if rng.chance(1, 3) {
+                blizzards.insert(Blizzard {
+                    initial_position: Position {
+                        x: x as i32,
+                        y: y as i32,
+                    },
+                    direction: rng.choose(&directions).clone(),
+                });
+            }
+        }
+        Valley {
+            width,
+            height,
+            start: Position { x: 0, y: -1 },
+            end: Position {
+                x: width as i32 - 1,
+                y: height as i32,
+            },
+            blizzards,
+        }
+    }
+
+    fn format_valley(valley: &Valley) -> String {
+        let wall = |opening: &Position| {
+            (-1..=valley.width as i32)
+                .map(|x| if x == opening.x { '.' } else { '#' })
+                .collect::<String>()
+        };
+        let rows = (0..valley.height as i32).map(|y| {
+            let tiles = (0..valley.width as i32)
+                .map(|x| {
+                    let blizzard = valley
+                        .blizzards
+                        .iter()
+                        .find(|b| b.initial_position == Position { x, y });
+                    match blizzard.map(|b| &b.direction) {
+                        Some(Direction::North) => '^',
+                        Some(Direction::West) => '<',
+                        Some(Direction::South) => 'v',
+                        Some(Direction::East) => '>',
+                        None => '.',
+                    }
+                })
+                .collect::<String>();
+            format!("#{tiles}#")
+        });
+        [wall(&valley.start)]
+            .into_iter()
+            .chain(rows)
+            .chain([wall(&valley.end)])
+            .join("\n")
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 24);
-        assert_eq!(part_one(&input), Some(18));
+        assert_eq!(part_one(&input).unwrap(), 18);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 24);
-        assert_eq!(part_two(&input), Some(54));
+        assert_eq!(part_two(&input).unwrap(), 54);
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let valley = random_valley(&mut rng);
+            let input = format_valley(&valley);
+            assert_eq!(input.parse::<Valley>().unwrap(), valley, "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = format_valley(&random_valley(&mut rng));
+            let _ = rng.mutate(&input, "#.^<v>\n").parse::<Valley>();
+        }
     }
 }
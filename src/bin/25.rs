@@ -1,3 +1,4 @@
+use anyhow::Context;
 use std::collections::VecDeque;
 use std::fmt;
 use std::fmt::Display;
@@ -27,7 +28,7 @@ impl TryFrom<char> for SnafuDigit {
             '0' => Ok(Self::Zero),
             '1' => Ok(Self::One),
             '2' => Ok(Self::Two),
-            _ => panic!("{value} is not a valid digit"),
+            _ => anyhow::bail!("{value} is not a valid digit"),
         }
     }
 }
@@ -133,8 +134,11 @@ impl FromStr for Snafu {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let digits = s
             .chars()
-            .flat_map(|c| SnafuDigit::try_from(c).ok())
-            .collect::<VecDeque<_>>();
+            .map(SnafuDigit::try_from)
+            .collect::<anyhow::Result<VecDeque<_>>>()?;
+        if digits.is_empty() {
+            anyhow::bail!("SNAFU number has no digits");
+        }
         Ok(Snafu { digits })
     }
 }
@@ -187,8 +191,15 @@ impl Sum for Snafu {
     }
 }
 
-pub fn part_one(input: &str) -> Option<Snafu> {
-    Some(input.lines().filter_map(|l| l.parse::<Snafu>().ok()).sum())
+pub fn part_one(input: &str) -> anyhow::Result<Snafu> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, l)| {
+            l.parse::<Snafu>()
+                .with_context(|| format!("invalid SNAFU number on line {}", i + 1))
+        })
+        .sum()
 }
 
 fn main() {
@@ -199,10 +210,37 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::Rng;
+
+    fn random_snafu(rng: &mut Rng) -> String {
+        let mut snafu = rng.choose(&['1', '2']).to_string();
+        for _ in 0..rng.range(0..=20) {
+            snafu.push(*rng.choose(&['=', '-', '0', '1', '2']));
+        }
+        snafu
+    }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 25);
-        assert_eq!(part_one(&input), Some("2=-1=0".parse().unwrap()));
+        assert_eq!(part_one(&input).unwrap(), "2=-1=0".parse().unwrap());
+    }
+
+    #[test]
+    fn parse_random_inputs() {
+        let mut rng = Rng::new(1);
+        for _ in 0..100 {
+            let input = random_snafu(&mut rng);
+            assert_eq!(input.parse::<Snafu>().unwrap().to_string(), input);
+        }
+    }
+
+    #[test]
+    fn parse_mutated_inputs() {
+        let mut rng = Rng::new(2);
+        for _ in 0..1000 {
+            let input = (0..5).map(|_| random_snafu(&mut rng)).collect::<Vec<_>>();
+            let _ = part_one(&rng.mutate(&input.join("\n"), "=-012\n"));
+        }
     }
 }
@@ -0,0 +1,47 @@
+/*
+ * Prints a random puzzle input for a day, for benchmarks and stress tests.
+ * The same seed always gives the same input, and `--size` scales it in the
+ * day's own unit: elves, moves, rows and so on.
+ * Example: `cargo run --bin gen -- 14 --seed 3 --size 150 > src/inputs/14.txt`.
+ */
+
+use advent_of_code::generate;
+use std::process;
+
+struct Args {
+    day: u8,
+    size: Option<usize>,
+    seed: u64,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        day: args.free_from_str()?,
+        size: args.opt_value_from_str(["-n", "--size"])?,
+        seed: args.opt_value_from_str(["-s", "--seed"])?.unwrap_or(0),
+    })
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let size = args
+        .size
+        .or_else(|| generate::default_size(args.day))
+        .unwrap_or(0);
+
+    match generate::generate(args.day, size, args.seed) {
+        Ok(input) => println!("{}", input),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,447 @@
+/*
+ * A cycle-accurate model of the small "elf CPUs" some puzzles define.
+ * A `Decoder` knows the opcodes, their operands and how many cycles they take,
+ * and a `Cpu` runs the decoded program one cycle at a time.
+ * Example: `Cpu::new(decoder.decode_program(input)?).with_register('x', 1)`.
+ */
+
+use anyhow::{anyhow, bail, Context};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Register values by name. Registers that were never written read as 0.
+pub type Registers = BTreeMap<char, i64>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operand {
+    Register(char),
+    Value(i64),
+}
+
+impl Operand {
+    pub fn value(&self, registers: &Registers) -> i64 {
+        match self {
+            Operand::Register(name) => registers.get(name).copied().unwrap_or(0),
+            Operand::Value(value) => *value,
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Register(name) => write!(f, "{name}"),
+            Operand::Value(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// Where execution continues after an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Flow {
+    Next,
+    /// Moves the program counter by this many instructions.
+    Jump(i64),
+}
+
+/// The effect of an instruction, applied at the end of its last cycle. An
+/// error, such as an overflowing register, stops the CPU.
+pub type Execute = fn(&mut Registers, &[Operand]) -> anyhow::Result<Flow>;
+
+#[derive(Debug, Clone)]
+pub struct Opcode {
+    pub mnemonic: String,
+    pub operands: usize,
+    pub cycles: usize,
+    pub execute: Execute,
+}
+
+/// Opcodes are equal when they share mnemonic, operand count and cycle
+/// cost, as function pointers can't be compared reliably.
+impl PartialEq for Opcode {
+    fn eq(&self, other: &Self) -> bool {
+        (&self.mnemonic, self.operands, self.cycles)
+            == (&other.mnemonic, other.operands, other.cycles)
+    }
+}
+
+impl Eq for Opcode {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub operands: Vec<Operand>,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.opcode.mnemonic)?;
+        for operand in &self.operands {
+            write!(f, " {operand}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The instruction set of a CPU, extended one opcode at a time.
+#[derive(Debug, Clone, Default)]
+pub struct Decoder {
+    opcodes: HashMap<String, Opcode>,
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder::default()
+    }
+
+    /// Adds or replaces the opcode `mnemonic`, taking `operands` operands and
+    /// `cycles` cycles.
+    pub fn with_opcode(
+        mut self,
+        mnemonic: &str,
+        operands: usize,
+        cycles: usize,
+        execute: Execute,
+    ) -> Decoder {
+        assert!(cycles > 0, "opcode {mnemonic} must take at least one cycle");
+        let opcode = Opcode {
+            mnemonic: mnemonic.to_string(),
+            operands,
+            cycles,
+            execute,
+        };
+        self.opcodes.insert(mnemonic.to_string(), opcode);
+        self
+    }
+
+    pub fn opcode(&self, mnemonic: &str) -> Option<&Opcode> {
+        self.opcodes.get(mnemonic)
+    }
+
+    /// Decodes a line like `addx -3` or `cpy a b`, where operands are
+    /// integers or single lowercase register names.
+    pub fn decode(&self, line: &str) -> anyhow::Result<Instruction> {
+        use nom::{
+            branch::alt,
+            character::complete::{alpha1, i64, satisfy, space1},
+            combinator::{all_consuming, map},
+            multi::many0,
+            sequence::{pair, preceded},
+            Finish,
+        };
+        let operand = alt((
+            map(i64, Operand::Value),
+            map(satisfy(|c| c.is_ascii_lowercase()), Operand::Register),
+        ));
+        let mut parser = all_consuming(pair(alpha1, many0(preceded(space1, operand))));
+        let (_, (mnemonic, operands)) = parser(line)
+            .finish()
+            .map_err(|e: nom::error::Error<&str>| anyhow!("unexpected {:?}", e.input))?;
+        let opcode = self
+            .opcode(mnemonic)
+            .with_context(|| format!("unknown opcode {mnemonic:?}"))?;
+        if operands.len() != opcode.operands {
+            bail!(
+                "{mnemonic} takes {} operands, got {}",
+                opcode.operands,
+                operands.len()
+            );
+        }
+        Ok(Instruction {
+            opcode: opcode.clone(),
+            operands,
+        })
+    }
+
+    /// Decodes one instruction per line.
+    pub fn decode_program(&self, source: &str) -> anyhow::Result<Vec<Instruction>> {
+        source
+            .lines()
+            .enumerate()
+            .map(|(i, l)| {
+                self.decode(l)
+                    .with_context(|| format!("invalid instruction on line {}", i + 1))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Breakpoint {
+    /// Pauses before the given cycle, so the registers hold their values
+    /// during it. As breakpoints are checked after every cycle, cycle 1 is
+    /// never reached this way.
+    Cycle(usize),
+    /// Pauses after a cycle that sets the register to the value.
+    Register(char, i64),
+}
+
+/// Why `Cpu::run` returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stop {
+    Halted,
+    Breakpoint(Breakpoint),
+}
+
+/// A cycle that has run, with the registers as they were during it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tick {
+    pub cycle: usize,
+    pub registers: Registers,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cpu {
+    program: Vec<Instruction>,
+    registers: Registers,
+    program_counter: i64,
+    cycles_run: usize,
+    /// Cycles already spent on the current instruction
+    progress: usize,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl Cpu {
+    pub fn new(program: Vec<Instruction>) -> Cpu {
+        Cpu {
+            program,
+            registers: Registers::new(),
+            program_counter: 0,
+            cycles_run: 0,
+            progress: 0,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    pub fn with_register(mut self, name: char, value: i64) -> Cpu {
+        self.registers.insert(name, value);
+        self
+    }
+
+    pub fn register(&self, name: char) -> i64 {
+        Operand::Register(name).value(&self.registers)
+    }
+
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    /// The number of the next cycle to run, starting at 1.
+    pub fn cycle(&self) -> usize {
+        self.cycles_run + 1
+    }
+
+    /// The instruction being executed, or `None` once the program counter
+    /// has left the program.
+    pub fn instruction(&self) -> Option<&Instruction> {
+        let index = usize::try_from(self.program_counter).ok()?;
+        self.program.get(index)
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.instruction().is_none()
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) {
+        self.breakpoints.retain(|b| b != breakpoint);
+    }
+
+    /// Runs a single cycle, completing the current instruction if this is
+    /// its last one. If the instruction fails, the CPU is left as it was
+    /// before the cycle.
+    pub fn tick(&mut self) -> anyhow::Result<Option<Tick>> {
+        let instruction = match self.instruction() {
+            Some(instruction) => instruction.clone(),
+            None => return Ok(None),
+        };
+        let tick = Tick {
+            cycle: self.cycle(),
+            registers: self.registers.clone(),
+        };
+        if self.progress + 1 == instruction.opcode.cycles {
+            let mut registers = self.registers.clone();
+            let offset = match (instruction.opcode.execute)(&mut registers, &instruction.operands)
+                .with_context(|| format!("cycle {}: {instruction}", tick.cycle))?
+            {
+                Flow::Next => 1,
+                Flow::Jump(offset) => offset,
+            };
+            self.program_counter = self
+                .program_counter
+                .checked_add(offset)
+                .with_context(|| format!("cycle {}: {instruction} jumps too far", tick.cycle))?;
+            self.registers = registers;
+            self.progress = 0;
+        } else {
+            self.progress += 1;
+        }
+        self.cycles_run += 1;
+        Ok(Some(tick))
+    }
+
+    /// Runs the rest of the current instruction, returning the cycles it
+    /// took.
+    pub fn step(&mut self) -> anyhow::Result<Option<usize>> {
+        let start = self.cycles_run;
+        if self.tick()?.is_none() {
+            return Ok(None);
+        }
+        while self.progress != 0 {
+            self.tick()?;
+        }
+        Ok(Some(self.cycles_run - start))
+    }
+
+    /// Runs until the program ends or a breakpoint is hit after a cycle.
+    pub fn run(&mut self) -> anyhow::Result<Stop> {
+        while let Some(tick) = self.tick()? {
+            let hit = self.breakpoints.iter().find(|breakpoint| match breakpoint {
+                Breakpoint::Cycle(cycle) => *cycle == self.cycle(),
+                Breakpoint::Register(name, value) => {
+                    let before = Operand::Register(*name).value(&tick.registers);
+                    before != *value && self.register(*name) == *value
+                }
+            });
+            if let Some(breakpoint) = hit {
+                return Ok(Stop::Breakpoint(*breakpoint));
+            }
+        }
+        Ok(Stop::Halted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoder() -> Decoder {
+        Decoder::new()
+            .with_opcode("nop", 0, 1, |_, _| Ok(Flow::Next))
+            .with_opcode("set", 2, 1, |registers, operands| {
+                if let Operand::Register(name) = operands[0] {
+                    registers.insert(name, operands[1].value(registers));
+                }
+                Ok(Flow::Next)
+            })
+            .with_opcode("dec", 1, 2, |registers, operands| {
+                if let Operand::Register(name) = operands[0] {
+                    let value = registers.entry(name).or_default();
+                    *value = value.checked_sub(1).context("underflow")?;
+                }
+                Ok(Flow::Next)
+            })
+            .with_opcode("jnz", 2, 3, |registers, operands| {
+                Ok(match operands[0].value(registers) {
+                    0 => Flow::Next,
+                    _ => Flow::Jump(operands[1].value(registers)),
+                })
+            })
+    }
+
+    fn countdown() -> Cpu {
+        let source = "set a 3\ndec a\njnz a -1\nnop";
+        Cpu::new(decoder().decode_program(source).unwrap())
+    }
+
+    #[test]
+    fn decode_round_trip() {
+        let source = "set a -12\ndec b\njnz a -1\nnop";
+        let program = decoder().decode_program(source).unwrap();
+        assert_eq!(program[0].operands[1], Operand::Value(-12));
+        assert_eq!(
+            program.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+            source.lines().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn decode_errors() {
+        let decoder = decoder();
+        for line in [
+            "", "mul a 2", "dec", "dec a 1", "dec A", "set a 1 ", "nop x",
+        ] {
+            assert!(decoder.decode(line).is_err(), "{line:?} decoded");
+        }
+        let error = decoder.decode_program("nop\nhlt").unwrap_err();
+        assert_eq!(
+            format!("{error:#}"),
+            "invalid instruction on line 2: unknown opcode \"hlt\""
+        );
+    }
+
+    #[test]
+    fn ticks_and_steps() {
+        let mut cpu = countdown();
+        assert_eq!(cpu.step().unwrap(), Some(1));
+        assert_eq!(cpu.register('a'), 3);
+        let tick = cpu.tick().unwrap().unwrap();
+        assert_eq!((tick.cycle, tick.registers[&'a']), (2, 3));
+        // The decrement only lands at the end of its second cycle
+        assert_eq!(cpu.register('a'), 3);
+        assert_eq!(cpu.step().unwrap(), Some(1));
+        assert_eq!(cpu.register('a'), 2);
+        assert_eq!(cpu.step().unwrap(), Some(3));
+        assert_eq!(
+            cpu.instruction().map(|i| i.to_string()),
+            Some("dec a".to_string())
+        );
+    }
+
+    #[test]
+    fn run_to_halt() {
+        let mut cpu = countdown();
+        assert_eq!(cpu.run().unwrap(), Stop::Halted);
+        // set, then three decrements and jumps, the last one falling through
+        assert_eq!(cpu.cycle(), 1 + 1 + 3 * (2 + 3) + 1);
+        assert_eq!(cpu.register('a'), 0);
+        assert!(cpu.is_halted());
+        assert_eq!(cpu.tick().unwrap(), None);
+        assert_eq!(cpu.step().unwrap(), None);
+    }
+
+    #[test]
+    fn breakpoints() {
+        let mut cpu = countdown();
+        cpu.add_breakpoint(Breakpoint::Register('a', 1));
+        cpu.add_breakpoint(Breakpoint::Cycle(4));
+        assert_eq!(cpu.run().unwrap(), Stop::Breakpoint(Breakpoint::Cycle(4)));
+        assert_eq!(cpu.register('a'), 2);
+        assert_eq!(
+            cpu.run().unwrap(),
+            Stop::Breakpoint(Breakpoint::Register('a', 1))
+        );
+        assert_eq!(cpu.cycle(), 9);
+        cpu.remove_breakpoint(&Breakpoint::Register('a', 1));
+        assert_eq!(cpu.run().unwrap(), Stop::Halted);
+        let mut cpu = countdown();
+        cpu.add_breakpoint(Breakpoint::Cycle(1));
+        assert_eq!(cpu.run().unwrap(), Stop::Halted);
+    }
+
+    #[test]
+    fn failing_instructions() {
+        let source = format!("set a {}\ndec a\nnop", i64::MIN);
+        let mut cpu = Cpu::new(decoder().decode_program(&source).unwrap());
+        assert_eq!(cpu.step().unwrap(), Some(1));
+        let error = cpu.run().unwrap_err();
+        assert_eq!(format!("{error:#}"), "cycle 3: dec a: underflow");
+        // The failed cycle didn't happen
+        assert_eq!((cpu.cycle(), cpu.register('a')), (3, i64::MIN));
+        assert_eq!(
+            cpu.instruction().map(|i| i.to_string()),
+            Some("dec a".to_string())
+        );
+
+        let source = format!("set a 1\njnz a {}", i64::MAX);
+        let mut cpu = Cpu::new(decoder().decode_program(&source).unwrap());
+        let error = cpu.run().unwrap_err();
+        assert_eq!(
+            format!("{error:#}"),
+            format!("cycle 4: jnz a {} jumps too far", i64::MAX)
+        );
+    }
+}
@@ -0,0 +1,1631 @@
+/*
+ * Generators for puzzle inputs of arbitrary size.
+ * Every generator follows the constraints of its puzzle statement, so the
+ * solvers can be benchmarked and tested beyond the official input.
+ * Example: `advent_of_code::generate::generate(16, 100, 7)`.
+ */
+
+use crate::helpers::Rng;
+use crate::ocr;
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+
+/// Size that roughly matches the official input of `day`.
+pub fn default_size(day: u8) -> Option<usize> {
+    let size = match day {
+        1 => 250,
+        2 => 2500,
+        3 => 100,
+        4 => 1000,
+        5 => 500,
+        6 => 4096,
+        7 => 180,
+        8 => 99,
+        9 => 2000,
+        10 => 6,
+        11 => 8,
+        12 => 41,
+        13 => 150,
+        14 => 150,
+        15 => 25,
+        16 => 60,
+        17 => 10091,
+        18 => 2800,
+        19 => 30,
+        20 => 5000,
+        21 => 2500,
+        22 => 4000,
+        23 => 70,
+        24 => 25,
+        25 => 120,
+        _ => return None,
+    };
+    Some(size)
+}
+
+/// Input for `day` scaled by `size`; the same seed always gives the same input.
+/// See the generator of each day for what `size` counts.
+pub fn generate(day: u8, size: usize, seed: u64) -> anyhow::Result<String> {
+    let generator = match day {
+        1 => day01,
+        2 => day02,
+        3 => day03,
+        4 => day04,
+        5 => day05,
+        6 => day06,
+        7 => day07,
+        8 => day08,
+        9 => day09,
+        10 => day10,
+        11 => day11,
+        12 => day12,
+        13 => day13,
+        14 => day14,
+        15 => day15,
+        16 => day16,
+        17 => day17,
+        18 => day18,
+        19 => day19,
+        20 => day20,
+        21 => day21,
+        22 => day22,
+        23 => day23,
+        24 => day24,
+        25 => day25,
+        _ => anyhow::bail!("there is no puzzle for day {day}"),
+    };
+    Ok(generator(&mut Rng::new(seed), size))
+}
+
+fn lowercase(rng: &mut Rng) -> char {
+    rng.range(b'a'..=b'z') as char
+}
+
+fn uppercase(rng: &mut Rng) -> char {
+    rng.range(b'A'..=b'Z') as char
+}
+
+fn word(rng: &mut Rng, len: RangeInclusive<usize>) -> String {
+    (0..rng.range(len)).map(|_| lowercase(rng)).collect()
+}
+
+/// `size` elves carrying 1 to 15 snacks each.
+pub fn day01(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            (0..rng.range(1..=15))
+                .map(|_| rng.range(1000..=60000_u32).to_string())
+                .join("\n")
+        })
+        .join("\n\n")
+}
+
+/// `size` rounds of the strategy guide.
+pub fn day02(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            format!(
+                "{} {}",
+                rng.choose(&['A', 'B', 'C']),
+                rng.choose(&['X', 'Y', 'Z'])
+            )
+        })
+        .join("\n")
+}
+
+/// `size` groups of three rucksacks. Each rucksack has exactly one item in
+/// both compartments and each group shares exactly one badge.
+pub fn day03(rng: &mut Rng, size: usize) -> String {
+    let items = ('a'..='z').chain('A'..='Z').collect::<Vec<_>>();
+    let mut lines = Vec::new();
+    for _ in 0..size.max(1) {
+        let badge = *rng.choose(&items);
+        // Keeping every other item out of one of the rucksacks leaves the
+        // badge as the only item common to the whole group
+        let excluded = items
+            .iter()
+            .map(|item| (*item, rng.range(0..=2)))
+            .collect::<HashMap<_, _>>();
+        for elf in 0..3 {
+            let mut allowed = items
+                .iter()
+                .filter(|item| **item != badge && excluded[*item] != elf)
+                .copied()
+                .collect::<Vec<_>>();
+            rng.shuffle(&mut allowed);
+            let shared = allowed.pop().expect("plenty of items to choose from");
+            let (left_pool, right_pool) = allowed.split_at(allowed.len() / 2);
+            let half = rng.range(4..=16);
+            let mut compartments = [left_pool, right_pool].map(|pool| {
+                let mut compartment = vec![shared];
+                compartment.extend((1..half).map(|_| *rng.choose(pool)));
+                compartment
+            });
+            let side = rng.range(0..=1);
+            let slot = rng.range(1..=half - 1);
+            compartments[side][slot] = badge;
+            lines.push(
+                compartments
+                    .into_iter()
+                    .flat_map(|mut compartment| {
+                        rng.shuffle(&mut compartment);
+                        compartment
+                    })
+                    .collect::<String>(),
+            );
+        }
+    }
+    lines.join("\n")
+}
+
+/// `size` pairs of section assignments.
+pub fn day04(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            let mut range = || {
+                let (a, b) = (rng.range(1..=99_u32), rng.range(1..=99_u32));
+                format!("{}-{}", a.min(b), a.max(b))
+            };
+            format!("{},{}", range(), range())
+        })
+        .join("\n")
+}
+
+/// Nine stacks of crates and `size` moves, all of which can be carried out.
+pub fn day05(rng: &mut Rng, size: usize) -> String {
+    const STACKS: usize = 9;
+    // Bottom crate first
+    let mut stacks = (0..STACKS)
+        .map(|_| {
+            (0..rng.range(0..=8))
+                .map(|_| uppercase(rng))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    if stacks.iter().all(Vec::is_empty) {
+        stacks[0].push(uppercase(rng));
+    }
+    let height = stacks.iter().map(Vec::len).max().unwrap_or(0);
+    let drawing = (0..height)
+        .rev()
+        .map(|level| {
+            stacks
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(c) => format!("[{c}]"),
+                    None => "   ".to_string(),
+                })
+                .join(" ")
+        })
+        .collect::<Vec<_>>();
+    let labels = (1..=STACKS).map(|id| format!(" {id} ")).join(" ");
+    let mut commands = Vec::new();
+    for _ in 0..size.max(1) {
+        let non_empty = (0..STACKS)
+            .filter(|id| !stacks[*id].is_empty())
+            .collect::<Vec<_>>();
+        let from = *rng.choose(&non_empty);
+        let to = (from + rng.range(1..=STACKS - 1)) % STACKS;
+        let quantity = rng.range(1..=stacks[from].len());
+        for _ in 0..quantity {
+            let c = stacks[from].pop().expect("quantity is within the stack");
+            stacks[to].push(c);
+        }
+        commands.push(format!("move {quantity} from {} to {}", from + 1, to + 1));
+    }
+    drawing
+        .into_iter()
+        .chain([labels, String::new()])
+        .chain(commands)
+        .join("\n")
+}
+
+/// Datastream of about `size` characters with a start-of-packet marker
+/// followed later by a start-of-message marker.
+pub fn day06(rng: &mut Rng, size: usize) -> String {
+    let segment = size / 4;
+    let mut letters = ('a'..='z').collect::<Vec<_>>();
+    let mut stream = String::new();
+    // Three letters can't form a start-of-packet marker
+    stream.extend((0..segment).map(|_| rng.range(b'a'..=b'c') as char));
+    rng.shuffle(&mut letters);
+    stream.extend(&letters[..4]);
+    // Thirteen letters can't form a start-of-message marker
+    stream.extend((0..segment).map(|_| rng.range(b'a'..=b'm') as char));
+    rng.shuffle(&mut letters);
+    stream.extend(&letters[..14]);
+    stream.extend((stream.len()..size).map(|_| lowercase(rng)));
+    stream
+}
+
+/// Terminal output of walking a file system with `size` directories. Like
+/// the official input, the files fill more than 40000000 of the 70000000
+/// device, so part two has to delete something.
+pub fn day07(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(2);
+    let mut children: Vec<Vec<(String, usize)>> = vec![Vec::new(); size];
+    for dir in 1..size {
+        let parent = rng.range(0..=dir - 1);
+        let name = loop {
+            let name = word(rng, 1..=8);
+            if children[parent].iter().all(|(n, _)| *n != name) {
+                break name;
+            }
+        };
+        children[parent].push((name, dir));
+    }
+    let mut files: Vec<Vec<(String, usize)>> = vec![Vec::new(); size];
+    for dir in 0..size {
+        for _ in 0..rng.range(0..=4) {
+            let name = match rng.chance(1, 2) {
+                true => format!("{}.{}", word(rng, 1..=8), word(rng, 3..=3)),
+                false => word(rng, 1..=8),
+            };
+            if files[dir]
+                .iter()
+                .chain(&children[dir])
+                .all(|(n, _)| *n != name)
+            {
+                files[dir].push((name, rng.range(1..=1000)));
+            }
+        }
+    }
+    // Deleting `/` is not an option, so one directory below it has to hold at
+    // least half of the files for part two to free enough space
+    let weight = files.iter().flatten().map(|(_, w)| w).sum::<usize>().max(1);
+    let name = loop {
+        let name = word(rng, 1..=8);
+        if files[1].iter().chain(&children[1]).all(|(n, _)| *n != name) {
+            break name;
+        }
+    };
+    files[1].push((name, weight));
+    let weights = files.iter().flatten().map(|(_, w)| w).sum::<usize>();
+    let total = rng.range(40_100_000..=69_900_000);
+    files
+        .iter_mut()
+        .flatten()
+        .for_each(|(_, w)| *w = (*w * total / weights).max(1));
+
+    fn walk(
+        dir: usize,
+        children: &[Vec<(String, usize)>],
+        files: &[Vec<(String, usize)>],
+        lines: &mut Vec<String>,
+    ) {
+        lines.push("$ ls".to_string());
+        for (name, _) in &children[dir] {
+            lines.push(format!("dir {name}"));
+        }
+        for (name, size) in &files[dir] {
+            lines.push(format!("{size} {name}"));
+        }
+        for (name, child) in &children[dir] {
+            lines.push(format!("$ cd {name}"));
+            walk(*child, children, files, lines);
+            lines.push("$ cd ..".to_string());
+        }
+    }
+
+    let mut lines = vec!["$ cd /".to_string()];
+    walk(0, &children, &files, &mut lines);
+    lines.join("\n")
+}
+
+/// Square grid of trees with `size` rows.
+pub fn day08(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+    (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| rng.range(b'0'..=b'9') as char)
+                .collect::<String>()
+        })
+        .join("\n")
+}
+
+/// `size` head motions.
+pub fn day09(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            format!(
+                "{} {}",
+                rng.choose(&['U', 'D', 'L', 'R']),
+                rng.range(1..=19)
+            )
+        })
+        .join("\n")
+}
+
+/// Program that runs for `size` rows of 40 CRT cycles, rounded up to whole
+/// lines of letters so that the CRT always shows text.
+pub fn day10(rng: &mut Rng, size: usize) -> String {
+    const COLUMNS: usize = 40;
+    // Draw random letters like the official inputs. The sprite starts at 1,
+    // so the first letter has to light the top left corner.
+    let lines = (size.max(1) + ocr::GLYPH_HEIGHT - 1) / ocr::GLYPH_HEIGHT;
+    let letters = ocr::letters().collect_vec();
+    let corner = |letter: &char| ocr::render(&letter.to_string()).unwrap().starts_with("##");
+    let first_letters = letters.iter().copied().filter(corner).collect_vec();
+    let mut text = String::new();
+    for line in 0..lines {
+        for i in 0..COLUMNS / ocr::GLYPH_PITCH {
+            let letter = match (line, i) {
+                (0, 0) => rng.choose(&first_letters),
+                _ => rng.choose(&letters),
+            };
+            text.push(*letter);
+        }
+        text.push('\n');
+    }
+    let image = ocr::render(&text).unwrap();
+    let pixels = image.lines().flat_map(|l| l.chars()).collect_vec();
+    // Every addx keeps the sprite still for a pair of pixels, so pick a
+    // position per pair that lights exactly the pixels wanted
+    let mut x = 1;
+    let mut lines = Vec::new();
+    for (pair, next) in pixels.chunks(2).skip(1).enumerate() {
+        let column = (pair + 1) * 2 % COLUMNS;
+        let lit = |x: i32, offset: usize| ((column + offset) as i32 - x).abs() <= 1;
+        let positions = (-5..=45)
+            .filter(|x| lit(*x, 0) == (next[0] == '#') && lit(*x, 1) == (next[1] == '#'))
+            .collect_vec();
+        let next_x = *rng.choose(&positions);
+        match next_x - x {
+            0 => lines.extend(["noop".to_string(), "noop".to_string()]),
+            v => lines.push(format!("addx {v}")),
+        }
+        x = next_x;
+    }
+    lines.extend(["noop".to_string(), "noop".to_string()]);
+    lines.join("\n")
+}
+
+fn primes() -> impl Iterator<Item = u64> {
+    (2..).filter(|n: &u64| (2..*n).take_while(|d| d * d <= *n).all(|d| n % d != 0))
+}
+
+enum Day11Operation {
+    Add(u64),
+    Multiply(u64),
+    Square,
+}
+
+struct Day11Monkey {
+    items: Vec<u64>,
+    operation: Day11Operation,
+    divisor: u64,
+    targets: (usize, usize),
+}
+
+/// Whether the worry levels of part one fit in `u64` for all 20 rounds.
+fn day11_fits(monkeys: &[Day11Monkey]) -> bool {
+    let mut items = monkeys.iter().map(|m| m.items.clone()).collect::<Vec<_>>();
+    for _ in 0..20 {
+        for (id, monkey) in monkeys.iter().enumerate() {
+            for old in std::mem::take(&mut items[id]) {
+                let new = match monkey.operation {
+                    Day11Operation::Add(n) => old.checked_add(n),
+                    Day11Operation::Multiply(n) => old.checked_mul(n),
+                    Day11Operation::Square => old.checked_mul(old),
+                };
+                let Some(new) = new.map(|n| n / 3) else {
+                    return false;
+                };
+                let target = match new % monkey.divisor == 0 {
+                    true => monkey.targets.0,
+                    false => monkey.targets.1,
+                };
+                items[target].push(new);
+            }
+        }
+    }
+    true
+}
+
+/// `size` monkeys, at most 9, each testing divisibility by a different prime.
+/// Part two squares worry levels below the product of all divisors, which
+/// only fits in `u64` for the first 9 primes.
+pub fn day11(rng: &mut Rng, size: usize) -> String {
+    let count = size.clamp(2, 9);
+    let mut attempts = 0;
+    let monkeys = loop {
+        attempts += 1;
+        let mut divisors = primes().take(count).collect::<Vec<_>>();
+        rng.shuffle(&mut divisors);
+        // Small groups pass items back to the squaring monkey so often that
+        // part one always overflows, so eventually give up on squaring
+        let squaring = (attempts <= 100).then(|| rng.range(0..=count - 1));
+        let monkeys = (0..count)
+            .map(|id| {
+                let items = (0..rng.range(1..=8))
+                    .map(|_| rng.range(50..=99_u64))
+                    .collect();
+                let operation = match (Some(id) == squaring, rng.chance(1, 2)) {
+                    (true, _) => Day11Operation::Square,
+                    (false, true) => Day11Operation::Add(rng.range(1..=8)),
+                    (false, false) => Day11Operation::Multiply(rng.range(2..=19)),
+                };
+                let mut others = (0..count).filter(|other| *other != id).collect::<Vec<_>>();
+                rng.shuffle(&mut others);
+                Day11Monkey {
+                    items,
+                    operation,
+                    divisor: divisors[id],
+                    targets: (others[0], *others.get(1).unwrap_or(&others[0])),
+                }
+            })
+            .collect::<Vec<_>>();
+        if day11_fits(&monkeys) {
+            break monkeys;
+        }
+    };
+    monkeys
+        .iter()
+        .enumerate()
+        .map(|(id, monkey)| {
+            let operation = match monkey.operation {
+                Day11Operation::Add(n) => format!("old + {n}"),
+                Day11Operation::Multiply(n) => format!("old * {n}"),
+                Day11Operation::Square => "old * old".to_string(),
+            };
+            format!(
+                "Monkey {id}:\n  Starting items: {}\n  Operation: new = {operation}\n  Test: divisible by {}\n    If true: throw to monkey {}\n    If false: throw to monkey {}",
+                monkey.items.iter().join(", "),
+                monkey.divisor,
+                monkey.targets.0,
+                monkey.targets.1,
+            )
+        })
+        .join("\n\n")
+}
+
+/// Height map with `size` rows. Heights rise by at most one step from the
+/// left to the right edge along the row of the start and the right edge is
+/// flat, so the goal is always reachable.
+pub fn day12(rng: &mut Rng, size: usize) -> String {
+    let rows = size.max(1);
+    let cols = (4 * rows).max(27);
+    let start_row = rng.range(0..=rows - 1);
+    let mut grid = (0..rows)
+        .map(|row| {
+            (0..cols)
+                .map(|col| {
+                    // The first two columns are at the lowest elevation
+                    let height = (col.saturating_sub(1) * 25 / (cols - 2)) as u8;
+                    let dip = if row == start_row || col == cols - 1 {
+                        0
+                    } else {
+                        rng.range(0..=2)
+                    };
+                    (b'a' + height.saturating_sub(dip)) as char
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    grid[start_row][0] = 'S';
+    grid[rng.range(0..=rows - 1)][cols - 1] = 'E';
+    grid.into_iter()
+        .map(|row| row.into_iter().collect::<String>())
+        .join("\n")
+}
+
+fn day13_packet(rng: &mut Rng, depth: usize) -> String {
+    if depth == 0 || rng.chance(1, 3) {
+        rng.range(0..=10).to_string()
+    } else {
+        let items = (0..rng.range(0..=4))
+            .map(|_| day13_packet(rng, depth - 1))
+            .join(",");
+        format!("[{items}]")
+    }
+}
+
+/// `size` pairs of packets.
+pub fn day13(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            let mut packet = || {
+                format!(
+                    "[{}]",
+                    (0..rng.range(0..=5))
+                        .map(|_| day13_packet(rng, 4))
+                        .join(",")
+                )
+            };
+            format!("{}\n{}", packet(), packet())
+        })
+        .join("\n\n")
+}
+
+/// `size` rock paths made of horizontal and vertical segments at least 13
+/// rows below the sand source. Like the official input, sand pours past the
+/// rocks into the abyss in part one instead of piling up to the source.
+pub fn day14(rng: &mut Rng, size: usize) -> String {
+    // A pile blocking the source fills every row above the highest rock, 25
+    // columns wide by row 12, and sand can only rest on top of something. No
+    // rock in one of those columns leaves nothing to rest on there.
+    let gap = 500 + rng.range(3..=12_i32) * if rng.chance(1, 2) { 1 } else { -1 };
+    (0..size.max(1))
+        .map(|_| {
+            let left = rng.chance(1, 2);
+            let side = |x: i32| if left { x.min(gap - 1) } else { x.max(gap + 1) };
+            let top = rng.range(13..=170_i32);
+            let (mut x, mut y) = (side(rng.range(450..=550)), top);
+            let mut points = vec![format!("{x},{y}")];
+            for segment in 0..rng.range(1..=12) {
+                let length = rng.range(1..=8) * if rng.chance(1, 2) { 1 } else { -1 };
+                match segment % 2 {
+                    0 => x = side(x + length),
+                    _ => y = (y + length).max(top),
+                }
+                points.push(format!("{x},{y}"));
+            }
+            points.join(" -> ")
+        })
+        .join("\n")
+}
+
+/// `size` sensors plus four that cover the corners of the search area, so
+/// exactly one position within 0..=4000000 is out of range of every sensor.
+pub fn day15(rng: &mut Rng, size: usize) -> String {
+    const LIMIT: i64 = 4_000_000;
+    let distress = (rng.range(1..=LIMIT - 1), rng.range(1..=LIMIT - 1));
+    let distance = |a: (i64, i64), b: (i64, i64)| (a.0 - b.0).abs() + (a.1 - b.1).abs();
+    let reading = |rng: &mut Rng, sensor: (i64, i64), radius: i64| {
+        let dx = rng.range(0..=radius);
+        let sign = |rng: &mut Rng| if rng.chance(1, 2) { 1 } else { -1 };
+        let beacon = (
+            sensor.0 + sign(rng) * dx,
+            sensor.1 + sign(rng) * (radius - dx),
+        );
+        format!(
+            "Sensor at x={}, y={}: closest beacon is at x={}, y={}",
+            sensor.0, sensor.1, beacon.0, beacon.1
+        )
+    };
+    let mut lines = Vec::new();
+    // A sensor at (distress.x + a, distress.y + b) with range a + b - 1 covers
+    // everything in the a by b quadrant between them except the distress beacon
+    for (sx, sy) in [(-1, -1), (-1, 1), (1, -1), (1, 1)] {
+        let a = if sx < 0 {
+            distress.0
+        } else {
+            LIMIT - distress.0
+        };
+        let b = if sy < 0 {
+            distress.1
+        } else {
+            LIMIT - distress.1
+        };
+        let sensor = (distress.0 + sx * a, distress.1 + sy * b);
+        lines.push(reading(rng, sensor, a + b - 1));
+    }
+    for _ in 0..size {
+        let sensor = (rng.range(0..=LIMIT), rng.range(0..=LIMIT));
+        let radius = rng.range(1..=(distance(sensor, distress) - 1).max(1));
+        lines.push(reading(rng, sensor, radius));
+    }
+    rng.shuffle(&mut lines);
+    lines.join("\n")
+}
+
+/// Connected network of `size` valves. Like the official input, only a
+/// handful of valves (at most 15) have a non-zero flow rate and AA is not one
+/// of them.
+pub fn day16(rng: &mut Rng, size: usize) -> String {
+    let count = size.clamp(2, 26 * 26);
+    let mut names = vec!["AA".to_string()];
+    while names.len() < count {
+        let name = format!("{}{}", uppercase(rng), uppercase(rng));
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    let mut tunnels: Vec<Vec<usize>> = vec![Vec::new(); count];
+    let mut connect = |a: usize, b: usize| {
+        if a != b && !tunnels[a].contains(&b) {
+            tunnels[a].push(b);
+            tunnels[b].push(a);
+        }
+    };
+    for valve in 1..count {
+        connect(valve, rng.range(0..=valve - 1));
+    }
+    for _ in 0..count / 5 {
+        connect(rng.range(0..=count - 1), rng.range(0..=count - 1));
+    }
+    let mut valves = (1..count).collect::<Vec<_>>();
+    rng.shuffle(&mut valves);
+    valves.truncate((count / 4).clamp(1, 15));
+    let mut order = (0..count).collect::<Vec<_>>();
+    rng.shuffle(&mut order);
+    order
+        .into_iter()
+        .map(|valve| {
+            let rate = if valves.contains(&valve) {
+                rng.range(3..=25)
+            } else {
+                0
+            };
+            let targets = tunnels[valve].iter().map(|t| &names[*t]).join(", ");
+            let tunnels = match tunnels[valve].len() {
+                1 => format!("tunnel leads to valve {targets}"),
+                _ => format!("tunnels lead to valves {targets}"),
+            };
+            format!("Valve {} has flow rate={rate}; {tunnels}", names[valve])
+        })
+        .join("\n")
+}
+
+/// Jet pattern of `size` pushes.
+pub fn day17(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1)).map(|_| *rng.choose(&['<', '>'])).collect()
+}
+
+/// `size` distinct cubes packed into a box about three times their volume.
+pub fn day18(rng: &mut Rng, size: usize) -> String {
+    let side = ((3 * size.max(1)) as f64).cbrt().ceil() as i32;
+    let count = size.max(1).min((side * side * side) as usize);
+    let mut cubes = HashSet::new();
+    let mut lines = Vec::new();
+    while cubes.len() < count {
+        let cube = (
+            rng.range(0..=side - 1),
+            rng.range(0..=side - 1),
+            rng.range(0..=side - 1),
+        );
+        if cubes.insert(cube) {
+            lines.push(format!("{},{},{}", cube.0, cube.1, cube.2));
+        }
+    }
+    lines.join("\n")
+}
+
+/// `size` blueprints with robot costs in the ranges of the official input.
+pub fn day19(rng: &mut Rng, size: usize) -> String {
+    (1..=size.max(1))
+        .map(|id| {
+            format!(
+                "Blueprint {id}: Each ore robot costs {} ore. Each clay robot costs {} ore. Each obsidian robot costs {} ore and {} clay. Each geode robot costs {} ore and {} obsidian.",
+                rng.range(2..=4),
+                rng.range(2..=4),
+                rng.range(2..=4),
+                rng.range(5..=20),
+                rng.range(2..=4),
+                rng.range(5..=20),
+            )
+        })
+        .join("\n")
+}
+
+/// `size` numbers to mix, exactly one of which is 0.
+pub fn day20(rng: &mut Rng, size: usize) -> String {
+    let mut numbers = (1..size.max(1))
+        .map(|_| loop {
+            let n = rng.range(-10_000..=10_000_i64);
+            if n != 0 {
+                break n;
+            }
+        })
+        .collect::<Vec<_>>();
+    let zero = rng.range(0..=numbers.len());
+    numbers.insert(zero, 0);
+    numbers.into_iter().join("\n")
+}
+
+struct MonkeyTree<'a> {
+    rng: &'a mut Rng,
+    names: HashSet<String>,
+    lines: Vec<String>,
+}
+
+impl MonkeyTree<'_> {
+    fn name(&mut self) -> String {
+        loop {
+            let name = word(self.rng, 4..=4);
+            if name != "root" && name != "humn" && self.names.insert(name.clone()) {
+                return name;
+            }
+        }
+    }
+
+    /// Adds a monkey that yells `value` using about `budget` monkeys and
+    /// returns its name. When `human` is set, the subtree contains `humn`, and
+    /// yells `value` once `humn` yells the answer to part two.
+    fn monkey(&mut self, value: i64, budget: usize, human: bool) -> String {
+        let mut operations = Vec::new();
+        if budget >= 3 {
+            if value >= 2 {
+                operations.push('+');
+            }
+            if value < 1_000_000_000_000 {
+                operations.push('-');
+                if !human {
+                    operations.push('/');
+                }
+            }
+            if (2..=9).any(|f| value % f == 0) {
+                operations.push('*');
+            }
+        }
+        if operations.is_empty() {
+            let name = if human {
+                "humn".to_string()
+            } else {
+                self.name()
+            };
+            // Part one uses a value that is larger than the answer to part two
+            let yelled = if human {
+                value + self.rng.range(1..=1000)
+            } else {
+                value
+            };
+            self.lines.push(format!("{name}: {yelled}"));
+            return name;
+        }
+        let operation = *self.rng.choose(&operations);
+        let (a, b) = match operation {
+            '+' => {
+                let a = self.rng.range(1..=value - 1);
+                (a, value - a)
+            }
+            '-' => {
+                let b = self.rng.range(1..=1000);
+                (value + b, b)
+            }
+            '*' => {
+                let factors = (2..=9).filter(|f| value % f == 0).collect::<Vec<_>>();
+                let f = *self.rng.choose(&factors);
+                (value / f, f)
+            }
+            _ => {
+                let b = self.rng.range(2..=5);
+                (value * b, b)
+            }
+        };
+        // Every operation on the path to humn increases with it, except for
+        // humn being subtracted, so that is never allowed
+        let human_left = human && (operation == '-' || self.rng.chance(1, 2));
+        let left_budget = self.rng.range(1..=budget - 2);
+        let a = self.monkey(a, left_budget, human_left);
+        let b = self.monkey(b, budget - 1 - left_budget, human && !human_left);
+        let name = self.name();
+        self.lines.push(format!("{name}: {a} {operation} {b}"));
+        name
+    }
+}
+
+/// About `size` monkeys, whose expressions divide evenly and have a single
+/// answer for `humn` in part two.
+pub fn day21(rng: &mut Rng, size: usize) -> String {
+    let budget = size.max(3) - 1;
+    let value = rng.range(1_000..=1_000_000_000);
+    let human_left = rng.chance(1, 2);
+    let left_budget = budget / 2;
+    let mut tree = MonkeyTree {
+        rng,
+        names: HashSet::new(),
+        lines: Vec::new(),
+    };
+    let a = tree.monkey(value, left_budget, human_left);
+    let b = tree.monkey(value, budget - left_budget, !human_left);
+    let mut lines = tree.lines;
+    lines.push(format!("root: {a} + {b}"));
+    rng.shuffle(&mut lines);
+    lines.join("\n")
+}
+
+/// Board laid out like the official cube net, whose 50 tile faces the
+/// solver's cube wrapping depends on, followed by `size` moves.
+pub fn day22(rng: &mut Rng, size: usize) -> String {
+    const FACE: usize = 50;
+    // Faces present in each band of rows, from the left
+    const NET: [&[bool]; 4] = [&[false, true, true], &[false, true], &[true, true], &[true]];
+    let mut rows = Vec::new();
+    for (band, faces) in NET.iter().enumerate() {
+        for y in 0..FACE {
+            let row = faces
+                .iter()
+                .flat_map(|present| {
+                    (0..FACE)
+                        .map(|_| match present {
+                            false => ' ',
+                            true => *rng.choose(&['.', '.', '.', '.', '.', '.', '.', '#']),
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<String>();
+            rows.push(row);
+            // The path starts on the leftmost open tile of the top row
+            if band == 0 && y == 0 {
+                rows[0].replace_range(FACE..=FACE, ".");
+            }
+        }
+    }
+    let path = (0..size.max(1))
+        .map(|i| match i % 2 {
+            0 => rng.range(1..=50).to_string(),
+            _ => rng.choose(&["L", "R"]).to_string(),
+        })
+        .collect::<String>();
+    format!("{}\n\n{path}", rows.join("\n"))
+}
+
+/// Square grove with `size` rows, about half of it covered by elves.
+pub fn day23(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+    let mut grid = (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| *rng.choose(&['.', '#']))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    grid[0][0] = '#';
+    grid.into_iter()
+        .map(|row| row.into_iter().collect::<String>())
+        .join("\n")
+}
+
+/// Whether the expedition can cross the valley, go back for the snacks and
+/// cross it again.
+fn day24_passable(valley: &[Vec<char>]) -> bool {
+    let (height, width) = (valley.len() as i64, valley[0].len() as i64);
+    let blizzard = |(y, x): (i64, i64), t: i64| {
+        valley[y as usize][(x - t).rem_euclid(width) as usize] == '>'
+            || valley[y as usize][(x + t).rem_euclid(width) as usize] == '<'
+            || valley[(y - t).rem_euclid(height) as usize][x as usize] == 'v'
+            || valley[(y + t).rem_euclid(height) as usize][x as usize] == '^'
+    };
+    let (start, end) = ((-1, 0), (height, width - 1));
+    // Blizzards repeat after this many minutes. As waiting at the entrance is
+    // always possible, the reachable positions only grow from one period to
+    // the next, and the exit is out of reach once they stop growing.
+    let period = num::integer::lcm(height, width) as usize;
+    let mut t = 0;
+    for (from, to) in [(start, end), (end, start), (start, end)] {
+        let mut history = vec![HashSet::from([from])];
+        while !history[history.len() - 1].contains(&to) {
+            if history.len() > period
+                && history[history.len() - 1] == history[history.len() - 1 - period]
+            {
+                return false;
+            }
+            t += 1;
+            let reachable = history[history.len() - 1]
+                .iter()
+                .flat_map(|(y, x)| [(*y, *x), (y - 1, *x), (y + 1, *x), (*y, x - 1), (*y, x + 1)])
+                .filter(|p| {
+                    *p == from
+                        || *p == to
+                        || (0..height).contains(&p.0)
+                            && (0..width).contains(&p.1)
+                            && !blizzard(*p, t)
+                })
+                .collect();
+            history.push(reachable);
+        }
+    }
+    true
+}
+
+/// Valley with `size` rows and about five times as many columns, which the
+/// expedition can cross three times. Like the official input, no blizzard
+/// moves up or down through the start and end columns.
+pub fn day24(rng: &mut Rng, size: usize) -> String {
+    let height = size.max(1);
+    let width = 5 * height;
+    let valley = loop {
+        let valley = (0..height)
+            .map(|_| {
+                (0..width)
+                    .map(|x| {
+                        let vertical = x != 0 && x != width - 1;
+                        match rng.range(0..=7) {
+                            0 => '<',
+                            1 => '>',
+                            2 if vertical => '^',
+                            3 if vertical => 'v',
+                            _ => '.',
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        if day24_passable(&valley) {
+            break valley;
+        }
+    };
+    let wall = |opening: usize| {
+        (0..width + 2)
+            .map(|x| if x == opening + 1 { '.' } else { '#' })
+            .collect::<String>()
+    };
+    let rows = valley
+        .into_iter()
+        .map(|row| format!("#{}#", row.into_iter().collect::<String>()));
+    [wall(0)]
+        .into_iter()
+        .chain(rows)
+        .chain([wall(width - 1)])
+        .join("\n")
+}
+
+fn to_snafu(mut n: u64) -> String {
+    let mut digits = Vec::new();
+    while n > 0 {
+        let (digit, carry) = match n % 5 {
+            0 => ('0', 0),
+            1 => ('1', 0),
+            2 => ('2', 0),
+            3 => ('=', 1),
+            _ => ('-', 1),
+        };
+        digits.push(digit);
+        n = n / 5 + carry;
+    }
+    digits.into_iter().rev().collect()
+}
+
+/// `size` fuel requirements in SNAFU.
+pub fn day25(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            let digits = rng.range(1..=19);
+            to_snafu(rng.range(1..=5_u64.pow(digits)))
+        })
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        for day in 1..=25 {
+            let size = default_size(day).unwrap() / 4;
+            let input = generate(day, size, 42).unwrap();
+            assert!(!input.is_empty());
+            assert_eq!(generate(day, size, 42).unwrap(), input);
+        }
+        assert!(generate(26, 10, 42).is_err());
+    }
+
+    #[test]
+    fn test_to_snafu() {
+        assert_eq!(to_snafu(1), "1");
+        assert_eq!(to_snafu(3), "1=");
+        assert_eq!(to_snafu(2022), "1=11-2");
+        assert_eq!(to_snafu(314159265), "1121-1110-1=0");
+    }
+
+    #[test]
+    fn test_day01_elves_carry_snacks() {
+        let input = day01(&mut Rng::new(1), 50);
+        let elves = input.split("\n\n").collect_vec();
+        assert_eq!(elves.len(), 50);
+        assert!(elves
+            .iter()
+            .all(|elf| (1..=15).contains(&elf.lines().count())));
+    }
+
+    #[test]
+    fn test_day02_rounds() {
+        let input = day02(&mut Rng::new(2), 100);
+        assert_eq!(input.lines().count(), 100);
+        assert!(input.lines().all(|l| {
+            let (opponent, response) = l.split_once(' ').unwrap();
+            ["A", "B", "C"].contains(&opponent) && ["X", "Y", "Z"].contains(&response)
+        }));
+    }
+
+    #[test]
+    fn test_day03_groups_share_one_badge() {
+        let input = day03(&mut Rng::new(3), 20);
+        assert_eq!(input.lines().count(), 60);
+        for group in input.lines().chunks(3).into_iter() {
+            let badges = group
+                .map(|l| l.chars().collect::<HashSet<_>>())
+                .reduce(|a, b| &a & &b)
+                .unwrap();
+            assert_eq!(badges.len(), 1);
+        }
+        for line in input.lines() {
+            let (left, right) = line.split_at(line.len() / 2);
+            let shared = left.chars().filter(|c| right.contains(*c)).unique();
+            assert_eq!(shared.count(), 1, "{line}");
+        }
+    }
+
+    #[test]
+    fn test_day04_pairs() {
+        let input = day04(&mut Rng::new(4), 100);
+        assert_eq!(input.lines().count(), 100);
+        for line in input.lines() {
+            for range in line.split(',') {
+                let (start, end) = range.split_once('-').unwrap();
+                assert!(start.parse::<u32>().unwrap() <= end.parse::<u32>().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_day05_moves_can_be_carried_out() {
+        let input = day05(&mut Rng::new(5), 200);
+        let (drawing, moves) = input.split_once("\n\n").unwrap();
+        let mut stacks = vec![Vec::new(); 9];
+        for row in drawing.lines().rev().skip(1) {
+            for (stack, c) in row.chars().skip(1).step_by(4).enumerate() {
+                if c != ' ' {
+                    stacks[stack].push(c);
+                }
+            }
+        }
+        assert_eq!(moves.lines().count(), 200);
+        for line in moves.lines() {
+            let [quantity, from, to] = line
+                .split(' ')
+                .filter_map(|w| w.parse::<usize>().ok())
+                .collect_vec()[..]
+            else {
+                panic!("{line}");
+            };
+            assert!(quantity <= stacks[from - 1].len(), "{line}");
+            for _ in 0..quantity {
+                let c = stacks[from - 1].pop().unwrap();
+                stacks[to - 1].push(c);
+            }
+        }
+    }
+
+    #[test]
+    fn test_day06_markers() {
+        let input = day06(&mut Rng::new(6), 200);
+        assert_eq!(input.len(), 200);
+        let marker = |n: usize| {
+            input
+                .as_bytes()
+                .windows(n)
+                .position(|w| w.iter().all_unique())
+        };
+        assert!(marker(4).unwrap() < marker(14).unwrap());
+    }
+
+    #[test]
+    fn test_day07_disk_needs_cleanup() {
+        let input = day07(&mut Rng::new(7), 30);
+        let mut sizes: HashMap<Vec<&str>, usize> = HashMap::new();
+        let mut path = Vec::new();
+        for line in input.lines() {
+            match line.split(' ').collect_vec()[..] {
+                ["$", "cd", "/"] => path.clear(),
+                ["$", "cd", ".."] => {
+                    path.pop();
+                }
+                ["$", "cd", name] => path.push(name),
+                ["$", "ls"] | ["dir", _] => {}
+                [size, _] => {
+                    for depth in 0..=path.len() {
+                        *sizes.entry(path[..depth].to_vec()).or_default() +=
+                            size.parse::<usize>().unwrap();
+                    }
+                }
+                _ => panic!("{line}"),
+            }
+        }
+        assert_eq!(input.matches("\ndir ").count() + 1, 30);
+        let used = sizes[&vec![]];
+        assert!((40_000_001..=70_000_000).contains(&used));
+        let needed = used - 40_000_000;
+        assert!(sizes
+            .iter()
+            .any(|(path, size)| !path.is_empty() && *size >= needed));
+    }
+
+    #[test]
+    fn test_day08_square_grid() {
+        let input = day08(&mut Rng::new(8), 20);
+        assert_eq!(input.lines().count(), 20);
+        assert!(input
+            .lines()
+            .all(|l| l.len() == 20 && l.bytes().all(|b| b.is_ascii_digit())));
+    }
+
+    #[test]
+    fn test_day09_motions() {
+        let input = day09(&mut Rng::new(9), 200);
+        assert_eq!(input.lines().count(), 200);
+        assert!(input.lines().all(|l| {
+            let (direction, steps) = l.split_once(' ').unwrap();
+            "UDLR".contains(direction) && steps.parse::<u32>().unwrap() > 0
+        }));
+    }
+
+    #[test]
+    fn test_day10_runs_for_whole_rows() {
+        for (size, rows) in [(0, 6), (1, 6), (5, 6), (6, 6), (7, 12), (12, 12)] {
+            let input = day10(&mut Rng::new(10), size);
+            let cycles: usize = input.lines().map(|l| if l == "noop" { 1 } else { 2 }).sum();
+            assert_eq!(cycles, rows * 40, "size {size}");
+        }
+    }
+
+    #[test]
+    fn test_day10_draws_letters() {
+        for seed in 0..20 {
+            let input = day10(&mut Rng::new(seed), 12);
+            let mut x = 1;
+            let mut positions = Vec::new();
+            for line in input.lines() {
+                positions.push(x);
+                if let Some(v) = line.strip_prefix("addx ") {
+                    positions.push(x);
+                    x += v.parse::<i32>().unwrap();
+                }
+            }
+            let image = positions
+                .chunks(40)
+                .map(|row| {
+                    (0..40)
+                        .zip(row)
+                        .map(|(column, x)| if (column - x).abs() <= 1 { '#' } else { '.' })
+                        .collect::<String>()
+                })
+                .join("\n");
+            let text = ocr::read(&image).unwrap();
+            assert!(text.lines().all(|line| line.len() == 8), "{text}");
+        }
+    }
+
+    /// Grains of sand resting before one falls into the abyss, and before the
+    /// source is blocked once there is a floor.
+    fn day14_sand(input: &str) -> (usize, usize) {
+        let mut cave = HashSet::new();
+        for line in input.lines() {
+            let points = line
+                .split(" -> ")
+                .map(|p| {
+                    let (x, y) = p.split_once(',').unwrap();
+                    (x.parse::<i32>().unwrap(), y.parse::<i32>().unwrap())
+                })
+                .collect_vec();
+            for (a, b) in points.into_iter().tuple_windows() {
+                for x in a.0.min(b.0)..=a.0.max(b.0) {
+                    for y in a.1.min(b.1)..=a.1.max(b.1) {
+                        cave.insert((x, y));
+                    }
+                }
+            }
+        }
+        let floor = cave.iter().map(|p| p.1).max().unwrap() + 2;
+        let mut abyss = None;
+        for grain in 0.. {
+            let (mut x, mut y) = (500, 0);
+            while let Some(dx) = [0, -1, 1]
+                .into_iter()
+                .find(|dx| y + 1 < floor && !cave.contains(&(x + dx, y + 1)))
+            {
+                (x, y) = (x + dx, y + 1);
+            }
+            if y == floor - 1 && abyss.is_none() {
+                abyss = Some(grain);
+            }
+            cave.insert((x, y));
+            if (x, y) == (500, 0) {
+                return (abyss.unwrap_or(grain), grain + 1);
+            }
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn test_day14_sand_escapes() {
+        assert_eq!(
+            day14_sand("498,4 -> 498,6 -> 496,6\n503,4 -> 502,4 -> 502,9 -> 494,9"),
+            (24, 93)
+        );
+        for seed in 1..=5 {
+            let (abyss, blocked) = day14_sand(&day14(&mut Rng::new(seed), 150));
+            assert!(blocked > abyss + 1, "seed {seed}: {abyss} then {blocked}");
+        }
+    }
+
+    #[test]
+    fn test_day11_worry_levels_fit() {
+        for seed in 0..10 {
+            let input = day11(&mut Rng::new(seed), 8);
+            let monkeys = input
+                .split("\n\n")
+                .map(|monkey| {
+                    let lines = monkey.lines().collect_vec();
+                    let numbers = |line: &str| {
+                        line.split(|c: char| !c.is_ascii_digit())
+                            .filter_map(|n| n.parse::<u64>().ok())
+                            .collect_vec()
+                    };
+                    let operation = lines[2].split(' ').rev().take(2).collect_vec();
+                    (
+                        numbers(lines[1]),
+                        (operation[1].to_string(), operation[0].parse::<u64>().ok()),
+                        numbers(lines[3])[0],
+                        (numbers(lines[4])[0] as usize, numbers(lines[5])[0] as usize),
+                    )
+                })
+                .collect_vec();
+            assert_eq!(monkeys.len(), 8);
+            let divisors = monkeys.iter().map(|m| m.2).collect_vec();
+            assert!(divisors.iter().all_unique());
+            assert!(divisors.iter().all(|d| (2..*d).all(|f| d % f != 0)));
+            let product = divisors.iter().product::<u64>();
+            assert!(product.checked_mul(product).is_some());
+            let mut items = monkeys.iter().map(|m| m.0.clone()).collect_vec();
+            for _ in 0..20 {
+                for (id, (_, (operator, operand), divisor, targets)) in monkeys.iter().enumerate() {
+                    for old in std::mem::take(&mut items[id]) {
+                        let operand = operand.unwrap_or(old);
+                        let new = match operator.as_str() {
+                            "+" => old.checked_add(operand),
+                            _ => old.checked_mul(operand),
+                        };
+                        let new = new.expect("worry level overflows") / 3;
+                        let target = if new % divisor == 0 {
+                            targets.0
+                        } else {
+                            targets.1
+                        };
+                        items[target].push(new);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_day12_goal_is_reachable() {
+        for seed in 0..10 {
+            let input = day12(&mut Rng::new(seed), 10);
+            let grid = input.lines().map(|l| l.as_bytes()).collect_vec();
+            assert_eq!(grid.len(), 10);
+            let height = |b: u8| match b {
+                b'S' => b'a',
+                b'E' => b'z',
+                b => b,
+            };
+            let find = |target: u8| {
+                (0..grid.len())
+                    .flat_map(|y| (0..grid[0].len()).map(move |x| (y, x)))
+                    .find(|(y, x)| grid[*y][*x] == target)
+                    .unwrap()
+            };
+            let mut seen = HashSet::from([find(b'S')]);
+            let mut queue = vec![find(b'S')];
+            while let Some((y, x)) = queue.pop() {
+                let neighbours = [
+                    (y + 1, x),
+                    (y, x + 1),
+                    (y.wrapping_sub(1), x),
+                    (y, x.wrapping_sub(1)),
+                ];
+                for (ny, nx) in neighbours {
+                    let Some(&next) = grid.get(ny).and_then(|row| row.get(nx)) else {
+                        continue;
+                    };
+                    if height(next) <= height(grid[y][x]) + 1 && seen.insert((ny, nx)) {
+                        queue.push((ny, nx));
+                    }
+                }
+            }
+            assert!(seen.contains(&find(b'E')), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_day13_pairs_of_packets() {
+        let input = day13(&mut Rng::new(13), 30);
+        let pairs = input.split("\n\n").collect_vec();
+        assert_eq!(pairs.len(), 30);
+        for packet in pairs.iter().flat_map(|pair| pair.lines()) {
+            let depths = packet.chars().scan(0, |depth, c| {
+                match c {
+                    '[' => *depth += 1,
+                    ']' => *depth -= 1,
+                    _ => {}
+                }
+                Some(*depth)
+            });
+            assert!(depths
+                .collect_vec()
+                .split_last()
+                .map_or(false, |(last, rest)| {
+                    *last == 0 && rest.iter().all(|d| *d > 0)
+                }));
+        }
+    }
+
+    #[test]
+    fn test_day15_one_position_uncovered() {
+        const LIMIT: i64 = 4_000_000;
+        for seed in 0..10 {
+            let input = day15(&mut Rng::new(seed), 25);
+            let sensors = input
+                .lines()
+                .map(|line| {
+                    let n = line
+                        .split(|c: char| c != '-' && !c.is_ascii_digit())
+                        .filter_map(|n| n.parse::<i64>().ok())
+                        .collect_vec();
+                    ((n[0], n[1]), (n[0] - n[2]).abs() + (n[1] - n[3]).abs())
+                })
+                .collect_vec();
+            assert_eq!(sensors.len(), 25 + 4);
+            let covered = |p: (i64, i64)| {
+                sensors
+                    .iter()
+                    .any(|(s, r)| (s.0 - p.0).abs() + (s.1 - p.1).abs() <= *r)
+            };
+            // A lone gap sits where the edges just outside two sensors cross
+            let (rising, falling): (Vec<_>, Vec<_>) = sensors
+                .iter()
+                .flat_map(|((x, y), r)| [-1, 1].map(|d| (x + y + d * (r + 1), x - y + d * (r + 1))))
+                .unzip();
+            let gaps = rising
+                .iter()
+                .cartesian_product(&falling)
+                .filter(|(a, b)| (*a + *b) % 2 == 0)
+                .map(|(a, b)| ((a + b) / 2, (a - b) / 2))
+                .filter(|p| (0..=LIMIT).contains(&p.0) && (0..=LIMIT).contains(&p.1))
+                .filter(|p| !covered(*p))
+                .unique()
+                .collect_vec();
+            assert_eq!(gaps.len(), 1, "seed {seed}");
+            let (gx, gy) = gaps[0];
+            // Every rectangle between the gap and a corner of the area is
+            // covered by a single sensor, bar the gap itself. Sensors are
+            // convex, so covering the corners of that shape covers it all.
+            for (cx, cy) in [(0, 0), (0, LIMIT), (LIMIT, 0), (LIMIT, LIMIT)] {
+                let (dx, dy) = ((cx - gx).signum(), (cy - gy).signum());
+                let shape = [(cx, cy), (cx, gy), (gx, cy), (gx + dx, gy), (gx, gy + dy)];
+                assert!(sensors.iter().any(|(s, r)| shape
+                    .iter()
+                    .all(|p| (s.0 - p.0).abs() + (s.1 - p.1).abs() <= *r)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_day16_connected_network() {
+        let input = day16(&mut Rng::new(16), 60);
+        let valves = input
+            .lines()
+            .map(|line| {
+                let words = line
+                    .split([' ', '=', ';', ','])
+                    .filter(|w| !w.is_empty())
+                    .collect_vec();
+                let tunnels = words[10..].to_vec();
+                (words[1], (words[5].parse::<u32>().unwrap(), tunnels))
+            })
+            .collect::<HashMap<_, _>>();
+        assert_eq!(valves.len(), 60);
+        assert_eq!(valves["AA"].0, 0);
+        let working = valves.values().filter(|(rate, _)| *rate > 0).count();
+        assert!((1..=15).contains(&working));
+        let mut seen = HashSet::from(["AA"]);
+        let mut queue = vec!["AA"];
+        while let Some(valve) = queue.pop() {
+            for next in &valves[valve].1 {
+                assert!(valves[next].1.contains(&valve));
+                if seen.insert(*next) {
+                    queue.push(*next);
+                }
+            }
+        }
+        assert_eq!(seen.len(), 60);
+    }
+
+    #[test]
+    fn test_day17_jets() {
+        let input = day17(&mut Rng::new(17), 200);
+        assert_eq!(input.len(), 200);
+        assert!(input.chars().all(|c| c == '<' || c == '>'));
+    }
+
+    #[test]
+    fn test_day18_distinct_packed_cubes() {
+        let input = day18(&mut Rng::new(18), 1000);
+        let cubes = input
+            .lines()
+            .map(|l| {
+                l.split(',')
+                    .map(|n| n.parse::<i32>().unwrap())
+                    .collect_vec()
+            })
+            .collect_vec();
+        assert_eq!(cubes.len(), 1000);
+        assert!(cubes.iter().all_unique());
+        let volume = (0..3)
+            .map(|axis| cubes.iter().map(|c| c[axis]).max().unwrap() + 1)
+            .product::<i32>();
+        assert!((3000..4000).contains(&volume), "{volume}");
+    }
+
+    #[test]
+    fn test_day19_blueprints() {
+        let input = day19(&mut Rng::new(19), 30);
+        for (i, line) in input.lines().enumerate() {
+            let n = line
+                .split(|c: char| !c.is_ascii_digit())
+                .filter_map(|n| n.parse::<u32>().ok())
+                .collect_vec();
+            assert_eq!(n[0] as usize, i + 1);
+            for (cost, range) in n[1..]
+                .iter()
+                .zip([2..=4, 2..=4, 2..=4, 5..=20, 2..=4, 5..=20])
+            {
+                assert!(range.contains(cost), "{line}");
+            }
+        }
+        assert_eq!(input.lines().count(), 30);
+    }
+
+    #[test]
+    fn test_day20_one_zero() {
+        let input = day20(&mut Rng::new(20), 200);
+        assert_eq!(input.lines().count(), 200);
+        assert_eq!(input.lines().filter(|l| *l == "0").count(), 1);
+    }
+
+    #[test]
+    fn test_day21_single_answer() {
+        for seed in 0..10 {
+            let input = day21(&mut Rng::new(seed), 100);
+            let monkeys = input
+                .lines()
+                .map(|l| {
+                    let (name, job) = l.split_once(": ").unwrap();
+                    (name, job.split(' ').collect_vec())
+                })
+                .collect::<HashMap<_, _>>();
+            // The number a monkey yells, or `None` if it depends on an
+            // unknown `humn`, checking that every division is exact
+            fn yell(
+                monkeys: &HashMap<&str, Vec<&str>>,
+                name: &str,
+                humn: Option<i64>,
+            ) -> Option<i64> {
+                if name == "humn" && humn.is_some() {
+                    return humn;
+                }
+                match monkeys[name][..] {
+                    [n] if name != "humn" => Some(n.parse().unwrap()),
+                    [a, op, b] => {
+                        let (a, b) = (yell(monkeys, a, humn)?, yell(monkeys, b, humn)?);
+                        Some(match op {
+                            "+" => a + b,
+                            "-" => a - b,
+                            "*" => a * b,
+                            _ => {
+                                assert_eq!(a % b, 0);
+                                a / b
+                            }
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            let own = monkeys["humn"][0].parse().unwrap();
+            yell(&monkeys, "root", Some(own)).unwrap();
+            // Undo the operations from root down to humn
+            let (mut name, mut target) = ("root", None);
+            while name != "humn" {
+                let [a, op, b] = monkeys[name][..] else {
+                    panic!("{name} yells a number");
+                };
+                let (x, y) = (yell(&monkeys, a, None), yell(&monkeys, b, None));
+                let Some(wanted) = target else {
+                    // root checks equality, so one side has to match the other
+                    assert!(x.is_none() != y.is_none());
+                    (name, target) = if x.is_none() { (a, y) } else { (b, x) };
+                    continue;
+                };
+                target = Some(match (x, op, y) {
+                    (None, "+", Some(y)) | (Some(y), "+", None) => wanted - y,
+                    (None, "-", Some(y)) => wanted + y,
+                    (Some(x), "-", None) => x - wanted,
+                    (None, "*", Some(y)) | (Some(y), "*", None) => {
+                        assert!(y != 0 && wanted % y == 0);
+                        wanted / y
+                    }
+                    (None, "/", Some(y)) => wanted * y,
+                    _ => panic!("humn on both or neither side of {name}"),
+                });
+                name = if x.is_none() { a } else { b };
+            }
+            let answer = target.unwrap();
+            let [a, _, b] = monkeys["root"][..] else {
+                panic!("root yells a number");
+            };
+            assert_eq!(
+                yell(&monkeys, a, Some(answer)),
+                yell(&monkeys, b, Some(answer))
+            );
+        }
+    }
+
+    #[test]
+    fn test_day22_cube_net() {
+        let input = day22(&mut Rng::new(22), 50);
+        let (board, path) = input.split_once("\n\n").unwrap();
+        let rows = board.lines().collect_vec();
+        assert_eq!(rows.len(), 200);
+        let faces = ["-##", "-#", "##", "#"];
+        for (y, row) in rows.iter().enumerate() {
+            let layout = row
+                .as_bytes()
+                .chunks(50)
+                .map(|face| match face.iter().all(|b| *b == b' ') {
+                    true => '-',
+                    false => '#',
+                })
+                .collect::<String>();
+            assert_eq!(layout, faces[y / 50]);
+            assert!(row.bytes().all(|b| b" .#".contains(&b)));
+        }
+        assert_eq!(rows[0].as_bytes()[50], b'.');
+        let steps = path
+            .split(['L', 'R'])
+            .filter(|n| !n.is_empty())
+            .collect_vec();
+        let turns = path.matches(['L', 'R']).count();
+        assert_eq!(steps.len() + turns, 50);
+        assert!(steps.iter().all(|n| (1..=50).contains(&n.parse().unwrap())));
+    }
+
+    #[test]
+    fn test_day23_half_covered_grove() {
+        let input = day23(&mut Rng::new(23), 30);
+        assert_eq!(input.lines().count(), 30);
+        assert!(input.lines().all(|l| l.len() == 30));
+        let elves = input.matches('#').count();
+        assert!((300..=600).contains(&elves), "{elves}");
+    }
+
+    #[test]
+    fn test_day24_valley() {
+        let input = day24(&mut Rng::new(24), 4);
+        let rows = input.lines().map(|l| l.chars().collect_vec()).collect_vec();
+        assert_eq!((rows.len(), rows[0].len()), (4 + 2, 20 + 2));
+        let valley = rows[1..5]
+            .iter()
+            .map(|row| row[1..21].to_vec())
+            .collect_vec();
+        assert!(valley
+            .iter()
+            .all(|row| !"^v".contains(row[0]) && !"^v".contains(row[19])));
+        assert!(day24_passable(&valley));
+    }
+
+    #[test]
+    fn test_day25_snafu_numbers() {
+        let input = day25(&mut Rng::new(25), 120);
+        assert_eq!(input.lines().count(), 120);
+        assert!(input
+            .lines()
+            .all(|l| !l.starts_with('0') && l.chars().all(|c| "=-012".contains(c))));
+    }
+
+    #[test]
+    fn test_day24_passable() {
+        let valley = |input: &str| {
+            input
+                .lines()
+                .map(|l| l.chars().collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+        assert!(day24_passable(&valley(">>.<^<\n.<..<<\n>v.><>\n<^v^^>")));
+        assert!(!day24_passable(&valley(">>>>>")));
+    }
+}
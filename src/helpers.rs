@@ -3,8 +3,36 @@
  * Example import from this file: `use advent_of_code::helpers::example_fn;`.
  */
 
-use std::collections::VecDeque;
+use num::{
+    CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, FromPrimitive, Integer, PrimInt, ToPrimitive,
+};
+use std::collections::{vec_deque, VecDeque};
+use std::fmt;
+use std::ops::RangeInclusive;
 
+/// Error returned by the checked [`Stack`] operations when asked for more
+/// items than the stack holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackUnderflow {
+    pub requested: usize,
+    pub available: usize,
+}
+
+impl fmt::Display for StackUnderflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "requested {} items from a stack holding {}",
+            self.requested, self.available
+        )
+    }
+}
+
+impl std::error::Error for StackUnderflow {}
+
+/// LIFO container. Iteration and `peek_n` go from the top down, while
+/// `FromIterator` and `Display` go from the bottom up, so collecting a
+/// sequence and printing it gives the items back in the same order.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Stack<T> {
     crates: VecDeque<T>,
@@ -19,20 +47,763 @@ impl<T> Stack<T> {
         self.crates.push_front(item);
     }
 
+    /// Pops up to `n` items, fewer if the stack runs out. The first item is
+    /// the one that was on top.
     pub fn pop_n(&mut self, n: usize) -> Vec<T> {
+        let n = n.min(self.len());
         self.crates.drain(0..n).collect()
     }
 
+    /// Pops exactly `n` items, leaving the stack untouched if it holds fewer.
+    pub fn try_pop_n(&mut self, n: usize) -> Result<Vec<T>, StackUnderflow> {
+        self.check_len(n)?;
+        Ok(self.crates.drain(0..n).collect())
+    }
+
+    /// Pushes `items` so that the first one ends up on top.
     pub fn push_n(&mut self, items: Vec<T>) {
         items.into_iter().rev().for_each(|i| self.push(i));
     }
 
+    /// Takes up to `n` items from the bottom, fewer if the stack runs out.
+    /// They keep their order, so the first item is the highest of them.
+    pub fn pop_bottom_n(&mut self, n: usize) -> Vec<T> {
+        let n = n.min(self.len());
+        self.crates.drain(self.len() - n..).collect()
+    }
+
+    /// Slides `items` in under the top `depth` items, or under all of them
+    /// if the stack holds fewer. The first item ends up highest.
+    pub fn insert_n_at(&mut self, depth: usize, items: Vec<T>) {
+        let below = self.crates.split_off(depth.min(self.len()));
+        self.crates.extend(items);
+        self.crates.extend(below);
+    }
+
     pub fn top_item(&self) -> Option<&T> {
         self.crates.front()
     }
 
+    /// The top `n` items, or an error if the stack holds fewer.
+    pub fn peek_n(&self, n: usize) -> Result<vec_deque::Iter<'_, T>, StackUnderflow> {
+        self.check_len(n)?;
+        Ok(self.crates.range(0..n))
+    }
+
+    pub fn iter(&self) -> vec_deque::Iter<'_, T> {
+        self.crates.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.crates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.crates.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.crates.capacity()
+    }
+
     pub fn new() -> Stack<T> {
         let crates = VecDeque::new();
         Stack { crates }
     }
+
+    pub fn with_capacity(capacity: usize) -> Stack<T> {
+        let crates = VecDeque::with_capacity(capacity);
+        Stack { crates }
+    }
+
+    fn check_len(&self, n: usize) -> Result<(), StackUnderflow> {
+        if n > self.len() {
+            Err(StackUnderflow {
+                requested: n,
+                available: self.len(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<T> FromIterator<T> for Stack<T> {
+    /// Pushes the items in order, so the last one ends up on top.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut stack = Stack::new();
+        stack.extend(iter);
+        stack
+    }
+}
+
+impl<T> Extend<T> for Stack<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|i| self.push(i));
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Stack<T> {
+    type Item = &'a T;
+    type IntoIter = vec_deque::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Stack<T> {
+    /// Writes the items from the bottom up, separated by spaces.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, item) in self.crates.iter().rev().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", item)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Operator::Add => "+",
+            Operator::Subtract => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+            Operator::Remainder => "%",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// Error returned by [`CheckedNum`] operations instead of wrapping or panicking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArithmeticError {
+    Overflow {
+        lhs: String,
+        operator: Operator,
+        rhs: String,
+    },
+    DivisionByZero,
+    OutOfRange(String),
+}
+
+impl fmt::Display for ArithmeticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArithmeticError::Overflow { lhs, operator, rhs } => {
+                write!(f, "{} {} {} overflows", lhs, operator, rhs)
+            }
+            ArithmeticError::DivisionByZero => write!(f, "division by zero"),
+            ArithmeticError::OutOfRange(n) => write!(f, "{} is out of range", n),
+        }
+    }
+}
+
+impl std::error::Error for ArithmeticError {}
+
+/// Integer type a solver can be instantiated with: a primitive for speed, or
+/// `num::BigInt`/`num::BigUint` when values outgrow 64 bits. Every operation is
+/// checked, so the primitives report overflow rather than wrap or panic.
+pub trait CheckedNum:
+    Clone
+    + fmt::Debug
+    + fmt::Display
+    + Integer
+    + CheckedAdd
+    + CheckedSub
+    + CheckedMul
+    + CheckedDiv
+    + FromPrimitive
+    + ToPrimitive
+{
+    fn try_add(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+        self.checked_add(rhs)
+            .ok_or_else(|| overflow(self, Operator::Add, rhs))
+    }
+
+    fn try_sub(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+        self.checked_sub(rhs)
+            .ok_or_else(|| overflow(self, Operator::Subtract, rhs))
+    }
+
+    fn try_mul(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+        self.checked_mul(rhs)
+            .ok_or_else(|| overflow(self, Operator::Multiply, rhs))
+    }
+
+    fn try_div(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+        if rhs.is_zero() {
+            return Err(ArithmeticError::DivisionByZero);
+        }
+        self.checked_div(rhs)
+            .ok_or_else(|| overflow(self, Operator::Divide, rhs))
+    }
+
+    /// Remainder with the sign of `rhs`, like `i64::rem_euclid` for positive `rhs`.
+    fn try_mod_floor(&self, rhs: &Self) -> Result<Self, ArithmeticError> {
+        if rhs.is_zero() {
+            return Err(ArithmeticError::DivisionByZero);
+        }
+        // `MIN % -1` is the only remainder that can overflow.
+        self.checked_div(rhs)
+            .ok_or_else(|| overflow(self, Operator::Remainder, rhs))?;
+        Ok(self.mod_floor(rhs))
+    }
+
+    fn try_from_u64(n: u64) -> Result<Self, ArithmeticError> {
+        Self::from_u64(n).ok_or_else(|| ArithmeticError::OutOfRange(n.to_string()))
+    }
+
+    fn try_from_i64(n: i64) -> Result<Self, ArithmeticError> {
+        Self::from_i64(n).ok_or_else(|| ArithmeticError::OutOfRange(n.to_string()))
+    }
+
+    fn try_from_usize(n: usize) -> Result<Self, ArithmeticError> {
+        Self::from_usize(n).ok_or_else(|| ArithmeticError::OutOfRange(n.to_string()))
+    }
+
+    fn try_to_usize(&self) -> Result<usize, ArithmeticError> {
+        self.to_usize()
+            .ok_or_else(|| ArithmeticError::OutOfRange(self.to_string()))
+    }
+}
+
+impl<T> CheckedNum for T where
+    T: Clone
+        + fmt::Debug
+        + fmt::Display
+        + Integer
+        + CheckedAdd
+        + CheckedSub
+        + CheckedMul
+        + CheckedDiv
+        + FromPrimitive
+        + ToPrimitive
+{
+}
+
+fn overflow<T: fmt::Display>(lhs: &T, operator: Operator, rhs: &T) -> ArithmeticError {
+    ArithmeticError::Overflow {
+        lhs: lhs.to_string(),
+        operator,
+        rhs: rhs.to_string(),
+    }
+}
+
+/// Closed, non-empty interval of integers `start..=end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+// Intervals are never empty, so `len` comes without `is_empty`
+#[allow(clippy::len_without_is_empty)]
+impl<T: PrimInt> Interval<T> {
+    /// `None` if `start` is greater than `end`.
+    pub fn new(start: T, end: T) -> Option<Interval<T>> {
+        (start <= end).then_some(Interval { start, end })
+    }
+
+    pub fn point(x: T) -> Interval<T> {
+        Interval { start: x, end: x }
+    }
+
+    pub fn start(&self) -> T {
+        self.start
+    }
+
+    pub fn end(&self) -> T {
+        self.end
+    }
+
+    /// Number of integers in the interval, saturating at `u64::MAX`.
+    pub fn len(&self) -> u64 {
+        // The width of a signed interval can exceed T::MAX, so measure it in i128
+        let width = match (self.start.to_i128(), self.end.to_i128()) {
+            (Some(start), Some(end)) => end.wrapping_sub(start) as u128,
+            // Only u128 values don't fit in i128, and they subtract without overflow
+            _ => (self.end - self.start).to_u128().unwrap_or(u128::MAX),
+        };
+        u64::try_from(width.saturating_add(1)).unwrap_or(u64::MAX)
+    }
+
+    pub fn contains(&self, x: T) -> bool {
+        self.start <= x && x <= self.end
+    }
+
+    pub fn contains_interval(&self, other: &Interval<T>) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Interval<T>) -> bool {
+        self.start.max(other.start) <= self.end.min(other.end)
+    }
+
+    pub fn intersection(&self, other: &Interval<T>) -> Option<Interval<T>> {
+        Interval::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    /// Whether `other`, starting no earlier than `self`, overlaps or directly
+    /// follows it, so the two can be merged into one interval.
+    fn joins(&self, other: &Interval<T>) -> bool {
+        other.start <= self.end || self.end.checked_add(&T::one()) == Some(other.start)
+    }
+}
+
+impl<T: PrimInt> From<Interval<T>> for RangeInclusive<T> {
+    fn from(interval: Interval<T>) -> RangeInclusive<T> {
+        interval.start..=interval.end
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Interval<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}..={}", self.start, self.end)
+    }
+}
+
+/// Set of integers stored as sorted intervals, none of which overlap or
+/// touch each other. Collecting intervals into a set merges them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T: PrimInt> IntervalSet<T> {
+    pub fn new() -> IntervalSet<T> {
+        IntervalSet {
+            intervals: Vec::new(),
+        }
+    }
+
+    pub fn intervals(&self) -> &[Interval<T>] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Number of integers in the set, saturating at `u64::MAX`.
+    pub fn len(&self) -> u64 {
+        self.intervals
+            .iter()
+            .fold(0_u64, |len, interval| len.saturating_add(interval.len()))
+    }
+
+    pub fn contains(&self, x: T) -> bool {
+        let i = self.intervals.partition_point(|interval| interval.end < x);
+        self.intervals
+            .get(i)
+            .map_or(false, |interval| interval.start <= x)
+    }
+
+    pub fn insert(&mut self, interval: Interval<T>) {
+        let i = self
+            .intervals
+            .partition_point(|other| other.start <= interval.start);
+        self.intervals.insert(i, interval);
+        self.intervals = Self::merged(std::mem::take(&mut self.intervals));
+    }
+
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        self.intervals
+            .iter()
+            .chain(&other.intervals)
+            .copied()
+            .collect()
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut intervals = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.intervals.get(i), other.intervals.get(j)) {
+            intervals.extend(a.intersection(b));
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { intervals }
+    }
+
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut intervals = Vec::new();
+        let mut j = 0;
+        for a in &self.intervals {
+            while other.intervals.get(j).map_or(false, |b| b.end < a.start) {
+                j += 1;
+            }
+            // Cut the parts covered by `other` out of `a`, left to right
+            let mut start = Some(a.start);
+            for b in other.intervals[j..].iter().take_while(|b| b.start <= a.end) {
+                let Some(from) = start else { break };
+                if b.start > from {
+                    intervals.push(Interval {
+                        start: from,
+                        end: b.start - T::one(),
+                    });
+                }
+                start = (b.end < a.end).then(|| b.end + T::one());
+            }
+            if let Some(from) = start {
+                intervals.push(Interval {
+                    start: from,
+                    end: a.end,
+                });
+            }
+        }
+        IntervalSet { intervals }
+    }
+
+    /// Merges intervals sorted by start.
+    fn merged(sorted: Vec<Interval<T>>) -> Vec<Interval<T>> {
+        let mut merged: Vec<Interval<T>> = Vec::with_capacity(sorted.len());
+        for interval in sorted {
+            match merged.last_mut() {
+                Some(last) if last.joins(&interval) => last.end = last.end.max(interval.end),
+                _ => merged.push(interval),
+            }
+        }
+        merged
+    }
+}
+
+impl<T: PrimInt> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        let mut intervals = iter.into_iter().collect::<Vec<_>>();
+        intervals.sort_unstable();
+        IntervalSet {
+            intervals: Self::merged(intervals),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a IntervalSet<T> {
+    type Item = &'a Interval<T>;
+    type IntoIter = std::slice::Iter<'a, Interval<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.intervals.iter()
+    }
+}
+
+/// Seeded pseudo-random generator (SplitMix64). Not suitable for anything
+/// but tests and generated inputs, where the same seed must always give the
+/// same sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `range`. Panics if the range is empty.
+    pub fn range<T: PrimInt>(&mut self, range: RangeInclusive<T>) -> T {
+        let low = range.start().to_i128().expect("range fits in i128");
+        let high = range.end().to_i128().expect("range fits in i128");
+        assert!(low <= high, "Rng::range called with an empty range");
+        let span = (high - low) as u128 + 1;
+        let offset = (self.next_u64() as u128 % span) as i128;
+        T::from(low + offset).expect("value lies within the range")
+    }
+
+    /// True with probability `numerator / denominator`. Always false if the
+    /// denominator is 0.
+    pub fn chance(&mut self, numerator: u64, denominator: u64) -> bool {
+        match denominator.checked_sub(1) {
+            Some(max) => self.range(0..=max) < numerator,
+            None => false,
+        }
+    }
+
+    /// Uniformly picked element. Panics if `items` is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.range(0..=items.len() - 1)]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.range(0..=i));
+        }
+    }
+
+    /// Copy of `input` with a few random characters deleted, duplicated or
+    /// replaced, either by one of `alphabet` or by arbitrary unicode. Meant to
+    /// produce inputs that are almost, but not quite, well formed.
+    pub fn mutate(&mut self, input: &str, alphabet: &str) -> String {
+        let mut chars: Vec<char> = input.chars().collect();
+        let alphabet: Vec<char> = alphabet.chars().collect();
+        for _ in 0..self.range(1..=4) {
+            let pos = self.range(0..=chars.len());
+            let replacement = if alphabet.is_empty() || self.chance(1, 8) {
+                char::from_u32(self.range(0..=0x10ffff)).unwrap_or('\u{fffd}')
+            } else {
+                *self.choose(&alphabet)
+            };
+            match self.range(0..=3) {
+                0 if pos < chars.len() => {
+                    chars.remove(pos);
+                }
+                1 if pos < chars.len() => chars.insert(pos, chars[pos]),
+                2 if pos < chars.len() => chars[pos] = replacement,
+                _ => chars.insert(pos, replacement),
+            }
+        }
+        chars.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stack_from_iter() {
+        let stack: Stack<char> = "ZND".chars().collect();
+        assert_eq!(stack.top_item(), Some(&'D'));
+        assert_eq!(stack.len(), 3);
+        assert_eq!(stack.iter().collect::<String>(), "DNZ");
+        assert_eq!(stack.to_string(), "Z N D");
+    }
+
+    #[test]
+    fn test_stack_pop_n() {
+        let mut stack: Stack<char> = "ZND".chars().collect();
+        assert_eq!(stack.pop_n(2), vec!['D', 'N']);
+        assert_eq!(stack.pop_n(2), vec!['Z']);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn test_stack_try_pop_n() {
+        let mut stack: Stack<char> = "ZND".chars().collect();
+        assert_eq!(
+            stack.try_pop_n(4),
+            Err(StackUnderflow {
+                requested: 4,
+                available: 3
+            })
+        );
+        assert_eq!(stack.len(), 3);
+        assert_eq!(stack.try_pop_n(3), Ok(vec!['D', 'N', 'Z']));
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn test_stack_pop_bottom_n() {
+        let mut stack: Stack<char> = "ZNDP".chars().collect();
+        assert_eq!(stack.pop_bottom_n(2), vec!['N', 'Z']);
+        assert_eq!(stack.iter().collect::<String>(), "PD");
+        assert_eq!(stack.pop_bottom_n(3), vec!['P', 'D']);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn test_stack_insert_n_at() {
+        let mut stack: Stack<char> = "ZND".chars().collect();
+        stack.insert_n_at(1, vec!['A', 'B']);
+        assert_eq!(stack.iter().collect::<String>(), "DABNZ");
+        stack.insert_n_at(0, vec!['C']);
+        assert_eq!(stack.top_item(), Some(&'C'));
+        stack.insert_n_at(9, vec!['E']);
+        assert_eq!(stack.iter().collect::<String>(), "CDABNZE");
+    }
+
+    #[test]
+    fn test_stack_peek_n() {
+        let stack: Stack<char> = "ZND".chars().collect();
+        assert_eq!(stack.peek_n(2).unwrap().collect::<String>(), "DN");
+        assert!(stack.peek_n(4).is_err());
+    }
+
+    #[test]
+    fn test_checked_num_primitive() {
+        assert_eq!(3_u64.try_add(&4), Ok(7));
+        assert_eq!(
+            3_u64.try_sub(&4),
+            Err(ArithmeticError::Overflow {
+                lhs: "3".to_string(),
+                operator: Operator::Subtract,
+                rhs: "4".to_string()
+            })
+        );
+        assert_eq!(
+            u64::MAX.try_mul(&2).unwrap_err().to_string(),
+            "18446744073709551615 * 2 overflows"
+        );
+        assert_eq!(1_i64.try_div(&0), Err(ArithmeticError::DivisionByZero));
+        assert_eq!((-7_i64).try_mod_floor(&3), Ok(2));
+        assert!(i64::MIN.try_mod_floor(&-1).is_err());
+        assert!(u8::try_from_u64(256).is_err());
+    }
+
+    #[test]
+    fn test_checked_num_big() {
+        use num::BigInt;
+        let big = BigInt::try_from_u64(u64::MAX).unwrap();
+        let squared = big.try_mul(&big).unwrap();
+        assert_eq!(
+            squared.to_string(),
+            "340282366920938463426481119284349108225"
+        );
+        assert!(squared.try_to_usize().is_err());
+        assert_eq!(
+            BigInt::from(-7).try_mod_floor(&BigInt::from(3)),
+            Ok(BigInt::from(2))
+        );
+    }
+
+    #[test]
+    fn test_rng_is_deterministic() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        assert!((0..100).all(|_| a.next_u64() == b.next_u64()));
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn test_rng_range() {
+        let mut rng = Rng::new(7);
+        for _ in 0..1000 {
+            let n = rng.range(-3_i64..=3);
+            assert!((-3..=3).contains(&n));
+        }
+        assert_eq!(rng.range(5_u8..=5), 5);
+        rng.range(u64::MIN..=u64::MAX);
+    }
+
+    #[test]
+    fn test_rng_chance() {
+        let mut rng = Rng::new(9);
+        assert!((0..100).all(|_| rng.chance(3, 3)));
+        assert!((0..100).all(|_| !rng.chance(0, 3)));
+        assert!(!rng.chance(1, 0));
+    }
+
+    #[test]
+    fn test_rng_mutate() {
+        let mut rng = Rng::new(3);
+        let mutated = (0..100)
+            .map(|_| rng.mutate("move 1 from 2 to 3", "0123456789"))
+            .filter(|m| m != "move 1 from 2 to 3")
+            .count();
+        assert!(mutated > 50);
+    }
+
+    #[test]
+    fn test_interval() {
+        let a = Interval::new(2, 6).unwrap();
+        let b = Interval::new(4, 8).unwrap();
+        assert_eq!(Interval::new(3, 2), None);
+        assert_eq!(a.len(), 5);
+        assert!(a.contains(2) && a.contains(6) && !a.contains(7));
+        assert!(a.overlaps(&b));
+        assert!(!a.contains_interval(&b));
+        assert!(a.contains_interval(&Interval::new(3, 6).unwrap()));
+        assert_eq!(a.intersection(&b), Interval::new(4, 6));
+        assert_eq!(a.intersection(&Interval::point(7)), None);
+        assert_eq!(Interval::new(i64::MIN, i64::MAX).unwrap().len(), u64::MAX);
+        assert_eq!(Interval::<i8>::new(-100, 100).unwrap().len(), 201);
+        assert_eq!(Interval::new(i8::MIN, i8::MAX).unwrap().len(), 256);
+        assert_eq!(Interval::new(i128::MIN, i128::MAX).unwrap().len(), u64::MAX);
+        assert_eq!(Interval::new(u128::MAX - 1, u128::MAX).unwrap().len(), 2);
+        let signed = [(-100, 100), (110, 120)]
+            .into_iter()
+            .map(|(a, b)| Interval::<i8>::new(a, b).unwrap())
+            .collect::<IntervalSet<i8>>();
+        assert_eq!(signed.len(), 212);
+        assert_eq!(RangeInclusive::from(a), 2..=6);
+        assert_eq!(a.to_string(), "2..=6");
+    }
+
+    #[test]
+    fn test_interval_set_merge() {
+        let set = [
+            (5, 7),
+            (1, 2),
+            (3, 3),
+            (10, 12),
+            (11, 11),
+            (u8::MAX, u8::MAX),
+        ]
+        .into_iter()
+        .map(|(a, b)| Interval::new(a, b).unwrap())
+        .collect::<IntervalSet<u8>>();
+        assert_eq!(
+            set.intervals(),
+            [
+                Interval::new(1, 3).unwrap(),
+                Interval::new(5, 7).unwrap(),
+                Interval::new(10, 12).unwrap(),
+                Interval::point(u8::MAX)
+            ]
+        );
+        assert_eq!(set.len(), 10);
+        assert!(set.contains(6) && set.contains(12) && !set.contains(4) && !set.contains(0));
+
+        let mut inserted = set.clone();
+        inserted.insert(Interval::new(4, 4).unwrap());
+        assert_eq!(inserted.intervals()[0], Interval::new(1, 7).unwrap());
+        assert_eq!(inserted.len(), 11);
+    }
+
+    #[test]
+    fn test_interval_set_operations() {
+        let mut rng = Rng::new(5);
+        let random_set = |rng: &mut Rng| {
+            (0..rng.range(0..=5))
+                .map(|_| {
+                    let (a, b) = (rng.range(-20..=20), rng.range(-20..=20));
+                    Interval::new(a.min(b), a.max(b)).unwrap()
+                })
+                .collect::<IntervalSet<i32>>()
+        };
+        for _ in 0..200 {
+            let (a, b) = (random_set(&mut rng), random_set(&mut rng));
+            let union = a.union(&b);
+            let intersection = a.intersection(&b);
+            let difference = a.difference(&b);
+            for x in -25..=25 {
+                assert_eq!(union.contains(x), a.contains(x) || b.contains(x));
+                assert_eq!(intersection.contains(x), a.contains(x) && b.contains(x));
+                assert_eq!(difference.contains(x), a.contains(x) && !b.contains(x));
+            }
+            for set in [&union, &intersection, &difference] {
+                assert_eq!(
+                    set.len(),
+                    (-25..=25).filter(|x| set.contains(*x)).count() as u64
+                );
+                assert!(set
+                    .intervals()
+                    .windows(2)
+                    .all(|w| w[0].end() + 1 < w[1].start()));
+            }
+        }
+    }
 }
@@ -4,35 +4,105 @@
  * Prefer `./helpers.rs` if you want to extract code from your solutions.
  */
 use std::env;
+use std::fmt;
 use std::fs;
 
+pub mod animation;
+pub mod cpu;
+pub mod generate;
 pub mod helpers;
+pub mod ocr;
 
 pub const ANSI_ITALIC: &str = "\x1b[3m";
 pub const ANSI_BOLD: &str = "\x1b[1m";
 pub const ANSI_RESET: &str = "\x1b[0m";
 
+/// Error marking a part that has no implementation yet.
+///
+/// `solve!` reports it as "not solved." rather than as a failure, so stubs can
+/// return `Err(Unsolved.into())` without being confused with a broken input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unsolved;
+
+impl fmt::Display for Unsolved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "not solved.")
+    }
+}
+
+impl std::error::Error for Unsolved {}
+
+/// What a solver produced, as understood by `solve!`.
+#[derive(Debug)]
+pub enum Answer<T> {
+    Solved(T),
+    Unsolved,
+    Failed(anyhow::Error),
+}
+
+/// Return types accepted from `part_one`/`part_two`.
+///
+/// `Option<T>` maps `None` to [`Answer::Unsolved`]. `Result<T, E>` maps errors
+/// to [`Answer::Failed`], except for [`Unsolved`] which keeps its meaning even
+/// when wrapped in context.
+pub trait Solution {
+    type Output: fmt::Display;
+
+    fn into_answer(self) -> Answer<Self::Output>;
+}
+
+impl<T: fmt::Display> Solution for Option<T> {
+    type Output = T;
+
+    fn into_answer(self) -> Answer<T> {
+        match self {
+            Some(result) => Answer::Solved(result),
+            None => Answer::Unsolved,
+        }
+    }
+}
+
+impl<T: fmt::Display, E: Into<anyhow::Error>> Solution for Result<T, E> {
+    type Output = T;
+
+    fn into_answer(self) -> Answer<T> {
+        match self {
+            Ok(result) => Answer::Solved(result),
+            Err(err) => {
+                let err = err.into();
+                if err.chain().any(|cause| cause.is::<Unsolved>()) {
+                    Answer::Unsolved
+                } else {
+                    Answer::Failed(err)
+                }
+            }
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! solve {
     ($part:expr, $solver:ident, $input:expr) => {{
-        use advent_of_code::{ANSI_BOLD, ANSI_ITALIC, ANSI_RESET};
-        use std::fmt::Display;
+        use advent_of_code::{Answer, Solution, ANSI_BOLD, ANSI_ITALIC, ANSI_RESET};
         use std::time::Instant;
 
-        fn print_result<T: Display>(func: impl FnOnce(&str) -> Option<T>, input: &str) {
+        fn print_result<S: Solution>(func: impl FnOnce(&str) -> S, input: &str) {
             let timer = Instant::now();
             let result = func(input);
             let elapsed = timer.elapsed();
-            match result {
-                Some(result) => {
+            match result.into_answer() {
+                Answer::Solved(result) => {
                     println!(
                         "{} {}(elapsed: {:.2?}){}",
                         result, ANSI_ITALIC, elapsed, ANSI_RESET
                     );
                 }
-                None => {
+                Answer::Unsolved => {
                     println!("not solved.")
                 }
+                Answer::Failed(err) => {
+                    println!("failed: {:#}", err)
+                }
             }
         }
 
@@ -122,6 +192,36 @@ mod tests {
             10400.50_f64
         );
     }
+
+    #[test]
+    fn test_option_into_answer() {
+        assert!(matches!(Some(1).into_answer(), Answer::Solved(1)));
+        assert!(matches!(None::<u32>.into_answer(), Answer::Unsolved));
+    }
+
+    #[test]
+    fn test_result_into_answer() {
+        let solved: anyhow::Result<u32> = Ok(1);
+        assert!(matches!(solved.into_answer(), Answer::Solved(1)));
+
+        let unsolved: anyhow::Result<u32> = Err(Unsolved.into());
+        assert!(matches!(unsolved.into_answer(), Answer::Unsolved));
+
+        let wrapped: anyhow::Result<u32> = Err(anyhow::Error::from(Unsolved).context("part two"));
+        assert!(matches!(wrapped.into_answer(), Answer::Unsolved));
+
+        match "x".parse::<u32>().into_answer() {
+            Answer::Failed(err) => assert_eq!(err.to_string(), "invalid digit found in string"),
+            _ => panic!("expected a failed answer"),
+        }
+
+        let failed: anyhow::Result<u32> =
+            Err(anyhow::anyhow!("bad digit")).map_err(|e| e.context("line 3"));
+        match failed.into_answer() {
+            Answer::Failed(err) => assert_eq!(format!("{:#}", err), "line 3: bad digit"),
+            _ => panic!("expected a failed answer"),
+        }
+    }
 }
 
 pub mod aoc_cli {
@@ -0,0 +1,178 @@
+/*
+ * Reading of the block letters some puzzles draw instead of printing an answer.
+ * Letters use the 4x6 font of the CRT and dot-matrix puzzles, with `#` for lit
+ * and `.` for dark pixels and one dark column between letters.
+ * Example: `advent_of_code::ocr::read(&image)?` returns "EHPZPJGL".
+ */
+
+use anyhow::bail;
+use itertools::Itertools;
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+/// Columns from the start of one letter to the start of the next.
+pub const GLYPH_PITCH: usize = GLYPH_WIDTH + 1;
+
+/// Every known letter with its rows concatenated.
+const FONT: [(char, &str); 18] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Y', "#...#....#.#..#...#...#."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+/// The letters the font can draw and read.
+pub fn letters() -> impl Iterator<Item = char> {
+    FONT.iter().map(|(letter, _)| *letter)
+}
+
+fn glyph(letter: char) -> Option<&'static str> {
+    FONT.iter()
+        .find(|(l, _)| *l == letter)
+        .map(|(_, glyph)| *glyph)
+}
+
+/// `text` drawn in the font, six rows per line of text and a dark column
+/// after every letter, or `None` if it has a character other than a known
+/// letter, a space or a newline.
+pub fn render(text: &str) -> Option<String> {
+    let mut rows = Vec::new();
+    for line in text.lines() {
+        let glyphs = line
+            .chars()
+            .map(|c| match c {
+                ' ' => Some(".".repeat(GLYPH_WIDTH * GLYPH_HEIGHT)),
+                c => glyph(c).map(str::to_string),
+            })
+            .collect::<Option<Vec<_>>>()?;
+        for y in 0..GLYPH_HEIGHT {
+            let row = glyphs
+                .iter()
+                .map(|glyph| format!("{}.", &glyph[y * GLYPH_WIDTH..(y + 1) * GLYPH_WIDTH]))
+                .collect::<String>();
+            rows.push(row);
+        }
+    }
+    Some(rows.join("\n"))
+}
+
+/// Reads the letters drawn in `image`, one line of text per six rows.
+/// Blank cells read as spaces and are trimmed from the end of each line.
+pub fn read(image: &str) -> anyhow::Result<String> {
+    let rows = image.lines().map(|l| l.as_bytes()).collect_vec();
+    if rows.is_empty() || rows.len() % GLYPH_HEIGHT != 0 {
+        bail!(
+            "an image of {} rows can't hold letters {GLYPH_HEIGHT} rows high",
+            rows.len()
+        );
+    }
+    let lit = |row: &[u8], x: usize| row.get(x) == Some(&b'#');
+    let mut text = Vec::new();
+    for (line, band) in rows.chunks(GLYPH_HEIGHT).enumerate() {
+        let width = band.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut letters = String::new();
+        for (i, x) in (0..width).step_by(GLYPH_PITCH).enumerate() {
+            if band.iter().any(|row| lit(row, x + GLYPH_WIDTH)) {
+                bail!(
+                    "letter {} on line {} runs into the next one",
+                    i + 1,
+                    line + 1
+                );
+            }
+            let cell = band
+                .iter()
+                .flat_map(|row| (x..x + GLYPH_WIDTH).map(|x| if lit(row, x) { '#' } else { '.' }))
+                .collect::<String>();
+            let letter = match FONT.iter().find(|(_, glyph)| *glyph == cell) {
+                Some((letter, _)) => *letter,
+                None if !cell.contains('#') => ' ',
+                None => bail!(
+                    "unknown letter {} on line {}:\n{}",
+                    i + 1,
+                    line + 1,
+                    cell.as_bytes()
+                        .chunks(GLYPH_WIDTH)
+                        .map(|row| String::from_utf8_lossy(row))
+                        .join("\n")
+                ),
+            };
+            letters.push(letter);
+        }
+        text.push(letters.trim_end().to_string());
+    }
+    Ok(text.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::Rng;
+
+    #[test]
+    fn read_puzzle_answer() {
+        let image = "\
+####.#..#.###..####.###....##..##..#....
+#....#..#.#..#....#.#..#....#.#..#.#....
+###..####.#..#...#..#..#....#.#....#....
+#....#..#.###...#...###.....#.#.##.#....
+#....#..#.#....#....#....#..#.#..#.#....
+####.#..#.#....####.#.....##...###.####.";
+        assert_eq!(read(image).unwrap(), "EHPZPJGL");
+        assert_eq!(render("EHPZPJGL").unwrap(), image);
+    }
+
+    #[test]
+    fn render_round_trip() {
+        let mut rng = Rng::new(1);
+        let alphabet = letters().chain([' ']).collect_vec();
+        for _ in 0..100 {
+            let text = (0..rng.range(1..=3))
+                .map(|_| {
+                    let line = (0..rng.range(0..=9))
+                        .map(|_| *rng.choose(&alphabet))
+                        .collect::<String>();
+                    // Start with a letter so no line trims down to nothing
+                    format!("{}{}", rng.choose(&alphabet[..FONT.len()]), line.trim_end())
+                })
+                .join("\n");
+            let image = render(&text).unwrap();
+            assert_eq!(read(&image).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn font_is_unambiguous() {
+        assert!(FONT.iter().map(|(_, glyph)| glyph).all_unique());
+        assert!(FONT
+            .iter()
+            .all(|(_, glyph)| glyph.len() == GLYPH_WIDTH * GLYPH_HEIGHT));
+    }
+
+    #[test]
+    fn unreadable_images() {
+        assert!(read("").is_err());
+        assert!(read("####\n#...").is_err());
+        let mut image = render("HI").unwrap();
+        // Join the letters with a lit pixel in the gap
+        image.replace_range(4..5, "#");
+        assert!(read(&image).is_err());
+        let smudged = render("O").unwrap().replacen('.', "#", 1);
+        let error = read(&smudged).unwrap_err().to_string();
+        assert!(error.starts_with("unknown letter 1 on line 1:\n###."));
+        assert!(render("a").is_none());
+    }
+}